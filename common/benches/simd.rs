@@ -0,0 +1,48 @@
+//! Compares the vectorized [`BulkOps`] of `Vector` against the scalar fallbacks for vectors the
+//! size of a lattice latent state.
+
+use std::hint::black_box;
+
+use common::{
+    simd::{add_assign_slice_scalar, scale_slice_scalar},
+    vector::Vector,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// The length of the benchmarked vectors, matching the latent space of the lattice system.
+const LEN: usize = 1024;
+
+/// Benchmarks the bulk operations for the given float type.
+macro_rules! bench_bulk_ops {
+    ($c:ident, $t:ty) => {{
+        let mut x = Vector::<$t, LEN>::from_idx(|i| i as $t);
+        let y = Vector::<$t, LEN>::broadcast(0.5);
+
+        let mut group = $c.benchmark_group(concat!("add_assign_", stringify!($t)));
+        group.bench_function("simd", |b| {
+            b.iter(|| x.add_assign_slice(black_box(y.as_ref())))
+        });
+        group.bench_function("scalar", |b| {
+            b.iter(|| add_assign_slice_scalar(x.as_mut(), black_box(y.as_ref())))
+        });
+        group.finish();
+
+        // Scale by one, so that the repeated scaling neither overflows nor underflows.
+        let mut group = $c.benchmark_group(concat!("scale_", stringify!($t)));
+        group.bench_function("simd", |b| b.iter(|| x.scale(black_box(1.))));
+        group.bench_function("scalar", |b| {
+            b.iter(|| scale_slice_scalar(x.as_mut(), black_box(1.)))
+        });
+        group.finish();
+        black_box(x);
+    }};
+}
+
+/// Benchmarks the bulk operations for [`f32`] and [`f64`].
+fn bulk_ops(c: &mut Criterion) {
+    bench_bulk_ops!(c, f32);
+    bench_bulk_ops!(c, f64);
+}
+
+criterion_group!(benches, bulk_ops);
+criterion_main!(benches);
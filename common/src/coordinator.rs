@@ -1,15 +1,487 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use crate::{
     interfaces::{
         DriverInterface, GeneratorInterface, SimulatorInterface, StatePredictionInterface,
     },
-    system::System,
+    system::{DynamicVector, System},
     Float,
 };
-use futures::FutureExt;
+use futures::{future::FusedFuture, FutureExt};
+
+/// How long the experiment loop sleeps between checks of the pause flag while paused.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Sleeps for the [`PAUSE_POLL_INTERVAL`]. With the `tokio` feature, this uses the timer of the
+/// current `tokio` runtime if there is one, rather than the `async-io` reactor thread, so that a
+/// paused experiment on a `tokio` runtime doesn't need a second reactor.
+async fn pause_sleep() {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+        return;
+    }
+    async_std::task::sleep(PAUSE_POLL_INTERVAL).await;
+}
+
+/// Wraps the future of a phase of the control cycle in a `tracing` span named after the phase,
+/// recording the step number, with the `tracing` feature.
+macro_rules! phase {
+    ($name:literal, $step:expr, $future:expr) => {{
+        #[cfg(feature = "tracing")]
+        let future =
+            tracing::Instrument::instrument($future, tracing::info_span!($name, step = $step));
+        #[cfg(not(feature = "tracing"))]
+        let future = {
+            let _ = $step;
+            $future
+        };
+        future
+    }};
+}
+
+/// The configuration of the experiment control cycle run by [`experiment`].
+#[derive(Debug, Clone)]
+pub struct ExperimentConfig<T> {
+    /// The time to evolve the simulator per step, unless the simulator gives a
+    /// [`SimulatorInterface::dt_hint`].
+    pub dt: T,
+    /// The number of simulator steps after which the experiment stops. If [`None`], the
+    /// experiment runs forever.
+    pub max_steps: Option<usize>,
+    /// While set, the control cycle is halted without stepping the simulator, and resumes once the
+    /// flag is cleared.
+    pub paused: Arc<AtomicBool>,
+    /// The largest change of any control parameter applied to the generator per step. If
+    /// [`None`], the control parameters are applied as soon as the driver computes them.
+    pub max_control_rate: Option<T>,
+    /// Whether to display a progress bar over the simulator steps.
+    pub progress: bool,
+    /// Whether the driver computes the controls from the true system state given by
+    /// [`SimulatorInterface::peek_full_state`] rather than from the latent state estimate,
+    /// whenever the simulator exposes it.
+    pub full_state: bool,
+    /// Whether the experiment stops early with [`ExperimentOutcome::Diverged`] once
+    /// [`SimulatorInterface::is_stable`] reports that the simulation has diverged.
+    pub stop_on_divergence: bool,
+    /// The number of simulator steps of every training phase, after which the driver is evaluated
+    /// for [`ExperimentConfig::eval_steps`] steps in evaluation mode, see
+    /// [`DriverInterface::set_eval`]. If [`None`], the driver is never evaluated.
+    pub eval_interval: Option<usize>,
+    /// The number of simulator steps of every evaluation phase.
+    pub eval_steps: usize,
+    /// Whether the state predictor is [reset](StatePredictionInterface::reset) before the control
+    /// cycle starts, i.e. whether the experiment starts a new episode rather than continuing the
+    /// hidden state of the predictor from a previous experiment.
+    pub reset_state_predictor: bool,
+}
+
+impl<T: Float> ExperimentConfig<T> {
+    /// Creates an [`ExperimentConfig`] with the given timestep that runs forever and starts
+    /// unpaused, without a progress bar.
+    pub fn new(dt: T) -> Self {
+        Self {
+            dt,
+            max_steps: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            max_control_rate: None,
+            progress: false,
+            full_state: false,
+            stop_on_divergence: false,
+            eval_interval: None,
+            eval_steps: 0,
+            reset_state_predictor: false,
+        }
+    }
+
+    /// Stops the experiment after the given number of simulator steps.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Uses the given flag to pause and resume the experiment.
+    pub fn paused(mut self, paused: Arc<AtomicBool>) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Ramps the control parameters applied to the generator towards those computed by the driver
+    /// by at most `max_control_rate` per step, to avoid abrupt control jumps.
+    pub fn max_control_rate(mut self, max_control_rate: T) -> Self {
+        self.max_control_rate = Some(max_control_rate);
+        self
+    }
+
+    /// Displays a [`tqdm`] progress bar over the simulator steps, up to
+    /// [`ExperimentConfig::max_steps`] if set.
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Hands the true system state to the driver through
+    /// [`DriverInterface::compute_controls_from_state`], for privileged (teacher) drivers. Drivers
+    /// that can't use it fall back to [`DriverInterface::compute_controls`].
+    pub fn full_state(mut self, full_state: bool) -> Self {
+        self.full_state = full_state;
+        self
+    }
+
+    /// Stops the experiment as soon as the simulation diverges, rather than stepping it on
+    /// producing non-finite values forever. The stability is checked before every step.
+    pub fn stop_on_divergence(mut self, stop_on_divergence: bool) -> Self {
+        self.stop_on_divergence = stop_on_divergence;
+        self
+    }
+
+    /// Alternates training phases of `interval` simulator steps with evaluation phases of
+    /// `eval_steps` steps, whose average dynamics losses are reported in
+    /// [`ExperimentResult::eval_losses`].
+    pub fn eval_phases(mut self, interval: usize, eval_steps: usize) -> Self {
+        self.eval_interval = Some(interval);
+        self.eval_steps = eval_steps;
+        self
+    }
+
+    /// Resets the hidden state of the state predictor before the control cycle starts, so that
+    /// the experiment is an episode of its own.
+    pub fn reset_state_predictor(mut self, reset_state_predictor: bool) -> Self {
+        self.reset_state_predictor = reset_state_predictor;
+        self
+    }
+
+    /// Creates the progress bar over the simulator steps, if enabled.
+    fn progress_bar(&self) -> Option<tqdm::Tqdm<()>> {
+        self.progress.then(|| tqdm::pbar(self.max_steps))
+    }
+}
+
+/// The complete setup of an experiment run, from which it can be reproduced: the configuration of
+/// the system and its dynamics, the seed of any randomness, and the stopping criteria. With the
+/// `serde` feature, it can be saved to and loaded from a TOML file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize, S::SystemConfiguration: serde::Serialize, \
+                     S::DynamicsConfiguration: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, S::SystemConfiguration: serde::Deserialize<'de>, \
+                       S::DynamicsConfiguration: serde::Deserialize<'de>"
+    ))
+)]
+pub struct ExperimentSetup<T: Float, S: System<T>> {
+    /// The configuration of the system.
+    pub system: S::SystemConfiguration,
+    /// The configuration of the dynamics.
+    pub dynamics: S::DynamicsConfiguration,
+    /// The time to evolve the simulator per step.
+    pub dt: T,
+    /// The seed of the random number generators used by the experiment components. It is an
+    /// [`i64`] like the seeds taken by [`JaxKey::key`](crate::python::JaxKey::key).
+    pub seed: i64,
+    /// The number of simulator steps after which the experiment stops. If [`None`], the
+    /// experiment runs forever.
+    pub max_steps: Option<usize>,
+    /// The largest change of any control parameter applied to the generator per step, if any.
+    pub max_control_rate: Option<T>,
+}
+
+impl<T: Float, S: System<T>> ExperimentSetup<T, S> {
+    /// Creates an [`ExperimentSetup`] that runs forever, without limiting the control rate.
+    pub fn new(
+        system: S::SystemConfiguration,
+        dynamics: S::DynamicsConfiguration,
+        dt: T,
+        seed: i64,
+    ) -> Self {
+        Self {
+            system,
+            dynamics,
+            dt,
+            seed,
+            max_steps: None,
+            max_control_rate: None,
+        }
+    }
+
+    /// Stops the experiment after the given number of simulator steps.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Limits the per-step change of the control parameters, see
+    /// [`ExperimentConfig::max_control_rate`].
+    pub fn max_control_rate(mut self, max_control_rate: T) -> Self {
+        self.max_control_rate = Some(max_control_rate);
+        self
+    }
+
+    /// The [`ExperimentConfig`] of the control cycle described by this setup. The experiment
+    /// starts unpaused.
+    pub fn config(&self) -> ExperimentConfig<T> {
+        ExperimentConfig {
+            max_steps: self.max_steps,
+            max_control_rate: self.max_control_rate,
+            ..ExperimentConfig::new(self.dt)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Float, S: System<T>> ExperimentSetup<T, S>
+where
+    Self: serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Saves the setup to a TOML file at the given path.
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), SetupError> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Loads a setup from a TOML file at the given path.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, SetupError> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// The error returned when validating, saving or loading an [`ExperimentSetup`] fails.
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+    /// An intermediate of the control cycle doesn't have the number of parameters given by the
+    /// [`System::sizes`] of the setup, see [`validate_setup`].
+    #[error("Expected the {stage} to have {expected} parameters, got {actual}")]
+    Shape {
+        /// The intermediate of the control cycle with the wrong number of parameters.
+        stage: &'static str,
+        /// The number of parameters given by the system.
+        expected: usize,
+        /// The number of parameters of the intermediate.
+        actual: usize,
+    },
+    /// The setup file couldn't be read or written.
+    #[cfg(feature = "serde")]
+    #[error("Failed to access the setup file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The setup couldn't be serialized to TOML.
+    #[cfg(feature = "serde")]
+    #[error("Failed to serialize the setup: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    /// The setup file isn't a valid TOML description of the setup.
+    #[cfg(feature = "serde")]
+    #[error("Failed to parse the setup: {0}")]
+    Deserialize(#[from] toml::de::Error),
+}
+
+/// Limits the per-step change of the control parameters applied to the generator.
+struct SlewLimiter<T, P> {
+    /// The largest change of any control parameter per step, if any.
+    max_delta: Option<T>,
+    /// The control parameters last applied to the generator.
+    applied: Option<P>,
+    /// The control parameters to ramp towards, if they haven't been reached yet.
+    target: Option<P>,
+}
+
+impl<T: Float, P: DynamicVector<T> + Clone> SlewLimiter<T, P> {
+    /// Creates a [`SlewLimiter`] that hasn't applied any control parameters yet.
+    fn new(max_delta: Option<T>) -> Self {
+        Self {
+            max_delta,
+            applied: None,
+            target: None,
+        }
+    }
+
+    /// Sets the control parameters to ramp towards.
+    fn set_target(&mut self, target: P) {
+        self.target = Some(target);
+    }
+
+    /// Moves the applied control parameters a step towards the target if the simulator `stepped`,
+    /// returning them if they changed. The first control parameters, and any without a rate
+    /// limit, are applied as is, since there is nothing to ramp from or to.
+    fn step(&mut self, stepped: bool) -> Option<P> {
+        self.target.as_ref()?;
+        let (Some(applied), Some(max_delta)) = (&mut self.applied, self.max_delta) else {
+            let target = self.target.take()?;
+            self.applied = Some(target.clone());
+            return Some(target);
+        };
+        if !stepped {
+            return None;
+        }
+
+        let target = self.target.take()?;
+
+        let mut reached = true;
+        applied
+            .get_rope_mut()
+            .iter_mut()
+            .zip(target.get_rope().iter())
+            .for_each(|(x, &target)| {
+                let delta = target - *x;
+                if delta.abs() <= max_delta {
+                    *x = target;
+                } else {
+                    *x = *x + max_delta.copysign(delta);
+                    reached = false;
+                }
+            });
+        if !reached {
+            self.target = Some(target);
+        }
+
+        Some(applied.clone())
+    }
+}
+
+/// Alternates the training and evaluation phases of the driver, and keeps track of the dynamics
+/// losses of the evaluation phases.
+struct EvalPhases<T> {
+    /// The number of steps of every training phase.
+    interval: usize,
+    /// The number of steps of every evaluation phase.
+    steps: usize,
+    /// The sum of the dynamics losses of the current evaluation phase so far.
+    loss_sum: T,
+    /// The average dynamics losses of the completed evaluation phases.
+    losses: Vec<T>,
+}
+
+impl<T: Float> EvalPhases<T> {
+    /// Creates the [`EvalPhases`] of the given configuration, if there are any evaluation steps.
+    fn new(config: &ExperimentConfig<T>) -> Option<Self> {
+        let interval = config.eval_interval?;
+        (config.eval_steps > 0).then(|| Self {
+            interval,
+            steps: config.eval_steps,
+            loss_sum: T::zero(),
+            losses: Vec::new(),
+        })
+    }
+
+    /// Whether the step with the given (zero-based) index is part of an evaluation phase.
+    fn is_eval(&self, step: usize) -> bool {
+        step % (self.interval + self.steps) >= self.interval
+    }
+
+    /// Accounts for the dynamics loss after the step with the given index, and returns whether
+    /// the driver should be switched into or out of evaluation mode for the next step.
+    fn step(&mut self, step: usize, dynamics_loss: T) -> Option<bool> {
+        if !self.is_eval(step) {
+            return self.is_eval(step + 1).then_some(true);
+        }
+
+        self.loss_sum = self.loss_sum + dynamics_loss;
+        if self.is_eval(step + 1) {
+            return None;
+        }
+        self.losses
+            .push(self.loss_sum / T::from(self.steps).unwrap());
+        self.loss_sum = T::zero();
+        Some(false)
+    }
+}
+
+/// A query of the control parameters to the driver.
+struct Query<T: Float, S: System<T>> {
+    /// The latent state estimate.
+    latent_state: S::LatentState,
+    /// The true system state, if the driver is given it.
+    full_state: Option<S::SystemState>,
+    /// The dynamics loss of the simulator.
+    dynamics_loss: T,
+}
+
+/// What the [`experiment`] control cycle does on a tick, as decided by a [`ControlScheduler`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ControlTick {
+    /// Steps the simulator. A control computation in progress keeps running concurrently, and
+    /// if it finishes first, its controls are applied and the step is retried on the next tick.
+    Step,
+    /// Starts a control computation from the latest state estimate, unless one is already in
+    /// progress, and then steps the simulator as with [`ControlTick::Step`].
+    Control,
+    /// Waits for the control computation in progress to finish and applies its controls,
+    /// without stepping the simulator. Does nothing if no computation is in progress.
+    Wait,
+}
+
+/// Decides the interleaving of the simulator steps and the control computations of the driver
+/// in the [`experiment`] control cycle, see [`experiment_with_scheduler`].
+pub trait ControlScheduler {
+    /// Decides what to do on the next tick, given the number of simulator steps taken so far and
+    /// whether a control computation is in progress.
+    fn tick(&mut self, step: usize, control_in_progress: bool) -> ControlTick;
+}
+
+/// The [`ControlScheduler`] of [`experiment`], which starts a new control computation as soon as
+/// the previous one finished, and otherwise races the simulator against it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DefaultScheduler;
+
+impl ControlScheduler for DefaultScheduler {
+    fn tick(&mut self, _step: usize, _control_in_progress: bool) -> ControlTick {
+        ControlTick::Control
+    }
+}
+
+/// How an [`experiment`] came to an end.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExperimentOutcome {
+    /// The experiment took all of its [`ExperimentConfig::max_steps`] simulator steps.
+    Finished,
+    /// The simulation diverged, and the experiment was stopped early as requested by
+    /// [`ExperimentConfig::stop_on_divergence`].
+    Diverged,
+}
+
+/// The outcome of a finished [`experiment`].
+pub struct ExperimentResult<T: Float, S: System<T>> {
+    /// How the experiment came to an end.
+    pub outcome: ExperimentOutcome,
+    /// The time of the system state when the experiment finished.
+    pub time: T,
+    /// The number of simulator steps taken.
+    pub steps: usize,
+    /// The dynamics loss of the simulator when the experiment finished.
+    pub dynamics_loss: T,
+    /// The last latent state estimate, if it was not already handed to the driver.
+    pub latent_state: Option<S::LatentState>,
+    /// The average dynamics loss of every completed evaluation phase, in order, see
+    /// [`ExperimentConfig::eval_phases`].
+    pub eval_losses: Vec<T>,
+}
 
 /// Given a system type, and some [`DriverInterface`], [`GeneratorInterface`],
-/// [`SimulatorInterface`], and [`StatePredictionInterface`] implementors (along with a timestep),
-/// the experiment control cycle is run.
+/// [`SimulatorInterface`], and [`StatePredictionInterface`] implementors (along with an
+/// [`ExperimentConfig`]), the experiment control cycle is run.
+///
+/// The experiment finishes once [`ExperimentConfig::max_steps`] simulator steps have been taken,
+/// and reports the final metrics as an [`ExperimentResult`]. While [`ExperimentConfig::paused`]
+/// is set, the control cycle is halted without stepping the simulator. With
+/// [`ExperimentConfig::stop_on_divergence`], it finishes early once the simulation diverges.
+/// With [`ExperimentConfig::eval_interval`], the driver is periodically evaluated in evaluation
+/// mode. With [`ExperimentConfig::reset_state_predictor`], the state predictor is
+/// [reset](StatePredictionInterface::reset) before the control cycle starts.
+///
+/// The control cycle only relies on executor-independent primitives (the combinators of
+/// [`futures`] and the timers of `async-io` while paused), so it can be run on any executor, e.g.
+/// `futures::executor::block_on`, `smol`, `async-std` or `tokio`, including a single-threaded
+/// one. With the `tokio` feature, the pause timer of a `tokio` runtime is used when there is one.
+///
+/// A new control computation is started as soon as the previous one finished, and the simulator
+/// is stepped concurrently, see [`DefaultScheduler`]. Use [`experiment_with_scheduler`] for
+/// another interleaving.
 pub async fn experiment<
     T: Float,
     S: System<T>,
@@ -17,47 +489,1304 @@ pub async fn experiment<
     G: GeneratorInterface<T, S>,
     SIM: SimulatorInterface<T, S>,
     SP: StatePredictionInterface<T, S>,
+>(
+    system: &S,
+    driver: D,
+    generator: G,
+    simulator: SIM,
+    state_predictor: SP,
+    config: ExperimentConfig<T>,
+) -> ExperimentResult<T, S> {
+    experiment_with_scheduler(
+        system,
+        driver,
+        generator,
+        simulator,
+        state_predictor,
+        config,
+        DefaultScheduler,
+    )
+    .await
+}
+
+/// Runs the [`experiment`] control cycle with the interleaving of the simulator steps and the
+/// control computations decided by the given [`ControlScheduler`] on every tick.
+pub async fn experiment_with_scheduler<
+    T: Float,
+    S: System<T>,
+    D: DriverInterface<T, S>,
+    G: GeneratorInterface<T, S>,
+    SIM: SimulatorInterface<T, S>,
+    SP: StatePredictionInterface<T, S>,
 >(
     system: &S,
     driver: D,
     mut generator: G,
     mut simulator: SIM,
     mut state_predictor: SP,
-    dt: T,
+    config: ExperimentConfig<T>,
+    mut scheduler: impl ControlScheduler,
     // TODO: Add some customizable target dynamics into this experiment code.
     // Maybe by means of some given target dynamics loss function?
-) {
+) -> ExperimentResult<T, S> {
+    let mut progress_bar = config.progress_bar();
+    let mut eval_phases = EvalPhases::new(&config);
+    let ExperimentConfig {
+        dt,
+        max_steps,
+        paused,
+        max_control_rate,
+        full_state,
+        stop_on_divergence,
+        reset_state_predictor,
+        ..
+    } = config;
+    let mut slew_limiter = SlewLimiter::new(max_control_rate);
+
     let mut current_query = None;
     let mut in_progress = None;
+    let driver = &driver;
     let future_in_progress =
-        |query, dynamics_loss| Box::pin(driver.compute_controls(query, dynamics_loss).fuse());
+        |query: Query<T, S>, step: usize| -> Pin<Box<dyn FusedFuture<Output = _>>> {
+            match query.full_state {
+                Some(state) => Box::pin(
+                    phase!("compute_controls", step, async move {
+                        match driver
+                            .compute_controls_from_state(&state, query.dynamics_loss)
+                            .await
+                        {
+                            Some(controls) => controls,
+                            None => {
+                                driver
+                                    .compute_controls(query.latent_state, query.dynamics_loss)
+                                    .await
+                            }
+                        }
+                    })
+                    .fuse(),
+                ),
+                None => Box::pin(
+                    phase!(
+                        "compute_controls",
+                        step,
+                        driver.compute_controls(query.latent_state, query.dynamics_loss)
+                    )
+                    .fuse(),
+                ),
+            }
+        };
 
-    let mut i = 0;
-    loop {
-        i += 1;
-        if i % 100 == 0 {
-            println!("{i}");
+    let mut steps = 0;
+    let mut outcome = ExperimentOutcome::Finished;
+    if reset_state_predictor {
+        state_predictor.reset();
+    }
+    if eval_phases.as_ref().is_some_and(|eval| eval.is_eval(0)) {
+        driver.set_eval(true);
+    }
+    while max_steps.is_none_or(|max_steps| steps < max_steps) {
+        if paused.load(Ordering::Relaxed) {
+            pause_sleep().await;
+            continue;
+        }
+        if stop_on_divergence && !simulator.is_stable().await {
+            outcome = ExperimentOutcome::Diverged;
+            break;
         }
 
-        let observations = simulator.get_observations().await;
+        let observations = phase!("get_observations", steps, simulator.get_observations()).await;
 
-        let current_state_estimate = state_predictor.predict_state(&observations).await;
-        current_query.replace((current_state_estimate, simulator.get_dynamics_loss().await));
+        let current_state_estimate = phase!(
+            "predict_state",
+            steps,
+            state_predictor.predict_state(&observations)
+        )
+        .await;
+        current_query.replace(Query {
+            latent_state: current_state_estimate,
+            full_state: full_state.then(|| simulator.peek_full_state()).flatten(),
+            dynamics_loss: simulator.get_dynamics_loss().await,
+        });
 
-        if in_progress.is_none() {
-            if let Some((current_query, dynamics_loss)) = current_query.take() {
-                in_progress.replace(future_in_progress(current_query, dynamics_loss));
+        let tick = scheduler.tick(steps, in_progress.is_some());
+        if tick == ControlTick::Control && in_progress.is_none() {
+            if let Some(current_query) = current_query.take() {
+                in_progress.replace(future_in_progress(current_query, steps));
             }
         }
 
-        if let Some(mut in_progress_future) = in_progress.take() {
+        let previous_steps = steps;
+        if tick == ControlTick::Wait {
+            if let Some(in_progress_future) = in_progress.take() {
+                slew_limiter.set_target(in_progress_future.await);
+            }
+        } else {
+            generator.set_observation_context(&observations);
             let signal = generator.control_signal(simulator.get_time());
-            futures::select! {
-                controls = in_progress_future => generator.set_parameters(controls, simulator.get_time()).await,
-                _ = simulator.update(system, dt, &signal).fuse() => {
-                    in_progress.replace(in_progress_future);
+            let step_dt = simulator.dt_hint().unwrap_or(dt);
+            let stepped = match in_progress.take() {
+                Some(mut in_progress_future) => futures::select! {
+                    controls = in_progress_future => {
+                        slew_limiter.set_target(controls);
+                        false
+                    },
+                    _ = phase!("update", steps, simulator.update(system, step_dt, &signal)).fuse() => {
+                        in_progress.replace(in_progress_future);
+                        true
+                    },
                 },
+                None => {
+                    phase!("update", steps, simulator.update(system, step_dt, &signal)).await;
+                    true
+                }
             };
+            if stepped {
+                steps += 1;
+                if let Some(progress_bar) = &mut progress_bar {
+                    // Failing to draw the progress bar shouldn't stop the experiment.
+                    let _ = progress_bar.update(1);
+                }
+            }
+        }
+
+        if let Some(eval_phases) = eval_phases.as_mut().filter(|_| steps > previous_steps) {
+            let dynamics_loss = simulator.get_dynamics_loss().await;
+            if let Some(eval) = eval_phases.step(previous_steps, dynamics_loss) {
+                driver.set_eval(eval);
+            }
+        }
+
+        if let Some(controls) = slew_limiter.step(steps > previous_steps) {
+            generator
+                .set_parameters(controls, simulator.get_time())
+                .await;
+        }
+    }
+
+    if let Some(progress_bar) = &mut progress_bar {
+        let _ = progress_bar.close();
+    }
+    // Leave the driver out of evaluation mode if the experiment stopped during an evaluation
+    // phase, which isn't reported since it is incomplete.
+    if eval_phases.as_ref().is_some_and(|eval| eval.is_eval(steps)) {
+        driver.set_eval(false);
+    }
+
+    ExperimentResult {
+        outcome,
+        time: simulator.get_time(),
+        steps,
+        dynamics_loss: simulator.get_dynamics_loss().await,
+        latent_state: current_query.map(|query| query.latent_state),
+        eval_losses: eval_phases.map(|eval| eval.losses).unwrap_or_default(),
+    }
+}
+
+/// Runs the [`experiment`] control cycle described by the given [`ExperimentSetup`], e.g. one
+/// loaded with `ExperimentSetup::load`. The experiment components are expected to have been
+/// constructed from the setup.
+pub async fn experiment_from_setup<
+    T: Float,
+    S: System<T>,
+    D: DriverInterface<T, S>,
+    G: GeneratorInterface<T, S>,
+    SIM: SimulatorInterface<T, S>,
+    SP: StatePredictionInterface<T, S>,
+>(
+    system: &S,
+    driver: D,
+    generator: G,
+    simulator: SIM,
+    state_predictor: SP,
+    setup: &ExperimentSetup<T, S>,
+) -> ExperimentResult<T, S> {
+    experiment(
+        system,
+        driver,
+        generator,
+        simulator,
+        state_predictor,
+        setup.config(),
+    )
+    .await
+}
+
+/// Runs the [`experiment`] described by the given [`ExperimentSetup`] once for every seed, e.g.
+/// to aggregate its metrics over seeds, and returns the results in the order of the seeds. The
+/// components of every run are constructed by `build` from its seed, e.g. seeding a
+/// [`JaxKey`](crate::python::JaxKey) with it, so that every run is reproducible on its own. The
+/// [`ExperimentSetup::seed`] is ignored, as every run uses its own seed from `seeds` instead. The
+/// runs are driven concurrently on the current executor.
+pub async fn sweep<
+    T: Float,
+    S: System<T>,
+    D: DriverInterface<T, S>,
+    G: GeneratorInterface<T, S>,
+    SIM: SimulatorInterface<T, S>,
+    SP: StatePredictionInterface<T, S>,
+>(
+    system: &S,
+    setup: &ExperimentSetup<T, S>,
+    seeds: &[i64],
+    mut build: impl FnMut(i64) -> (D, G, SIM, SP),
+) -> Vec<ExperimentResult<T, S>> {
+    futures::future::join_all(seeds.iter().map(|&seed| {
+        let (driver, generator, simulator, state_predictor) = build(seed);
+        experiment_from_setup(system, driver, generator, simulator, state_predictor, setup)
+    }))
+    .await
+}
+
+/// Replays a previously recorded trace of control signals open loop, e.g. the controls logged by a
+/// [`TrajectoryRecorder`](crate::recorder::TrajectoryRecorder), bypassing the driver and state
+/// predictor. The simulator is stepped once per control signal with the timestep of the
+/// [`ExperimentSetup`], until the trace or [`ExperimentSetup::max_steps`] runs out. Replaying a
+/// trace on a freshly constructed simulator re-derives the recorded states, which validates that
+/// the simulation is deterministic.
+pub async fn replay<T: Float, S: System<T>, SIM: SimulatorInterface<T, S>>(
+    system: &S,
+    mut simulator: SIM,
+    setup: &ExperimentSetup<T, S>,
+    control_trace: impl IntoIterator<Item = S::ControlSignal>,
+) -> ExperimentResult<T, S> {
+    let mut steps = 0;
+    let max_steps = setup.max_steps.unwrap_or(usize::MAX);
+    for control_signal in control_trace.into_iter().take(max_steps) {
+        let dt = simulator.dt_hint().unwrap_or(setup.dt);
+        simulator.update(system, dt, &control_signal).await;
+        steps += 1;
+    }
+
+    ExperimentResult {
+        outcome: ExperimentOutcome::Finished,
+        time: simulator.get_time(),
+        steps,
+        dynamics_loss: simulator.get_dynamics_loss().await,
+        latent_state: None,
+        eval_losses: Vec::new(),
+    }
+}
+
+/// Checks that the given intermediate of the control cycle has the expected number of
+/// parameters.
+fn check_size<T: Float>(
+    stage: &'static str,
+    expected: usize,
+    value: &impl DynamicVector<T>,
+) -> Result<(), SetupError> {
+    let actual = value.get_rope().len();
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(SetupError::Shape {
+            stage,
+            expected,
+            actual,
+        })
+    }
+}
+
+/// Performs a dry run of a single [`experiment`] control cycle, i.e. `get_observations`,
+/// `predict_state`, `compute_controls`, `set_parameters`, `control_signal` and `update`, and
+/// checks the number of parameters of every intermediate against the [`System::sizes`] of the
+/// setup, so that inconsistent components fail fast rather than deep into a long run. The full
+/// state is also checked before and after the update if the simulator exposes it, and the
+/// observations are checked again after the update.
+///
+/// The dry run is performed on throwaway components built by `build` from the seed of the setup,
+/// which are dropped afterwards. This way the dry run doesn't commit any state to the components
+/// of the actual run.
+pub async fn validate_setup<
+    T: Float,
+    S: System<T>,
+    D: DriverInterface<T, S>,
+    G: GeneratorInterface<T, S>,
+    SIM: SimulatorInterface<T, S>,
+    SP: StatePredictionInterface<T, S>,
+>(
+    system: &S,
+    setup: &ExperimentSetup<T, S>,
+    build: impl FnOnce(i64) -> (D, G, SIM, SP),
+) -> Result<(), SetupError> {
+    let sizes = S::sizes(&setup.system);
+    let (driver, mut generator, mut simulator, mut state_predictor) = build(setup.seed);
+
+    let observations = simulator.get_observations().await;
+    for observation in &observations {
+        check_size("observation", sizes.observable_state, observation)?;
+    }
+    if let Some(state) = simulator.peek_full_state() {
+        check_size("system state", sizes.system_state, &state)?;
+    }
+
+    let latent_state = state_predictor.predict_state(&observations).await;
+    check_size("latent state", sizes.latent_state, &latent_state)?;
+
+    let dynamics_loss = simulator.get_dynamics_loss().await;
+    let controls = driver.compute_controls(latent_state, dynamics_loss).await;
+    check_size("control parameters", sizes.control_params, &controls)?;
+
+    let time = simulator.get_time();
+    generator.set_parameters(controls, time).await;
+    generator.set_observation_context(&observations);
+    let signal = generator.control_signal(time);
+    check_size("control signal", sizes.control_signal, &signal)?;
+
+    let dt = simulator.dt_hint().unwrap_or(setup.dt);
+    simulator.update(system, dt, &signal).await;
+    if let Some(state) = simulator.peek_full_state() {
+        check_size("updated system state", sizes.system_state, &state)?;
+    }
+    for observation in &simulator.get_observations().await {
+        check_size("updated observation", sizes.observable_state, observation)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use async_std::task::sleep;
+    use futures::{executor::block_on, FutureExt};
+
+    use super::{
+        experiment, experiment_from_setup, experiment_with_scheduler, validate_setup,
+        ControlScheduler, ControlTick, ExperimentConfig, ExperimentOutcome, ExperimentSetup,
+        SetupError, SlewLimiter,
+    };
+    use crate::{
+        interfaces::{
+            DriverInterface, GeneratorInterface, SimulatorInterface, StatePredictionInterface,
+        },
+        system::{ObservationExt, System},
+    };
+
+    /// A minimal system used to exercise the coordinator.
+    struct CountingSystem;
+
+    impl System<f64> for CountingSystem {
+        const CONTROL_SIGNAL_SIZE: usize = 0;
+        const CONTROL_PARAMS_SIZE: usize = 0;
+        const LATENT_STATE_SIZE: usize = 1;
+        const SYSTEM_STATE_SIZE: usize = 1;
+        const OBSERVABLE_STATE_SIZE: usize = 1;
+
+        type SystemConfiguration = ();
+        type DynamicsConfiguration = ();
+
+        type SystemState = f64;
+        type LatentState = f64;
+        type ControlParams = Vec<f64>;
+        type ControlSignal = ();
+        type SystemObservation = f64;
+    }
+
+    /// A simulator that only keeps track of the time and the number of updates.
+    struct CountingSimulator {
+        /// The number of updates performed so far.
+        steps: Arc<AtomicUsize>,
+        /// The current time.
+        time: f64,
+        /// The preferred timestep, if any.
+        dt_hint: Option<f64>,
+        /// The time from which the simulation reports itself as diverged, if any.
+        diverges_at: Option<f64>,
+        /// The rate at which the dynamics loss grows with the time.
+        loss_rate: f64,
+    }
+
+    impl SimulatorInterface<f64, CountingSystem> for CountingSimulator {
+        async fn get_observations(&self) -> Vec<f64> {
+            vec![self.time]
+        }
+
+        async fn update(&mut self, _system: &CountingSystem, dt: f64, _control_signal: &()) {
+            async_std::task::yield_now().await;
+            self.time += dt;
+            self.steps.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn get_dynamics_loss(&self) -> f64 {
+            self.loss_rate * self.time
         }
+
+        fn get_time(&self) -> f64 {
+            self.time
+        }
+
+        fn dt_hint(&self) -> Option<f64> {
+            self.dt_hint
+        }
+
+        async fn is_stable(&self) -> bool {
+            self.diverges_at.is_none_or(|time| self.time < time)
+        }
+
+        fn peek_full_state(&self) -> Option<f64> {
+            Some(self.time)
+        }
+    }
+
+    /// A driver that never produces any controls.
+    struct NullDriver;
+
+    impl DriverInterface<f64, CountingSystem> for NullDriver {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) -> Vec<f64> {
+            futures::future::pending().await
+        }
+    }
+
+    /// A driver that never produces any controls, and logs the simulator step at which it is
+    /// switched into or out of evaluation mode.
+    struct EvalDriver {
+        /// The step counter of the simulator.
+        steps: Arc<AtomicUsize>,
+        /// The logged steps and evaluation modes.
+        log: Arc<Mutex<Vec<(usize, bool)>>>,
+    }
+
+    impl DriverInterface<f64, CountingSystem> for EvalDriver {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) -> Vec<f64> {
+            futures::future::pending().await
+        }
+
+        fn set_eval(&self, eval: bool) {
+            let step = self.steps.load(Ordering::SeqCst);
+            self.log.lock().unwrap().push((step, eval));
+        }
+    }
+
+    /// A generator that produces the empty control signal.
+    struct NullGenerator;
+
+    impl GeneratorInterface<f64, CountingSystem> for NullGenerator {
+        async fn set_parameters(&mut self, _controls: Vec<f64>, _time: f64) {}
+
+        fn control_signal(&mut self, _time: f64) {}
+    }
+
+    /// A driver that immediately produces the queued controls, one per query, and then never
+    /// produces any more.
+    struct QueuedDriver(Mutex<VecDeque<Vec<f64>>>);
+
+    impl DriverInterface<f64, CountingSystem> for QueuedDriver {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) -> Vec<f64> {
+            let controls = self.0.lock().unwrap().pop_front();
+            match controls {
+                Some(controls) => controls,
+                None => futures::future::pending().await,
+            }
+        }
+    }
+
+    /// A privileged driver that immediately produces the true system state it is given as the
+    /// controls for the first query, and then never produces any more. It never computes controls
+    /// from a latent state estimate.
+    struct StateDriver(AtomicBool);
+
+    impl DriverInterface<f64, CountingSystem> for StateDriver {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) -> Vec<f64> {
+            panic!("Expected the driver to be given the full state")
+        }
+
+        async fn compute_controls_from_state(
+            &self,
+            state: &f64,
+            _dynamics_loss: f64,
+        ) -> Option<Vec<f64>> {
+            if self.0.swap(true, Ordering::SeqCst) {
+                futures::future::pending().await
+            }
+            Some(vec![*state])
+        }
+    }
+
+    /// A driver that immediately produces empty controls, and logs the simulator step at which
+    /// every computation is started.
+    struct StepLoggingDriver {
+        /// The step counter of the simulator.
+        steps: Arc<AtomicUsize>,
+        /// The logged steps.
+        log: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl DriverInterface<f64, CountingSystem> for StepLoggingDriver {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) -> Vec<f64> {
+            let step = self.steps.load(Ordering::SeqCst);
+            self.log.lock().unwrap().push(step);
+            Vec::new()
+        }
+    }
+
+    /// A [`ControlScheduler`] starting a control computation every `period` simulator steps,
+    /// waiting for the previous one to finish if needed.
+    struct FixedRateScheduler {
+        /// The number of simulator steps between the starts of the control computations.
+        period: usize,
+        /// The step from which the next control computation is due.
+        next: usize,
+    }
+
+    impl ControlScheduler for FixedRateScheduler {
+        fn tick(&mut self, step: usize, control_in_progress: bool) -> ControlTick {
+            if step < self.next {
+                ControlTick::Step
+            } else if control_in_progress {
+                ControlTick::Wait
+            } else {
+                self.next = step + self.period;
+                ControlTick::Control
+            }
+        }
+    }
+
+    /// A generator that records every set of control parameters it is given.
+    struct RecordingGenerator(Arc<Mutex<Vec<Vec<f64>>>>);
+
+    impl GeneratorInterface<f64, CountingSystem> for RecordingGenerator {
+        async fn set_parameters(&mut self, controls: Vec<f64>, _time: f64) {
+            self.0.lock().unwrap().push(controls);
+        }
+
+        fn control_signal(&mut self, _time: f64) {}
+    }
+
+    /// A generator that logs the time of the latest observation it was given as context whenever
+    /// a control signal is requested.
+    struct ContextGenerator {
+        /// The time of the latest observation given as context.
+        context: Option<f64>,
+        /// The logged observation times.
+        log: Arc<Mutex<Vec<Option<f64>>>>,
+    }
+
+    impl GeneratorInterface<f64, CountingSystem> for ContextGenerator {
+        async fn set_parameters(&mut self, _controls: Vec<f64>, _time: f64) {}
+
+        fn control_signal(&mut self, _time: f64) {
+            self.log.lock().unwrap().push(self.context);
+        }
+
+        fn set_observation_context(&mut self, observations: &[f64]) {
+            self.context = observations.last().map(ObservationExt::time);
+        }
+    }
+
+    /// A state predictor with a hidden state counting its predictions since the last reset, which
+    /// it returns as the latent state.
+    struct CountingPredictor(usize);
+
+    impl StatePredictionInterface<f64, CountingSystem> for CountingPredictor {
+        async fn predict_state(&mut self, _observation: &[f64]) -> f64 {
+            self.0 += 1;
+            self.0 as f64
+        }
+
+        fn reset(&mut self) {
+            self.0 = 0;
+        }
+    }
+
+    /// A state predictor that returns the last observation.
+    struct LastObservationPredictor;
+
+    impl StatePredictionInterface<f64, CountingSystem> for LastObservationPredictor {
+        async fn predict_state(&mut self, observation: &[f64]) -> f64 {
+            *observation.last().unwrap()
+        }
+    }
+
+    /// Builds a [`CountingSimulator`] along with a handle to its step counter.
+    fn counting_simulator() -> (CountingSimulator, Arc<AtomicUsize>) {
+        let steps = Arc::new(AtomicUsize::new(0));
+        let simulator = CountingSimulator {
+            steps: steps.clone(),
+            time: 0.,
+            dt_hint: None,
+            diverges_at: None,
+            loss_rate: 0.,
+        };
+
+        (simulator, steps)
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let (simulator, steps) = counting_simulator();
+        let paused = Arc::new(AtomicBool::new(true));
+
+        let run = experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(1e-3).paused(paused.clone()),
+        );
+        let check = async {
+            sleep(Duration::from_millis(50)).await;
+            assert_eq!(
+                steps.load(Ordering::SeqCst),
+                0,
+                "Expected no steps while paused"
+            );
+
+            paused.store(false, Ordering::SeqCst);
+            while steps.load(Ordering::SeqCst) == 0 {
+                sleep(Duration::from_millis(1)).await;
+            }
+
+            paused.store(true, Ordering::SeqCst);
+            sleep(Duration::from_millis(20)).await;
+            let paused_steps = steps.load(Ordering::SeqCst);
+            sleep(Duration::from_millis(50)).await;
+            assert_eq!(
+                steps.load(Ordering::SeqCst),
+                paused_steps,
+                "Expected no steps after pausing again"
+            );
+        };
+
+        block_on(async {
+            futures::select! {
+                _ = Box::pin(run.fuse()) => unreachable!("The experiment never finishes"),
+                _ = Box::pin(check.fuse()) => {},
+            }
+        });
+    }
+
+    #[test]
+    fn test_fixed_rate_scheduler() {
+        let (simulator, steps) = counting_simulator();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let driver = StepLoggingDriver {
+            steps,
+            log: log.clone(),
+        };
+
+        let result = block_on(experiment_with_scheduler(
+            &CountingSystem,
+            driver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(12),
+            FixedRateScheduler { period: 4, next: 0 },
+        ));
+
+        assert_eq!(result.steps, 12, "Expected the configured number of steps");
+        assert_eq!(
+            *log.lock().unwrap(),
+            [0, 4, 8],
+            "Expected one control computation every 4 steps"
+        );
+    }
+
+    #[test]
+    fn test_state_predictor_reset() {
+        let (simulator, _) = counting_simulator();
+
+        // The hidden state left over from a previous episode.
+        let result = block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            CountingPredictor(100),
+            ExperimentConfig::new(0.5)
+                .max_steps(5)
+                .reset_state_predictor(true),
+        ));
+
+        assert_eq!(
+            result.latent_state,
+            Some(5.),
+            "Expected the predictions to be counted from the reset"
+        );
+
+        let (simulator, _) = counting_simulator();
+        let result = block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            CountingPredictor(100),
+            ExperimentConfig::new(0.5).max_steps(5),
+        ));
+
+        assert_eq!(
+            result.latent_state,
+            Some(105.),
+            "Expected the hidden state to be kept unless the reset is opted into"
+        );
+    }
+
+    #[test]
+    fn test_runs_on_other_executors() {
+        let (simulator, steps) = counting_simulator();
+        let config = ExperimentConfig::new(0.5).max_steps(5);
+        config.paused.store(true, Ordering::SeqCst);
+
+        // Resume from another thread, so the experiment has to wait on the pause timer.
+        let paused = config.paused.clone();
+        let resume = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            paused.store(false, Ordering::SeqCst);
+        });
+        let result = async_std::task::block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            config,
+        ));
+        resume.join().unwrap();
+
+        assert_eq!(result.steps, 5, "Expected the configured number of steps");
+        assert_eq!(steps.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_runs_on_tokio_current_thread() {
+        let (simulator, steps) = counting_simulator();
+        let config = ExperimentConfig::new(0.5).max_steps(5);
+        config.paused.store(true, Ordering::SeqCst);
+        let driver = QueuedDriver(Mutex::new(VecDeque::from([vec![1.], vec![2.]])));
+
+        let paused = config.paused.clone();
+        let resume = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            paused.store(false, Ordering::SeqCst);
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let result = runtime.block_on(experiment(
+            &CountingSystem,
+            driver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            config,
+        ));
+        resume.join().unwrap();
+
+        assert_eq!(result.steps, 5, "Expected the configured number of steps");
+        assert_eq!(steps.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let (simulator, steps) = counting_simulator();
+
+        let result = block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(10),
+        ));
+
+        assert_eq!(result.outcome, ExperimentOutcome::Finished);
+        assert_eq!(result.steps, 10, "Expected the configured number of steps");
+        assert_eq!(
+            steps.load(Ordering::SeqCst),
+            10,
+            "Expected 10 simulator updates"
+        );
+        assert_eq!(result.time, 5., "Expected the final simulator time");
+        assert_eq!(result.dynamics_loss, 0.);
+        assert_eq!(
+            result.latent_state,
+            Some(4.5),
+            "Expected the estimate made before the last step"
+        );
+    }
+
+    /// A `tracing` subscriber recording the name and step of every span created.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct SpanRecorder {
+        /// The recorded span names and steps, in order of creation.
+        spans: Arc<Mutex<Vec<(&'static str, u64)>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            /// Extracts the `step` field of a span.
+            struct StepVisitor(u64);
+
+            impl tracing::field::Visit for StepVisitor {
+                fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+                    if field.name() == "step" {
+                        self.0 = value;
+                    }
+                }
+
+                fn record_debug(
+                    &mut self,
+                    _field: &tracing::field::Field,
+                    _value: &dyn std::fmt::Debug,
+                ) {
+                }
+            }
+
+            let mut visitor = StepVisitor(u64::MAX);
+            span.record(&mut visitor);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((span.metadata().name(), visitor.0));
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, _event: &tracing::Event<'_>) {}
+
+        fn enter(&self, _span: &tracing::span::Id) {}
+
+        fn exit(&self, _span: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans() {
+        let (simulator, _) = counting_simulator();
+        let recorder = SpanRecorder::default();
+        let spans = recorder.spans.clone();
+
+        tracing::subscriber::with_default(recorder, || {
+            block_on(experiment(
+                &CountingSystem,
+                NullDriver,
+                NullGenerator,
+                simulator,
+                LastObservationPredictor,
+                ExperimentConfig::new(0.5).max_steps(3),
+            ))
+        });
+
+        let spans = spans.lock().unwrap();
+        for step in 0..3 {
+            for phase in ["get_observations", "predict_state", "update"] {
+                assert_eq!(
+                    spans.iter().filter(|&&span| span == (phase, step)).count(),
+                    1,
+                    "Expected one {phase} span at step {step}"
+                );
+            }
+        }
+        assert_eq!(
+            spans
+                .iter()
+                .filter(|(name, _)| *name == "compute_controls")
+                .collect::<Vec<_>>(),
+            [&("compute_controls", 0)],
+            "Expected the never-finishing controls to be computed once"
+        );
+    }
+
+    #[test]
+    fn test_dt_hint() {
+        let (mut simulator, _) = counting_simulator();
+        simulator.dt_hint = Some(0.25);
+
+        let result = block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(4),
+        ));
+
+        assert_eq!(result.steps, 4);
+        assert_eq!(
+            result.time, 1.,
+            "Expected the hinted timestep to override the configured one"
+        );
+    }
+
+    #[test]
+    fn test_stop_on_divergence() {
+        let run = |stop_on_divergence| {
+            let (mut simulator, _) = counting_simulator();
+            simulator.diverges_at = Some(2.);
+
+            block_on(experiment(
+                &CountingSystem,
+                NullDriver,
+                NullGenerator,
+                simulator,
+                LastObservationPredictor,
+                ExperimentConfig::new(0.5)
+                    .max_steps(10)
+                    .stop_on_divergence(stop_on_divergence),
+            ))
+        };
+
+        let result = run(true);
+        assert_eq!(result.outcome, ExperimentOutcome::Diverged);
+        assert_eq!(
+            result.steps, 4,
+            "Expected the experiment to stop once the simulation diverged"
+        );
+        assert_eq!(result.time, 2.);
+
+        let result = run(false);
+        assert_eq!(result.outcome, ExperimentOutcome::Finished);
+        assert_eq!(
+            result.steps, 10,
+            "Expected the divergence to be ignored unless requested"
+        );
+    }
+
+    #[test]
+    fn test_eval_phases() {
+        let (mut simulator, steps) = counting_simulator();
+        simulator.loss_rate = 1.;
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let driver = EvalDriver {
+            steps,
+            log: log.clone(),
+        };
+
+        let result = block_on(experiment(
+            &CountingSystem,
+            driver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(1.).max_steps(12).eval_phases(3, 2),
+        ));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [(3, true), (5, false), (8, true), (10, false)],
+            "Expected two evaluation steps after every three training steps"
+        );
+        assert_eq!(
+            result.eval_losses,
+            [4.5, 9.5],
+            "Expected the average losses after the evaluation steps"
+        );
+    }
+
+    #[test]
+    fn test_progress_disabled() {
+        let (simulator, steps) = counting_simulator();
+        let config = ExperimentConfig::new(0.5).max_steps(3).progress(false);
+        assert!(
+            config.progress_bar().is_none(),
+            "Expected no progress bar when disabled"
+        );
+
+        let result = block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            config,
+        ));
+
+        assert_eq!(result.steps, 3, "Expected the configured number of steps");
+        assert_eq!(
+            steps.load(Ordering::SeqCst),
+            3,
+            "Expected 3 simulator updates"
+        );
+    }
+
+    #[test]
+    fn test_experiment_from_setup() {
+        let (simulator, steps) = counting_simulator();
+        let setup = ExperimentSetup::<f64, CountingSystem>::new((), (), 0.25, 0).max_steps(8);
+
+        let result = block_on(experiment_from_setup(
+            &CountingSystem,
+            NullDriver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            &setup,
+        ));
+
+        assert_eq!(result.steps, 8, "Expected the number of steps of the setup");
+        assert_eq!(
+            steps.load(Ordering::SeqCst),
+            8,
+            "Expected 8 simulator updates"
+        );
+        assert_eq!(result.time, 2., "Expected the timestep of the setup");
+    }
+
+    #[test]
+    fn test_max_control_rate() {
+        let (simulator, _) = counting_simulator();
+        let driver = QueuedDriver(Mutex::new(VecDeque::from([vec![0., 0.], vec![1., -0.1]])));
+        let applied = Arc::new(Mutex::new(Vec::new()));
+
+        block_on(experiment(
+            &CountingSystem,
+            driver,
+            RecordingGenerator(applied.clone()),
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(1e-3)
+                .max_steps(10)
+                .max_control_rate(0.25),
+        ));
+
+        assert_eq!(
+            *applied.lock().unwrap(),
+            vec![
+                vec![0., 0.],
+                vec![0.25, -0.1],
+                vec![0.5, -0.1],
+                vec![0.75, -0.1],
+                vec![1., -0.1],
+            ],
+            "Expected the control jump to be ramped over multiple steps"
+        );
+    }
+
+    #[test]
+    fn test_slew_limiter_ramps_per_step() {
+        let mut limiter = SlewLimiter::new(Some(0.25));
+        limiter.set_target(vec![0f64]);
+        assert_eq!(
+            limiter.step(false),
+            Some(vec![0.]),
+            "Expected the first controls to be applied without a step"
+        );
+
+        limiter.set_target(vec![1.]);
+        let ramp = [false, true, false, false, true].map(|stepped| limiter.step(stepped));
+        assert_eq!(
+            ramp,
+            [None, Some(vec![0.25]), None, None, Some(vec![0.5])],
+            "Expected the ramp to only advance on simulator steps"
+        );
+    }
+
+    #[test]
+    fn test_full_state_driver() {
+        let (simulator, _) = counting_simulator();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        block_on(experiment(
+            &CountingSystem,
+            StateDriver(AtomicBool::new(false)),
+            RecordingGenerator(log.clone()),
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(4).full_state(true),
+        ));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [[0.]],
+            "Expected the controls computed from the initial system state"
+        );
+    }
+
+    #[test]
+    fn test_full_state_fallback() {
+        let (simulator, steps) = counting_simulator();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let driver = StepLoggingDriver {
+            steps,
+            log: log.clone(),
+        };
+
+        block_on(experiment_with_scheduler(
+            &CountingSystem,
+            driver,
+            NullGenerator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(8).full_state(true),
+            FixedRateScheduler { period: 4, next: 0 },
+        ));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [0, 4],
+            "Expected the controls computed from the state estimate"
+        );
+    }
+
+    #[test]
+    fn test_observation_context() {
+        let (simulator, _) = counting_simulator();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let generator = ContextGenerator {
+            context: None,
+            log: log.clone(),
+        };
+
+        block_on(experiment(
+            &CountingSystem,
+            NullDriver,
+            generator,
+            simulator,
+            LastObservationPredictor,
+            ExperimentConfig::new(0.5).max_steps(3),
+        ));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![Some(0.), Some(0.5), Some(1.)],
+            "Expected the latest observation as context for every control signal"
+        );
+    }
+
+    #[test]
+    fn test_validate_setup() {
+        let setup = ExperimentSetup::<f64, CountingSystem>::new((), (), 0.25, 0);
+        let (simulator, steps) = counting_simulator();
+        let build = move |_seed| {
+            let driver = QueuedDriver(Mutex::new(VecDeque::from([Vec::new()])));
+            (driver, NullGenerator, simulator, CountingPredictor(0))
+        };
+
+        let result = block_on(validate_setup(&CountingSystem, &setup, build));
+        assert!(
+            result.is_ok(),
+            "Expected a consistent setup, got {result:?}"
+        );
+        assert_eq!(
+            steps.load(Ordering::SeqCst),
+            1,
+            "Expected the dry run to update the throwaway simulator once"
+        );
+
+        // The system expects no control parameters.
+        let (simulator, _) = counting_simulator();
+        let build = move |_seed| {
+            let driver = QueuedDriver(Mutex::new(VecDeque::from([vec![1., 2.]])));
+            (driver, NullGenerator, simulator, CountingPredictor(0))
+        };
+        let result = block_on(validate_setup(&CountingSystem, &setup, build));
+        assert!(
+            matches!(
+                result,
+                Err(SetupError::Shape {
+                    stage: "control parameters",
+                    expected: 0,
+                    actual: 2,
+                })
+            ),
+            "Expected the mismatched control parameters to be rejected, got {result:?}"
+        );
+    }
+
+    /// A system with a growable full state, used to exercise [`validate_setup`] on a simulator
+    /// whose update breaks the shape of its state.
+    struct GrowingSystem;
+
+    impl System<f64> for GrowingSystem {
+        const CONTROL_SIGNAL_SIZE: usize = 0;
+        const CONTROL_PARAMS_SIZE: usize = 0;
+        const LATENT_STATE_SIZE: usize = 1;
+        const SYSTEM_STATE_SIZE: usize = 1;
+        const OBSERVABLE_STATE_SIZE: usize = 1;
+
+        type SystemConfiguration = ();
+        type DynamicsConfiguration = ();
+
+        type SystemState = Vec<f64>;
+        type LatentState = f64;
+        type ControlParams = ();
+        type ControlSignal = ();
+        type SystemObservation = f64;
+    }
+
+    /// A faulty simulator appending a new value to its full state on every update.
+    struct GrowingSimulator(Vec<f64>);
+
+    impl SimulatorInterface<f64, GrowingSystem> for GrowingSimulator {
+        async fn get_observations(&self) -> Vec<f64> {
+            vec![self.get_time()]
+        }
+
+        async fn update(&mut self, _system: &GrowingSystem, dt: f64, _control_signal: &()) {
+            self.0.push(self.get_time() + dt);
+        }
+
+        async fn get_dynamics_loss(&self) -> f64 {
+            0.
+        }
+
+        fn get_time(&self) -> f64 {
+            *self.0.last().unwrap()
+        }
+
+        fn peek_full_state(&self) -> Option<Vec<f64>> {
+            Some(self.0.clone())
+        }
+    }
+
+    /// The trivial driver, generator and state predictor of the [`GrowingSystem`].
+    struct GrowingComponents;
+
+    impl DriverInterface<f64, GrowingSystem> for GrowingComponents {
+        async fn compute_controls(&self, _state_estimate: f64, _dynamics_loss: f64) {}
+    }
+
+    impl GeneratorInterface<f64, GrowingSystem> for GrowingComponents {
+        async fn set_parameters(&mut self, _controls: (), _time: f64) {}
+
+        fn control_signal(&mut self, _time: f64) {}
+    }
+
+    impl StatePredictionInterface<f64, GrowingSystem> for GrowingComponents {
+        async fn predict_state(&mut self, observation: &[f64]) -> f64 {
+            *observation.last().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_validate_setup_update_mismatch() {
+        let setup = ExperimentSetup::<f64, GrowingSystem>::new((), (), 0.25, 0);
+
+        let result = block_on(validate_setup(&GrowingSystem, &setup, |_seed| {
+            (
+                GrowingComponents,
+                GrowingComponents,
+                GrowingSimulator(vec![0.]),
+                GrowingComponents,
+            )
+        }));
+        assert!(
+            matches!(
+                result,
+                Err(SetupError::Shape {
+                    stage: "updated system state",
+                    expected: 1,
+                    actual: 2,
+                })
+            ),
+            "Expected the state broken by the update to be rejected, got {result:?}"
+        );
     }
 }
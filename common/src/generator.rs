@@ -0,0 +1,78 @@
+use std::marker::PhantomData;
+
+use crate::{
+    interfaces::GeneratorInterface,
+    system::{DynamicVector, System},
+    Float,
+};
+
+/// A [`GeneratorInterface`] superimposing the control signals of two generators, e.g. a learned
+/// signal and a fixed bias waveform, by adding them element-wise. The control parameters and
+/// observation context are forwarded to both generators, so a generator that doesn't depend on
+/// the parameters can simply ignore them.
+pub struct SumGenerator<T, G1, G2> {
+    /// The first generator.
+    first: G1,
+    /// The second generator.
+    second: G2,
+    /// [`PhantomData`] to support the generic type.
+    _phantom: PhantomData<T>,
+}
+
+impl<T, G1, G2> SumGenerator<T, G1, G2> {
+    /// Creates a [`SumGenerator`] adding up the signals of the given generators.
+    pub fn new(first: G1, second: G2) -> Self {
+        Self {
+            first,
+            second,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Gets back the two generators.
+    pub fn into_inner(self) -> (G1, G2) {
+        (self.first, self.second)
+    }
+}
+
+impl<T, S, G1, G2> GeneratorInterface<T, S> for SumGenerator<T, G1, G2>
+where
+    T: Float,
+    S: System<T>,
+    G1: GeneratorInterface<T, S>,
+    G2: GeneratorInterface<T, S>,
+{
+    async fn set_parameters(&mut self, controls: S::ControlParams, time: T) {
+        self.first.set_parameters(controls.clone(), time).await;
+        self.second.set_parameters(controls, time).await;
+    }
+
+    /// Adds up the control signals of both generators.
+    ///
+    /// # Panics
+    /// If the signals don't have the same length.
+    fn control_signal(&mut self, time: T) -> S::ControlSignal {
+        let mut signal = self.first.control_signal(time);
+        let other = self.second.control_signal(time);
+        {
+            let (mut rope, other_rope) = (signal.get_rope_mut(), other.get_rope());
+            assert_eq!(
+                rope.len(),
+                other_rope.len(),
+                "Expected control signals of the same length but got {} and {}",
+                rope.len(),
+                other_rope.len()
+            );
+            rope.iter_mut()
+                .zip(other_rope.iter())
+                .for_each(|(x, &y)| *x = *x + y);
+        }
+
+        signal
+    }
+
+    fn set_observation_context(&mut self, observations: &[S::SystemObservation]) {
+        self.first.set_observation_context(observations);
+        self.second.set_observation_context(observations);
+    }
+}
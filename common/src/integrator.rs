@@ -0,0 +1,311 @@
+use itertools::izip;
+
+use crate::{system::PhysicsSystem, Float};
+
+/// A numerical scheme that advances the state of a [`PhysicsSystem`] by a single timestep.
+pub trait Integrator<T: Float, S: PhysicsSystem<T>> {
+    /// Advances `state` by `dt` under the given control signal, saving the time, positions and
+    /// velocities of the result into `next_state`. Any other data in `next_state` is left as is.
+    fn step(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        control_signal: &S::ControlSignal,
+        dt: T,
+    );
+
+    /// Advances `state` by `dt` like [`Integrator::step`], but with the control signal switching
+    /// from `control_signal` at the start of the step to `next_control_signal` at its end, i.e.
+    /// each state is paired with its own control. By default, `next_control_signal` is held over
+    /// the whole step.
+    fn step_with_controls(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        _control_signal: &S::ControlSignal,
+        next_control_signal: &S::ControlSignal,
+        dt: T,
+    ) {
+        self.step(system, state, next_state, next_control_signal, dt);
+    }
+}
+
+/// The [velocity Verlet](https://en.wikipedia.org/wiki/Verlet_integration#Velocity_Verlet)
+/// integrator. It is second order and symplectic for velocity-independent forces.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VelocityVerlet;
+
+/// The [semi-implicit Euler](https://en.wikipedia.org/wiki/Semi-implicit_Euler_method)
+/// integrator, which updates the velocity before the position. It is first order and symplectic.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SymplecticEuler;
+
+/// The classical fourth order [Runge-Kutta](https://en.wikipedia.org/wiki/Runge%E2%80%93Kutta_methods)
+/// integrator. It is not symplectic, but is the most accurate for smooth dynamics.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rk4;
+
+/// Computes the accelerations of the system state into a new buffer.
+fn acceleration<T: Float, S: PhysicsSystem<T>>(
+    system: &S,
+    state: &S::SystemState,
+    control_signal: &S::ControlSignal,
+) -> Vec<T> {
+    let mut acceleration = vec![T::zero(); S::position(state).len()];
+    system.acceleration(state, control_signal, &mut acceleration);
+
+    acceleration
+}
+
+impl<T: Float, S: PhysicsSystem<T>> Integrator<T, S> for VelocityVerlet {
+    fn step(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        control_signal: &S::ControlSignal,
+        dt: T,
+    ) {
+        self.step_with_controls(
+            system,
+            state,
+            next_state,
+            control_signal,
+            control_signal,
+            dt,
+        );
+    }
+
+    /// The acceleration at the start of the step is computed under `control_signal`, and that at
+    /// the end under `next_control_signal`.
+    fn step_with_controls(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        control_signal: &S::ControlSignal,
+        next_control_signal: &S::ControlSignal,
+        dt: T,
+    ) {
+        let two = T::one() + T::one();
+        let (position, velocity) = (S::position(state), S::velocity(state));
+        let prev_acc = acceleration(system, state, control_signal);
+
+        S::set_time(next_state, S::time(state) + dt);
+        let (next_position, next_velocity) = S::phase_space_mut(next_state);
+        for (next_x, &x, &v, &a) in izip!(next_position, position, velocity, &prev_acc) {
+            *next_x = x + v * dt + a * dt * dt / two;
+        }
+        next_velocity.copy_from_slice(velocity);
+
+        let next_acc = acceleration(system, next_state, next_control_signal);
+        let (_, next_velocity) = S::phase_space_mut(next_state);
+        for (next_v, &v, &a1, &a2) in izip!(next_velocity, velocity, &prev_acc, &next_acc) {
+            *next_v = v + (a1 + a2) / two * dt;
+        }
+    }
+}
+
+impl<T: Float, S: PhysicsSystem<T>> Integrator<T, S> for SymplecticEuler {
+    fn step(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        control_signal: &S::ControlSignal,
+        dt: T,
+    ) {
+        let (position, velocity) = (S::position(state), S::velocity(state));
+        let acc = acceleration(system, state, control_signal);
+
+        S::set_time(next_state, S::time(state) + dt);
+        let (next_position, next_velocity) = S::phase_space_mut(next_state);
+        for (next_x, next_v, &x, &v, &a) in
+            izip!(next_position, next_velocity, position, velocity, &acc)
+        {
+            *next_v = v + a * dt;
+            *next_x = x + *next_v * dt;
+        }
+    }
+}
+
+impl<T: Float, S: PhysicsSystem<T>> Integrator<T, S> for Rk4 {
+    fn step(
+        &self,
+        system: &S,
+        state: &S::SystemState,
+        next_state: &mut S::SystemState,
+        control_signal: &S::ControlSignal,
+        dt: T,
+    ) {
+        let two = T::one() + T::one();
+        let six = two + two + two;
+        let (position, velocity) = (S::position(state), S::velocity(state));
+        let time = S::time(state);
+
+        // The derivative of the position is the velocity, and that of the velocity is the
+        // acceleration. Each stage is evaluated using `next_state` as scratch space.
+        let k1_x = velocity.to_vec();
+        let k1_v = acceleration(system, state, control_signal);
+        let mut stage = |k_x: &[T], k_v: &[T], h: T| {
+            S::set_time(next_state, time + h);
+            let (next_position, next_velocity) = S::phase_space_mut(next_state);
+            for (next_x, next_v, &x, &v, &k_x, &k_v) in izip!(
+                &mut *next_position,
+                &mut *next_velocity,
+                position,
+                velocity,
+                k_x,
+                k_v
+            ) {
+                *next_x = x + k_x * h;
+                *next_v = v + k_v * h;
+            }
+            let k_x = next_velocity.to_vec();
+
+            (k_x, acceleration(system, next_state, control_signal))
+        };
+        let (k2_x, k2_v) = stage(&k1_x, &k1_v, dt / two);
+        let (k3_x, k3_v) = stage(&k2_x, &k2_v, dt / two);
+        let (k4_x, k4_v) = stage(&k3_x, &k3_v, dt);
+
+        S::set_time(next_state, time + dt);
+        let (next_position, next_velocity) = S::phase_space_mut(next_state);
+        let weighted = |k1: &[T], k2: &[T], k3: &[T], k4: &[T], i: usize| {
+            (k1[i] + two * (k2[i] + k3[i]) + k4[i]) * dt / six
+        };
+        for (i, (next_x, next_v, &x, &v)) in
+            izip!(next_position, next_velocity, position, velocity).enumerate()
+        {
+            *next_x = x + weighted(&k1_x, &k2_x, &k3_x, &k4_x, i);
+            *next_v = v + weighted(&k1_v, &k2_v, &k3_v, &k4_v, i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Integrator, Rk4, SymplecticEuler, VelocityVerlet};
+    use crate::{
+        rope::{Rope, RopeMut},
+        system::{DynamicVector, PhysicsSystem, System},
+    };
+
+    /// A unit mass on a unit spring, with the control signal ignored.
+    struct Spring;
+
+    /// The state of the [`Spring`] system.
+    #[derive(Debug, Clone, Copy)]
+    struct SpringState {
+        /// The current time.
+        time: f64,
+        /// The displacement of the mass.
+        position: [f64; 1],
+        /// The velocity of the mass.
+        velocity: [f64; 1],
+    }
+
+    impl DynamicVector<f64> for SpringState {
+        fn get_rope(&self) -> Rope<'_, f64> {
+            Rope::new(&[&self.position[..], &self.velocity[..]])
+        }
+
+        fn get_rope_mut(&mut self) -> RopeMut<'_, f64> {
+            RopeMut::new([&mut self.position, &mut self.velocity])
+        }
+    }
+
+    impl System<f64> for Spring {
+        const CONTROL_SIGNAL_SIZE: usize = 0;
+        const CONTROL_PARAMS_SIZE: usize = 0;
+        const LATENT_STATE_SIZE: usize = 2;
+        const SYSTEM_STATE_SIZE: usize = 2;
+        const OBSERVABLE_STATE_SIZE: usize = 2;
+
+        type SystemConfiguration = ();
+        type DynamicsConfiguration = ();
+
+        type SystemState = SpringState;
+        type LatentState = SpringState;
+        type ControlParams = ();
+        type ControlSignal = ();
+        type SystemObservation = f64;
+    }
+
+    impl PhysicsSystem<f64> for Spring {
+        fn time(state: &SpringState) -> f64 {
+            state.time
+        }
+
+        fn set_time(state: &mut SpringState, time: f64) {
+            state.time = time;
+        }
+
+        fn position(state: &SpringState) -> &[f64] {
+            &state.position
+        }
+
+        fn velocity(state: &SpringState) -> &[f64] {
+            &state.velocity
+        }
+
+        fn phase_space_mut(state: &mut SpringState) -> (&mut [f64], &mut [f64]) {
+            (&mut state.position, &mut state.velocity)
+        }
+
+        fn acceleration(
+            &self,
+            state: &SpringState,
+            _control_signal: &(),
+            acceleration: &mut [f64],
+        ) {
+            acceleration[0] = -state.position[0];
+        }
+
+        fn energy(&self, state: &SpringState) -> f64 {
+            (state.position[0].powi(2) + state.velocity[0].powi(2)) / 2.
+        }
+    }
+
+    /// Integrates the [`Spring`] from unit displacement at rest until `t = 1`, and returns the
+    /// error with respect to the analytic solution `cos(t)`.
+    fn integration_error(integrator: impl Integrator<f64, Spring>, steps: usize) -> f64 {
+        let dt = 1. / steps as f64;
+        let mut state = SpringState {
+            time: 0.,
+            position: [1.],
+            velocity: [0.],
+        };
+
+        for _ in 0..steps {
+            let mut next_state = state;
+            integrator.step(&Spring, &state, &mut next_state, &(), dt);
+            state = next_state;
+        }
+        assert!(
+            (state.time - 1.).abs() < 1e-12,
+            "Expected to integrate until t = 1, got t = {}",
+            state.time
+        );
+
+        (state.position[0] - 1f64.cos()).abs() + (state.velocity[0] + 1f64.sin()).abs()
+    }
+
+    #[test]
+    fn test_integrators_converge() {
+        assert!(integration_error(SymplecticEuler, 1000) < 1e-3);
+        assert!(integration_error(VelocityVerlet, 1000) < 1e-6);
+        assert!(integration_error(Rk4, 100) < 1e-9);
+    }
+
+    #[test]
+    fn test_integrator_orders() {
+        let ratio = |integrator: fn(usize) -> f64| integrator(100) / integrator(200);
+
+        assert!((ratio(|n| integration_error(SymplecticEuler, n)) - 2.).abs() < 0.1);
+        assert!((ratio(|n| integration_error(VelocityVerlet, n)) - 4.).abs() < 0.2);
+        assert!((ratio(|n| integration_error(Rk4, n)) - 16.).abs() < 1.);
+    }
+}
@@ -1,6 +1,9 @@
 #![allow(async_fn_in_trait)]
 
-use crate::{system::System, Float};
+use crate::{
+    system::{ObservationExt, System},
+    Float,
+};
 
 /// The interface for an agent driving our dynamical system.
 // ANCHOR: DriverInterface
@@ -11,6 +14,26 @@ pub trait DriverInterface<T: Float, S: System<T>> {
         state_estimate: S::LatentState,
         dynamics_loss: T,
     ) -> S::ControlParams;
+
+    /// For the true system state, computes the control parameters that should be associated with
+    /// it. This is used instead of [`DriverInterface::compute_controls`] by privileged (teacher)
+    /// drivers when [`ExperimentConfig::full_state`](crate::coordinator::ExperimentConfig) is set,
+    /// e.g. for behavior cloning. Returns [`None`] by default, if the driver can't compute
+    /// controls from the full system state, in which case the controls are computed from the
+    /// state estimate instead.
+    async fn compute_controls_from_state(
+        &self,
+        _state: &S::SystemState,
+        _dynamics_loss: T,
+    ) -> Option<S::ControlParams> {
+        None
+    }
+
+    /// Switches the driver into (or out of) evaluation mode, in which it should compute controls
+    /// without learning from them, e.g. during the evaluation phases of
+    /// [`ExperimentConfig::eval_interval`](crate::coordinator::ExperimentConfig). Does nothing by
+    /// default.
+    fn set_eval(&self, _eval: bool) {}
 }
 // ANCHOR_END: DriverInterface
 
@@ -22,6 +45,11 @@ pub trait GeneratorInterface<T: Float, S: System<T>> {
 
     /// Gets the control signal at the given time.
     fn control_signal(&mut self, time: T) -> S::ControlSignal;
+
+    /// Supplies the latest observations of the system before each call to
+    /// [`GeneratorInterface::control_signal`], so that feedforward generators can shape the
+    /// signal based on the recent history. Does nothing by default.
+    fn set_observation_context(&mut self, _observations: &[S::SystemObservation]) {}
 }
 // ANCHOR_END: GeneratorInterface
 
@@ -31,14 +59,71 @@ pub trait SimulatorInterface<T: Float, S: System<T>> {
     /// Gets the last `DELAY_DEPTH` collection of observed states.
     async fn get_observations(&self) -> Vec<S::SystemObservation>;
 
+    /// Gets only the most recent observation, e.g. for logging without copying the whole window
+    /// of [`SimulatorInterface::get_observations`]. By default, the window is fetched and its
+    /// latest observation picked by [`ObservationExt::time`], so simulators should override this
+    /// when they can copy a single observation directly.
+    ///
+    /// # Panics
+    /// If the simulator has no observations.
+    async fn latest_observation(&self) -> S::SystemObservation {
+        self.get_observations()
+            .await
+            .into_iter()
+            .reduce(|latest, observation| {
+                if observation.time() >= latest.time() {
+                    observation
+                } else {
+                    latest
+                }
+            })
+            .expect("Expected the simulator to have an observation")
+    }
+
     /// Updates the state of the system by the given timestep.
     async fn update(&mut self, system: &S, dt: T, control_signal: &S::ControlSignal);
 
+    /// Updates the state of the system by `n` timesteps under the same control signal.
+    /// Implementors can override this to batch the steps more cheaply than `n` calls to
+    /// [`SimulatorInterface::update`].
+    async fn step_n(&mut self, system: &S, dt: T, control_signal: &S::ControlSignal, n: usize) {
+        for _ in 0..n {
+            self.update(system, dt, control_signal).await;
+        }
+    }
+
     /// Compute the "goodness" of the dynamics thus far.
     async fn get_dynamics_loss(&self) -> T;
 
     /// Gets the current time of the system state.
     fn get_time(&self) -> T;
+
+    /// The timestep the simulator would prefer for its next update, e.g. as chosen by an adaptive
+    /// integrator. When given, the coordinator uses it instead of the configured timestep. Returns
+    /// [`None`] by default.
+    fn dt_hint(&self) -> Option<T> {
+        None
+    }
+
+    /// Cheaply checks whether the simulation is still numerically healthy, i.e. it has not
+    /// diverged or produced non-finite values.
+    async fn is_stable(&self) -> bool {
+        true
+    }
+
+    /// Gets a copy of the true current system state, for privileged drivers, see
+    /// [`DriverInterface::compute_controls_from_state`]. Returns [`None`] by default, if the
+    /// simulator doesn't expose its state.
+    fn peek_full_state(&self) -> Option<S::SystemState> {
+        None
+    }
+
+    /// Names each component of the flattened observations (see
+    /// [`DynamicVector::get_rope`](crate::system::DynamicVector::get_rope)), e.g. as column
+    /// headers when logging them. Empty by default, if the simulator doesn't label them.
+    fn observation_labels(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 // ANCHOR_END: SimulatorInterface
 
@@ -47,5 +132,22 @@ pub trait SimulatorInterface<T: Float, S: System<T>> {
 pub trait StatePredictionInterface<T: Float, S: System<T>> {
     /// Given a series of observations, predict the full state of the system.
     async fn predict_state(&mut self, observation: &[S::SystemObservation]) -> S::LatentState;
+
+    /// Resets any hidden state carried between predictions, e.g. a recurrent state, so that the
+    /// next episode starts afresh. Called by [`experiment`](crate::coordinator::experiment)
+    /// before its control cycle starts when
+    /// [`ExperimentConfig::reset_state_predictor`](crate::coordinator::ExperimentConfig::reset_state_predictor)
+    /// is set. Does nothing by default.
+    fn reset(&mut self) {}
 }
 // ANCHOR_END: StatePredictionInterface
+
+/// Labels the components of a `dims`-dimensional quantity by suffixing `prefix` with the axis,
+/// i.e. `x`, `y` and `z`, or the index of the axis beyond three dimensions. This is a helper for
+/// [`SimulatorInterface::observation_labels`].
+pub fn axis_labels(prefix: &str, dims: usize) -> impl Iterator<Item = String> + '_ {
+    (0..dims).map(move |axis| match ["x", "y", "z"].get(axis) {
+        Some(name) => format!("{prefix}_{name}"),
+        None => format!("{prefix}_{axis}"),
+    })
+}
@@ -0,0 +1,137 @@
+use crate::vector::Vector;
+
+/// Given a scalar index into a `DIMS`-dimensional flattened regular array of size `size^DIMS`,
+/// compute the vector index. The array is flattened in row-major order, i.e. the last coordinate
+/// varies the fastest.
+///
+/// The scalar index should be less than `size^DIMS`. Larger indices wrap around in the first
+/// coordinate, which always stays less than `size`.
+///
+/// # Panics
+/// If the strides of the lattice overflow a [`usize`], i.e. if `size^(DIMS - 1)` does.
+pub fn deindex<const DIMS: usize>(index: usize, size: usize) -> Vector<usize, DIMS> {
+    debug_assert!(size > 0, "Expected a positive lattice size");
+    (Vector::broadcast(index) / strides(size)) % size
+}
+
+/// Given a vector index into a `DIMS`-dimensional regular array of where each dimension has size
+/// `size`, compute the scalar index into the array flattened in row-major order. This is the
+/// inverse of [`deindex`].
+///
+/// Every coordinate should be less than `size`, otherwise the scalar index aliases that of
+/// another lattice point, or lies past the end of the array.
+///
+/// # Panics
+/// If the scalar index overflows a [`usize`], e.g. for a coordinate that wrapped around below
+/// zero, rather than silently producing the index of the wrong lattice point. See
+/// [`checked_index`] for a non-panicking version.
+pub fn index<const DIMS: usize>(index: Vector<usize, DIMS>, size: usize) -> usize {
+    checked_index(index, size).unwrap_or_else(|| {
+        panic!("The scalar index of {index:?} overflows a lattice of size {size}")
+    })
+}
+
+/// Given a vector index into a `DIMS`-dimensional regular array where each dimension has size
+/// `size`, compute the scalar index into the array flattened in row-major order, or [`None`] if
+/// it overflows a [`usize`]. See [`index`].
+pub fn checked_index<const DIMS: usize>(index: Vector<usize, DIMS>, size: usize) -> Option<usize> {
+    debug_assert!(size > 0, "Expected a positive lattice size");
+    let strides = checked_strides(size)?;
+
+    index
+        .checked_mul(strides)?
+        .into_iter()
+        .try_fold(0usize, |sum, x| sum.checked_add(x))
+}
+
+/// The [`checked_strides`] of a `DIMS`-dimensional regular array of size `size^DIMS`.
+///
+/// # Panics
+/// If any of the strides overflows a [`usize`].
+fn strides<const DIMS: usize>(size: usize) -> Vector<usize, DIMS> {
+    checked_strides(size).unwrap_or_else(|| {
+        panic!("The strides of a {DIMS}-dimensional lattice of size {size} overflow a usize")
+    })
+}
+
+/// The strides of the coordinates of a `DIMS`-dimensional regular array of size `size^DIMS`
+/// flattened in row-major order, i.e. `size^(DIMS - i - 1)` for the `i`-th coordinate, or
+/// [`None`] if any of them overflows a [`usize`].
+fn checked_strides<const DIMS: usize>(size: usize) -> Option<Vector<usize, DIMS>> {
+    Vector::try_from_idx(|i| {
+        u32::try_from(DIMS - i - 1)
+            .ok()
+            .and_then(|exponent| size.checked_pow(exponent))
+            .ok_or(())
+    })
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checked_index, deindex, index};
+    use crate::vector::Vector;
+
+    /// Checks that every scalar index of a lattice round-trips through its vector index.
+    fn check_round_trip<const DIMS: usize>(size: usize) {
+        for i in 0..size.pow(DIMS as u32) {
+            let x = deindex::<DIMS>(i, size);
+            assert!(
+                x.iter().all(|&x| x < size),
+                "Expected {x:?} to be in a lattice of size {size}"
+            );
+            assert_eq!(index(x, size), i, "Expected {x:?} to map back to {i}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for size in 1..6 {
+            check_round_trip::<1>(size);
+            check_round_trip::<2>(size);
+            check_round_trip::<3>(size);
+        }
+    }
+
+    #[test]
+    fn test_row_major_order() {
+        assert_eq!(deindex::<1>(3, 4), Vector::new([3]));
+        assert_eq!(deindex::<2>(6, 4), Vector::new([1, 2]));
+        assert_eq!(deindex::<3>(27, 3), Vector::new([0, 0, 0]));
+        assert_eq!(deindex::<3>(14, 3), Vector::new([1, 1, 2]));
+        assert_eq!(index(Vector::new([2, 0, 1]), 3), 19);
+    }
+
+    #[test]
+    fn test_checked_index_overflow() {
+        let size = 1 << 20;
+        assert_eq!(
+            checked_index(Vector::new([2, 3]), size),
+            Some(2 * size + 3),
+            "Expected the index of a large lattice to fit"
+        );
+        assert_eq!(
+            checked_index(Vector::new([usize::MAX - 1, 0]), 4),
+            None,
+            "Expected the index of a near-usize::MAX coordinate to overflow"
+        );
+        assert_eq!(
+            checked_index::<3>(Vector::new([0, 0, 0]), 1 << 40),
+            None,
+            "Expected the strides of a lattice with more than usize::MAX points to overflow"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow a usize")]
+    fn test_deindex_panics_on_stride_overflow() {
+        deindex::<3>(0, 1 << 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows a lattice of size 4")]
+    fn test_index_panics_on_overflow() {
+        // E.g. the neighbor of the origin along the first axis, wrapped around below zero.
+        index(Vector::new([usize::MAX, 0]), 4);
+    }
+}
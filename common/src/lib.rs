@@ -8,7 +8,10 @@
 //! This crate defines the common types, systems, messages, and utilities for the asynchronous RL
 //! project.
 
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
 use num::NumCast;
 use pyo3::{
@@ -44,8 +47,55 @@ pub mod rope;
 /// This module ties together all the interfaces into an experiment.
 pub mod coordinator;
 
+/// Defines the [`GaussianNoise<T>`](crate::noise::GaussianNoise) model used to perturb
+/// simulator observations, along with the [`Rng<T>`](crate::noise::Rng) it draws from.
+pub mod noise;
+
+/// Defines the [`Integrator`](crate::integrator::Integrator) trait for time-stepping a
+/// [`PhysicsSystem`](crate::system::PhysicsSystem), along with some standard integrators.
+pub mod integrator;
+
+/// Defines the [`RunningNormalizer<T>`](crate::normalizer::RunningNormalizer) that standardizes
+/// observations using running statistics.
+pub mod normalizer;
+
+/// Defines the conversions between scalar indices into flattened regular lattices and their
+/// vector indices, shared by the lattice systems.
+pub mod lattice;
+
+/// Defines the [`ring_indices`](crate::ring::ring_indices) of the history ring buffers kept by
+/// the simulators.
+pub mod ring;
+
+/// Defines generic [`GeneratorInterface`](crate::interfaces::GeneratorInterface) combinators,
+/// such as the [`SumGenerator`](crate::generator::SumGenerator) superimposing two signals.
+pub mod generator;
+
+/// Defines the [`TrajectoryRecorder`](crate::recorder::TrajectoryRecorder) trait for recording
+/// the states and control signals of a simulation, along with a CSV recorder.
+pub mod recorder;
+
+/// Defines the [`WireFormat`](crate::wire::WireFormat) binary encoding of the messages between
+/// the experiment components.
+pub mod wire;
+
+/// Defines the [`Transport`](crate::remote::Transport) trait for running the experiment
+/// components in separate processes, along with the adapters that forward the driver and
+/// simulator interfaces over it.
+pub mod remote;
+
+/// Defines the [`BulkOps`](crate::simd::BulkOps) trait for element-wise operations on large
+/// slices of floats, vectorized with the `wide` crate when the `simd` feature is enabled.
+pub mod simd;
+
+/// Defines shared utilities for testing systems and simulators across crates, such as the
+/// [`energy_drift`](crate::test_util::energy_drift) oracle for integrators. Only available with
+/// the `test-util` feature.
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
 /// The type of [`Float`]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum FloatType {
     /// 32-bit Float
     F32,
@@ -85,18 +135,48 @@ impl FloatType {
     }
 }
 
+/// The error returned when parsing an unknown [`FloatType`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown float type {0:?}, expected one of \"f32\" or \"f64\"")]
+pub struct ParseFloatTypeError(String);
+
+impl FromStr for FloatType {
+    type Err = ParseFloatTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f32" => Ok(FloatType::F32),
+            "f64" => Ok(FloatType::F64),
+            _ => Err(ParseFloatTypeError(s.to_owned())),
+        }
+    }
+}
+
+impl Display for FloatType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FloatType::F32 => write!(f, "f32"),
+            FloatType::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// This trait defines the set of numeric types that can be stored in a
+/// [`DynamicVector`](crate::system::DynamicVector) and used in [`Vector`](crate::vector::Vector)
+/// arithmetic. It is refined by [`Float`].
+pub trait Scalar: num::Num + Copy + bytemuck::Pod + Send + Sync + Default + Debug {}
+
+impl Scalar for f32 {}
+impl Scalar for f64 {}
+
+#[cfg(feature = "complex")]
+impl Scalar for num::Complex<f32> {}
+#[cfg(feature = "complex")]
+impl Scalar for num::Complex<f64> {}
+
 /// This trait defines the set of floats that have nice computer properties.
 pub trait Float:
-    num::Float
-    + bytemuck::Pod
-    + Send
-    + Sync
-    + Default
-    + ToPyObject
-    + for<'a> FromPyObject<'a>
-    + Debug
-    + Display
-    + NumCast
+    Scalar + num::Float + ToPyObject + for<'a> FromPyObject<'a> + Display + NumCast
 {
     /// The type of float represented by this type.
     fn float_type() -> FloatType;
@@ -112,3 +192,27 @@ impl Float for f64 {
         FloatType::F64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, FloatType, ParseFloatTypeError};
+
+    #[test]
+    fn test_float_type_from_str() {
+        assert_eq!("f32".parse(), Ok(FloatType::F32));
+        assert_eq!("f64".parse(), Ok(FloatType::F64));
+        assert_eq!(
+            "f128".parse::<FloatType>(),
+            Err(ParseFloatTypeError("f128".to_owned()))
+        );
+        assert!("F32".parse::<FloatType>().is_err());
+        assert!("".parse::<FloatType>().is_err());
+    }
+
+    #[test]
+    fn test_float_type_display_round_trip() {
+        for float_type in [f32::float_type(), f64::float_type()] {
+            assert_eq!(float_type.to_string().parse(), Ok(float_type));
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{fmt::Debug, sync::Mutex};
+
+use rand::{rngs::StdRng, Rng as _, SeedableRng};
+use rand_distr::StandardNormal;
+
+use crate::{rope::RopeMut, Float};
+
+/// A source of random numbers for the noise models. This decouples the noise from the Python
+/// feature: [`RustRng`] is a pure-Rust implementation, while
+/// [`JaxKey`](crate::python::JaxKey) draws from `jax.random`.
+pub trait Rng<T: Float>: Send {
+    /// Draws a sample from the standard normal distribution.
+    fn next_gaussian(&mut self) -> T;
+
+    /// Fills `samples` with independent samples from the standard normal distribution. By
+    /// default, this draws them one at a time with [`Rng::next_gaussian`]; generators with a
+    /// per-call overhead should draw the whole batch at once.
+    fn fill_gaussian(&mut self, samples: &mut [T]) {
+        samples.iter_mut().for_each(|x| *x = self.next_gaussian());
+    }
+}
+
+/// An [`Rng`] backed by a seeded [`StdRng`], so no Python is required. For a given seed, it
+/// draws the same samples as the noise did before the generators became pluggable.
+#[derive(Debug, Clone)]
+pub struct RustRng {
+    /// The underlying random number generator.
+    rng: StdRng,
+}
+
+impl RustRng {
+    /// Creates a [`RustRng`] seeded by `seed`.
+    pub fn seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<T: Float> Rng<T> for RustRng {
+    fn next_gaussian(&mut self) -> T {
+        let sample: f64 = self.rng.sample(StandardNormal);
+        T::from(sample).unwrap()
+    }
+}
+
+/// Additive Gaussian noise with a fixed standard deviation, drawn from a seeded random number
+/// generator so that the perturbations are reproducible. This models the sensor noise on the
+/// observations of a simulator.
+pub struct GaussianNoise<T> {
+    /// The standard deviation of the noise.
+    stddev: T,
+    /// The random number generator the noise is drawn from.
+    rng: Mutex<Box<dyn Rng<T>>>,
+}
+
+impl<T: Debug> Debug for GaussianNoise<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GaussianNoise")
+            .field("stddev", &self.stddev)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Float> GaussianNoise<T> {
+    /// Creates a [`GaussianNoise`] with the given standard deviation, drawn from a [`RustRng`]
+    /// seeded by `seed`.
+    pub fn new(stddev: T, seed: u64) -> Self {
+        Self::with_rng(stddev, RustRng::seed(seed))
+    }
+
+    /// Creates a [`GaussianNoise`] with the given standard deviation, drawn from the given
+    /// random number generator.
+    pub fn with_rng(stddev: T, rng: impl Rng<T> + 'static) -> Self {
+        Self {
+            stddev,
+            rng: Mutex::new(Box::new(rng)),
+        }
+    }
+
+    /// The standard deviation of the noise.
+    pub fn stddev(&self) -> T {
+        self.stddev
+    }
+
+    /// Adds independent noise to every element of the rope. Does nothing if the standard deviation
+    /// is zero, so exact values are preserved.
+    pub fn apply(&self, mut rope: RopeMut<T>) {
+        if self.stddev.is_zero() {
+            return;
+        }
+
+        let mut samples = vec![T::zero(); rope.len()];
+        self.rng.lock().unwrap().fill_gaussian(&mut samples);
+        rope.iter_mut()
+            .zip(samples)
+            .for_each(|(x, sample)| *x = *x + self.stddev * sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng as _, SeedableRng};
+    use rand_distr::StandardNormal;
+
+    use super::{GaussianNoise, Rng, RustRng};
+    use crate::system::DynamicVector;
+
+    #[test]
+    fn test_zero_stddev_is_exact() {
+        let noise = GaussianNoise::new(0., 0);
+        let mut observation = vec![1f64, -2., 3.];
+        noise.apply(observation.get_rope_mut());
+
+        assert_eq!(observation, vec![1., -2., 3.]);
+    }
+
+    #[test]
+    fn test_fixed_seed_is_deterministic() {
+        let perturb = |seed| {
+            let noise = GaussianNoise::new(0.1, seed);
+            let mut observation = vec![1f64, -2., 3.];
+            noise.apply(observation.get_rope_mut());
+            observation
+        };
+
+        assert_ne!(perturb(7), vec![1., -2., 3.]);
+        assert_eq!(perturb(7), perturb(7));
+        assert_ne!(perturb(7), perturb(8));
+    }
+
+    #[test]
+    fn test_rust_rng_is_reproducible() {
+        let draw = |seed| {
+            let mut rng = RustRng::seed(seed);
+            (0..8).map(|_| rng.next_gaussian()).collect::<Vec<f64>>()
+        };
+
+        assert_eq!(draw(11), draw(11));
+        assert_ne!(draw(11), draw(12));
+    }
+
+    #[test]
+    fn test_noise_matches_std_rng() {
+        let noise = GaussianNoise::new(0.1, 7);
+        let mut observation = vec![1f64, -2., 3.];
+        noise.apply(observation.get_rope_mut());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let expected = [1f64, -2., 3.].map(|x| {
+            let sample: f64 = rng.sample(StandardNormal);
+            x + 0.1 * sample
+        });
+        assert_eq!(
+            observation, expected,
+            "Expected the same noise for a given seed as a seeded `StdRng`"
+        );
+    }
+
+    #[test]
+    fn test_fill_gaussian_matches_next_gaussian() {
+        let mut batch = [0f64; 6];
+        Rng::fill_gaussian(&mut RustRng::seed(3), &mut batch);
+
+        let mut rng = RustRng::seed(3);
+        let single = [(); 6].map(|_| Rng::<f64>::next_gaussian(&mut rng));
+        assert_eq!(
+            batch, single,
+            "Expected a batch to hold the samples drawn one at a time"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    rope::{Rope, RopeMut},
+    Float,
+};
+
+/// Tracks running per-component statistics of a stream of observations using Welford's online
+/// algorithm, and standardizes observations to zero mean and unit variance.
+///
+/// A [`RunningNormalizer::default`] is empty. The number of components is fixed by the first call
+/// to [`RunningNormalizer::update`].
+#[derive(Debug, Clone, Default)]
+pub struct RunningNormalizer<T> {
+    /// The number of observations accumulated so far.
+    count: usize,
+    /// The running mean of each component.
+    mean: Vec<T>,
+    /// The running sum of squared deviations from the mean of each component.
+    m2: Vec<T>,
+}
+
+impl<T: Float> RunningNormalizer<T> {
+    /// The number of observations accumulated so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The running mean of each component.
+    pub fn mean(&self) -> &[T] {
+        &self.mean
+    }
+
+    /// The running (population) variance of each component.
+    pub fn variance(&self) -> Vec<T> {
+        let count = T::from(self.count.max(1)).unwrap();
+        self.m2.iter().map(|&m2| m2 / count).collect()
+    }
+
+    /// Accumulates the given observation into the running statistics.
+    ///
+    /// # Panics
+    /// If the observation doesn't have the same length as the previously accumulated ones.
+    pub fn update(&mut self, rope: Rope<T>) {
+        if self.count == 0 {
+            self.mean = vec![T::zero(); rope.len()];
+            self.m2 = vec![T::zero(); rope.len()];
+        }
+        assert_eq!(
+            self.mean.len(),
+            rope.len(),
+            "Expected observation of length {} but got {}",
+            self.mean.len(),
+            rope.len()
+        );
+
+        self.count += 1;
+        let count = T::from(self.count).unwrap();
+        self.mean
+            .iter_mut()
+            .zip(self.m2.iter_mut())
+            .zip(rope.iter())
+            .for_each(|((mean, m2), &x)| {
+                let delta = x - *mean;
+                *mean = *mean + delta / count;
+                *m2 = *m2 + delta * (x - *mean);
+            });
+    }
+
+    /// Standardizes the observation in place using the accumulated statistics. Does nothing until
+    /// at least two observations have been accumulated, since a single observation has no
+    /// variance. Components whose standard deviation is at most `sqrt(epsilon)`, e.g. a constant
+    /// control, are only centred on their mean rather than blown up by dividing by it.
+    ///
+    /// # Panics
+    /// If the observation doesn't have the same length as the accumulated ones.
+    pub fn normalize(&self, rope: &mut RopeMut<T>) {
+        if self.count < 2 {
+            return;
+        }
+        assert_eq!(
+            self.mean.len(),
+            rope.len(),
+            "Expected observation of length {} but got {}",
+            self.mean.len(),
+            rope.len()
+        );
+
+        let count = T::from(self.count).unwrap();
+        let min_std = T::epsilon().sqrt();
+        rope.iter_mut()
+            .zip(self.mean.iter().zip(self.m2.iter()))
+            .for_each(|(x, (&mean, &m2))| {
+                let std = (m2 / count).sqrt();
+                *x = *x - mean;
+                if std > min_std {
+                    *x = *x / std;
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunningNormalizer;
+    use crate::system::DynamicVector;
+
+    #[test]
+    fn test_normalize_zero_mean() {
+        let stream = (0..100)
+            .map(|i| vec![i as f64, 2. * i as f64 + 1., -3.])
+            .collect::<Vec<_>>();
+
+        let mut normalizer = RunningNormalizer::default();
+        for observation in &stream {
+            normalizer.update(observation.get_rope());
+        }
+        assert_eq!(normalizer.count(), 100, "Expected all observations counted");
+
+        let mut sum = [0f64; 3];
+        for observation in &stream {
+            let mut observation = observation.clone();
+            normalizer.normalize(&mut observation.get_rope_mut());
+            sum.iter_mut().zip(observation).for_each(|(s, x)| *s += x);
+        }
+
+        for s in sum {
+            assert!(
+                (s / 100.).abs() < 1e-9,
+                "Expected zero mean, got {}",
+                s / 100.
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_observation_unchanged() {
+        let mut normalizer = RunningNormalizer::default();
+        normalizer.update(vec![1f64, -2.].get_rope());
+
+        let mut observation = vec![5f64, 7.];
+        normalizer.normalize(&mut observation.get_rope_mut());
+        assert_eq!(
+            observation,
+            [5., 7.],
+            "Expected no normalization from a single observation"
+        );
+    }
+
+    #[test]
+    fn test_constant_component_centred() {
+        let mut normalizer = RunningNormalizer::default();
+        for i in 0..4 {
+            normalizer.update(vec![i as f64, 0.5].get_rope());
+        }
+
+        let mut observation = vec![3f64, 0.75];
+        normalizer.normalize(&mut observation.get_rope_mut());
+        assert!(
+            (observation[0] - 1.5 / 1.25f64.sqrt()).abs() < 1e-12,
+            "Expected the varying component to be standardized, got {observation:?}"
+        );
+        assert_eq!(
+            observation[1], 0.25,
+            "Expected the constant component to only be centred, got {observation:?}"
+        );
+    }
+}
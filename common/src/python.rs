@@ -1,13 +1,13 @@
-use std::{fmt::{Debug, Display}, future::Future, ops::Deref, pin::Pin, sync::OnceLock};
+use std::{fmt::{Debug, Display}, future::Future, ops::Deref, path::{Path, PathBuf}, pin::Pin, sync::OnceLock, time::Duration};
 
 use itertools::Itertools;
 use pyo3::{
-    exceptions::PyException,
-    types::{IntoPyDict, PyAnyMethods, PyBytes, PyDict, PyModule},
-    Borrowed, Bound, Py, PyAny, PyResult, Python, ToPyObject,
+    exceptions::{PyException, PyRuntimeError, PyTimeoutError, PyTypeError},
+    types::{IntoPyDict, PyAnyMethods, PyBytes, PyBytesMethods, PyDict, PyModule},
+    Borrowed, Bound, Py, PyAny, PyErr, PyResult, Python, ToPyObject,
 };
 
-use crate::Float;
+use crate::{noise::Rng, vector::Vector, Float};
 
 /// Tries to find the appropriate Python `sys.path` at runtime.
 fn query_shim(py: Python<'_>) -> PyResult<Vec<String>> {
@@ -129,6 +129,80 @@ pub static JAX: GILLazy<Py<PyModule>> =
 pub static NUMPY: GILLazy<Py<PyModule>> =
     GILLazy::new(|py| get_library(py, "numpy").expect("Couldn't load"));
 
+/// The delay before the first retry in [`retry`] and [`retry_async`], doubled after every failed
+/// attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+/// Checks whether a Python error is transient, i.e. whether the failed call might succeed if
+/// retried. These are:
+/// - `RuntimeError`, which includes JAX's `XlaRuntimeError` raised when compilation or execution
+///   fails due to e.g. device contention or exhausted device memory.
+/// - `TimeoutError`.
+fn is_transient(err: &PyErr) -> bool {
+    Python::with_gil(|py| {
+        err.is_instance_of::<PyRuntimeError>(py) || err.is_instance_of::<PyTimeoutError>(py)
+    })
+}
+
+/// Checks whether the given result of the given attempt should be retried, logging the retry.
+fn should_retry<T>(attempt: usize, attempts: usize, result: &PyResult<T>) -> bool {
+    match result {
+        Err(err) if attempt < attempts && is_transient(err) => {
+            log::warn!("Retrying after transient Python error (attempt {attempt}): {err}");
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Calls `f` up to `attempts` times until it succeeds, retrying with exponential backoff while it
+/// fails with a transient error (see [`is_transient`]). Any other error is returned immediately,
+/// as is the last transient error once the attempts are exhausted.
+///
+/// The GIL should not be held while calling this, so that other threads can run during the
+/// backoff. Instead, `f` should acquire it itself. The backoff blocks the current thread, so use
+/// [`retry_async`] from async code instead.
+///
+/// # Panics
+/// If `attempts` is zero.
+pub fn retry<T>(attempts: usize, mut f: impl FnMut() -> PyResult<T>) -> PyResult<T> {
+    assert!(attempts > 0, "Expected at least one attempt");
+
+    let mut delay = RETRY_BASE_DELAY;
+    for attempt in 1.. {
+        let result = f();
+        if !should_retry(attempt, attempts, &result) {
+            return result;
+        }
+        std::thread::sleep(delay);
+        delay *= 2;
+    }
+
+    unreachable!("The loop only exits by returning")
+}
+
+/// The async version of [`retry`], which awaits a timer during the backoff rather than blocking
+/// the thread, so that the executor can make progress on other futures, e.g. the simulator steps
+/// running concurrently with a control computation.
+///
+/// # Panics
+/// If `attempts` is zero.
+pub async fn retry_async<T>(attempts: usize, mut f: impl FnMut() -> PyResult<T>) -> PyResult<T> {
+    assert!(attempts > 0, "Expected at least one attempt");
+
+    let mut delay = RETRY_BASE_DELAY;
+    for attempt in 1.. {
+        let result = f();
+        if !should_retry(attempt, attempts, &result) {
+            return result;
+        }
+        async_std::task::sleep(delay).await;
+        delay *= 2;
+    }
+
+    unreachable!("The loop only exits by returning")
+}
+
 /// Adds some methods to [`Python<'py>`]
 pub trait PythonExt {
     /// Injects the [`set_venv_site_packages`] command
@@ -307,8 +381,45 @@ impl JaxArray {
 
     /// Constructs an instance of [`JaxArray`] from a Rust collection
     pub fn new_1d<T: Float>(data: Vec<T>) -> Self {
+        Self::from_slice(&data)
+    }
+
+    /// Constructs an instance of [`JaxArray`] with the given shape from a flattened, row-major
+    /// slice of floats.
+    ///
+    /// # Panics
+    /// If the length of the slice doesn't match the number of elements of the shape.
+    pub fn new_nd<T: Float>(data: &[T], shape: &[usize]) -> Self {
+        assert_eq!(
+            data.len(),
+            shape.iter().product::<usize>(),
+            "Expected {} elements for an array of shape {shape:?} but got {}",
+            shape.iter().product::<usize>(),
+            data.len()
+        );
+
+        let array = Self::from_slice(data);
+        Python::with_gil_ext(|py| -> PyResult<JaxArray> {
+            let obj = array
+                .obj
+                .bind(py)
+                .call_method1("reshape", (shape.to_vec(),))?
+                .unbind();
+
+            Ok(JaxArray { obj, sleep: None })
+        })
+        .unwrap()
+    }
+
+    /// Constructs an instance of [`JaxArray`] from a [`Vector`] without an intermediate [`Vec`].
+    pub fn from_vector<T: Float, const N: usize>(v: &Vector<T, N>) -> Self {
+        Self::from_slice(v.as_array())
+    }
+
+    /// Constructs a 1D instance of [`JaxArray`] by copying the bytes of a slice of floats.
+    fn from_slice<T: Float>(data: &[T]) -> Self {
         Python::with_gil_ext(|py| -> PyResult<JaxArray> {
-            let byteslice = bytemuck::cast_slice::<_, u8>(&data[..]);
+            let byteslice = bytemuck::cast_slice::<_, u8>(data);
             let pybytes = PyBytes::new_bound(py, byteslice);
 
             let array = py
@@ -330,6 +441,31 @@ impl JaxArray {
         .unwrap()
     }
 
+    /// Copies the data of the array into a flattened [`Vec`] of floats of type `T`.
+    pub fn to_vec<T: Float>(&self) -> Vec<T> {
+        Python::with_gil_ext(|py| -> PyResult<Vec<T>> {
+            let array = NUMPY.bind(py).call_method(
+                "asarray",
+                (self.obj.bind(py),),
+                Some(&[("dtype", T::float_type().r#type())].into_py_dict_bound(py)),
+            )?;
+            let bytes = array.call_method0("ravel")?.call_method0("tobytes")?;
+
+            Ok(bytes
+                .downcast::<PyBytes>()?
+                .as_bytes()
+                .chunks_exact(std::mem::size_of::<T>())
+                .map(bytemuck::pod_read_unaligned)
+                .collect())
+        })
+        .unwrap()
+    }
+
+    /// Gets the shape of the array.
+    pub fn shape(&self) -> Vec<usize> {
+        Python::with_gil_ext(|py| self.obj.bind(py).getattr("shape")?.extract()).unwrap()
+    }
+
     /// Gets inner [`Py<PyAny>`].
     pub fn into_inner(self) -> Py<PyAny> {
         self.obj
@@ -443,3 +579,221 @@ impl JaxKey {
         }
     }
 }
+
+impl<T: Float> Rng<T> for JaxKey {
+    fn next_gaussian(&mut self) -> T {
+        let mut sample = [T::zero()];
+        self.fill_gaussian(&mut sample);
+        let [sample] = sample;
+
+        sample
+    }
+
+    /// Splits off a subkey to draw the whole batch with a single call to `jax.random.normal`, and
+    /// advances the key.
+    fn fill_gaussian(&mut self, samples: &mut [T]) {
+        let [key, subkey] = self.split();
+        *self = key;
+
+        let batch = Python::with_gil_ext(|py| -> PyResult<Vec<f64>> {
+            JAX.bind(py)
+                .getattr("random")?
+                .call_method1("normal", (subkey.key.bind(py), (samples.len(),)))?
+                .call_method0("tolist")?
+                .extract()
+        })
+        .expect("Failed to draw samples with `jax.random.normal`");
+
+        for (x, sample) in samples.iter_mut().zip(batch) {
+            *x = T::from(sample).unwrap();
+        }
+    }
+}
+
+/// Where the Python code of a model (e.g. an agent or a state predictor) is loaded from, so that
+/// experiments can use external trained models without recompiling.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PythonModelSource {
+    /// The script embedded in the crate of the component.
+    #[default]
+    Embedded,
+    /// A Python file at the given path.
+    File(PathBuf),
+    /// The given Python source code.
+    Source(String),
+}
+
+impl PythonModelSource {
+    /// The methods the model class must expose to be driven by the experiment components.
+    const REQUIRED_METHODS: [&'static str; 2] = ["init_state", "step"];
+
+    /// Loads the model class called `class_name` from this source, validating that it exposes
+    /// the `init_state` and `step` methods. The `embedded` code of the component, along with its
+    /// file name, is used for [`PythonModelSource::Embedded`].
+    pub fn load_class<'py>(
+        &self,
+        py: Python<'py>,
+        (embedded, embedded_file): (&str, &str),
+        class_name: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (code, file_name) = match self {
+            Self::Embedded => (embedded.to_string(), embedded_file.to_string()),
+            Self::File(path) => (
+                std::fs::read_to_string(path)?,
+                path.to_string_lossy().into_owned(),
+            ),
+            Self::Source(code) => (code.clone(), format!("<{class_name}>")),
+        };
+        let module_name = Path::new(&file_name).file_stem().map_or_else(
+            || class_name.to_string(),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+
+        let class =
+            PyModule::from_code_bound(py, &code, &file_name, &module_name)?.getattr(class_name)?;
+        for method in Self::REQUIRED_METHODS {
+            let defined = class
+                .getattr(method)
+                .is_ok_and(|method| method.is_callable());
+            if !defined {
+                return Err(PyTypeError::new_err(format!(
+                    "Model class {class_name} from {file_name} doesn't define a `{method}` method"
+                )));
+            }
+        }
+
+        Ok(class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use pyo3::exceptions::{PyRuntimeError, PyValueError};
+
+    use pyo3::{types::PyAnyMethods, Python};
+
+    use super::{retry, retry_async, JaxArray, PythonModelSource};
+    use crate::vector::Vector;
+
+    #[test]
+    fn test_retry_transient() {
+        pyo3::prepare_freethreaded_python();
+        let calls = Cell::new(0);
+
+        let result = retry(5, || {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 | 2 => Err(PyRuntimeError::new_err("device busy")),
+                n => Ok(n),
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3, "Expected success on the third attempt");
+        assert_eq!(calls.get(), 3, "Expected no calls after the success");
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        pyo3::prepare_freethreaded_python();
+        let calls = Cell::new(0);
+        let result = retry(2, || -> pyo3::PyResult<()> {
+            calls.set(calls.get() + 1);
+            Err(PyRuntimeError::new_err("device busy"))
+        });
+        assert!(
+            result.is_err(),
+            "Expected the last error once out of attempts"
+        );
+        assert_eq!(calls.get(), 2, "Expected every attempt to be used");
+
+        calls.set(0);
+        let result = retry(5, || -> pyo3::PyResult<()> {
+            calls.set(calls.get() + 1);
+            Err(PyValueError::new_err("bad input"))
+        });
+        assert!(result.is_err(), "Expected the non-transient error");
+        assert_eq!(
+            calls.get(),
+            1,
+            "Expected non-transient errors not to be retried"
+        );
+    }
+
+    #[test]
+    fn test_retry_async_transient() {
+        pyo3::prepare_freethreaded_python();
+        let calls = Cell::new(0);
+
+        let result = futures::executor::block_on(retry_async(5, || {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 | 2 => Err(PyRuntimeError::new_err("device busy")),
+                n => Ok(n),
+            }
+        }));
+
+        assert_eq!(result.unwrap(), 3, "Expected success on the third attempt");
+        assert_eq!(calls.get(), 3, "Expected no calls after the success");
+    }
+
+    #[test]
+    #[ignore = "requires a Python environment with JAX installed"]
+    fn test_from_vector_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        let v = Vector::<f32, 12>::from_idx(|i| i as f32 * 0.5 - 1.);
+
+        assert_eq!(JaxArray::from_vector(&v).to_vec::<f32>(), v.as_array());
+    }
+
+    #[test]
+    fn test_load_model_from_source() {
+        pyo3::prepare_freethreaded_python();
+        let source = PythonModelSource::Source(
+            r#"
+class Model:
+    @staticmethod
+    def init_state(scale):
+        return Model()
+
+    def step(self, x):
+        return self, 2 * x
+
+class Incomplete:
+    @staticmethod
+    def init_state():
+        return Incomplete()
+"#
+            .to_string(),
+        );
+
+        Python::with_gil(|py| {
+            let class = source.load_class(py, ("", "model.py"), "Model").unwrap();
+            let model = class.call_method1("init_state", (1,)).unwrap();
+            let (_, y) = model
+                .call_method1("step", (21,))
+                .unwrap()
+                .extract::<(pyo3::Bound<pyo3::PyAny>, i64)>()
+                .unwrap();
+            assert_eq!(y, 42);
+
+            assert!(
+                source
+                    .load_class(py, ("", "model.py"), "Incomplete")
+                    .is_err(),
+                "Expected a class without a `step` method to be rejected"
+            );
+            assert!(
+                source.load_class(py, ("", "model.py"), "Missing").is_err(),
+                "Expected a missing class to be rejected"
+            );
+            assert!(
+                PythonModelSource::Embedded
+                    .load_class(py, ("class Model: pass", "model.py"), "Model")
+                    .is_err(),
+                "Expected the embedded code to be used"
+            );
+        });
+    }
+}
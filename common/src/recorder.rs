@@ -0,0 +1,149 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    rope::Rope,
+    system::{DynamicVector, System},
+    Float,
+};
+
+/// Records the trajectory of a simulated system, i.e. its states along with the control signals
+/// applied to them, so that the control can be replayed or analyzed afterwards.
+pub trait TrajectoryRecorder<T: Float, S: System<T>> {
+    /// Records the system state at the given time.
+    fn record_state(&mut self, time: T, state: &S::SystemState) -> io::Result<()>;
+
+    /// Records the control signal applied from the given time on.
+    fn record_control(&mut self, time: T, control: &S::ControlSignal) -> io::Result<()>;
+
+    /// Flushes any buffered records. Does nothing by default.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`TrajectoryRecorder`] writing the states and controls as interleaved CSV records, each
+/// starting with its kind (`state` or `control`) and time, followed by the flattened values.
+pub struct CsvRecorder<W: Write> {
+    /// The writer the records are written to.
+    writer: W,
+}
+
+impl<W: Write> CsvRecorder<W> {
+    /// Creates a [`CsvRecorder`] writing to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Gets back the writer the records were written to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a header row naming the columns of the records, i.e. `kind`, `time`, and the given
+    /// labels of the values, e.g. [`SimulatorInterface::observation_labels`].
+    ///
+    /// [`SimulatorInterface::observation_labels`]:
+    /// crate::interfaces::SimulatorInterface::observation_labels
+    pub fn write_header(&mut self, labels: &[String]) -> io::Result<()> {
+        write!(self.writer, "kind, time")?;
+        for label in labels {
+            write!(self.writer, ", {label}")?;
+        }
+        writeln!(self.writer)
+    }
+
+    /// Writes a single record of the given kind.
+    fn write_record<T: Float>(&mut self, kind: &str, time: T, values: Rope<T>) -> io::Result<()> {
+        write!(self.writer, "{kind}, {time:?}")?;
+        for value in values.iter() {
+            write!(self.writer, ", {value:?}")?;
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl CsvRecorder<BufWriter<File>> {
+    /// Creates a [`CsvRecorder`] writing to a new file at the given path.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<T: Float, S: System<T>, W: Write> TrajectoryRecorder<T, S> for CsvRecorder<W> {
+    fn record_state(&mut self, time: T, state: &S::SystemState) -> io::Result<()> {
+        self.write_record("state", time, state.get_rope())
+    }
+
+    fn record_control(&mut self, time: T, control: &S::ControlSignal) -> io::Result<()> {
+        self.write_record("control", time, control.get_rope())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvRecorder, TrajectoryRecorder};
+    use crate::system::System;
+
+    /// A minimal system with a two-dimensional state and a scalar control signal.
+    struct PlanarSystem;
+
+    impl System<f64> for PlanarSystem {
+        const CONTROL_SIGNAL_SIZE: usize = 1;
+        const CONTROL_PARAMS_SIZE: usize = 1;
+        const LATENT_STATE_SIZE: usize = 2;
+        const SYSTEM_STATE_SIZE: usize = 2;
+        const OBSERVABLE_STATE_SIZE: usize = 1;
+
+        type SystemConfiguration = ();
+        type DynamicsConfiguration = ();
+
+        type SystemState = [f64; 2];
+        type LatentState = [f64; 2];
+        type ControlParams = f64;
+        type ControlSignal = f64;
+        type SystemObservation = f64;
+    }
+
+    #[test]
+    fn test_csv_records_interleaved() {
+        let mut recorder = CsvRecorder::new(Vec::new());
+        for (time, state, control) in [(0., [1., 2.], 0.5), (0.25, [1.5, -2.], -1.)] {
+            TrajectoryRecorder::<f64, PlanarSystem>::record_state(&mut recorder, time, &state)
+                .unwrap();
+            TrajectoryRecorder::<f64, PlanarSystem>::record_control(&mut recorder, time, &control)
+                .unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(recorder.into_inner()).unwrap(),
+            "state, 0.0, 1.0, 2.0\n\
+             control, 0.0, 0.5\n\
+             state, 0.25, 1.5, -2.0\n\
+             control, 0.25, -1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_header() {
+        let mut recorder = CsvRecorder::new(Vec::new());
+        recorder
+            .write_header(&["pos_x".to_string(), "pos_y".to_string()])
+            .unwrap();
+        TrajectoryRecorder::<f64, PlanarSystem>::record_state(&mut recorder, 0.5, &[1., 2.])
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(recorder.into_inner()).unwrap(),
+            "kind, time, pos_x, pos_y\n\
+             state, 0.5, 1.0, 2.0\n"
+        );
+    }
+}
@@ -0,0 +1,664 @@
+use std::{
+    io::{self, Read, Stdin, Stdout, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{mpsc, Arc, Mutex},
+};
+
+use crate::{
+    interfaces::{DriverInterface, SimulatorInterface},
+    system::System,
+    wire::{WireError, WireFormat},
+    Float,
+};
+
+/// The error returned when serving or calling a remote experiment component fails.
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteError {
+    /// The transport failed to send or receive a message.
+    #[error("Failed to transfer a message: {0}")]
+    Io(#[from] io::Error),
+    /// A message couldn't be decoded.
+    #[error("Failed to decode a message: {0}")]
+    Wire(#[from] WireError),
+    /// The peer requested a call that isn't part of the protocol.
+    #[error("Unknown remote call {0}")]
+    UnknownCall(u8),
+}
+
+/// A bidirectional channel of whole messages between two processes (or threads) running
+/// different experiment components. The calls block, so the remote components run them on the
+/// blocking thread pool of [`blocking`], rather than on the executor of the experiment.
+pub trait Transport {
+    /// Sends a message to the peer.
+    fn send(&mut self, message: &[u8]) -> io::Result<()>;
+
+    /// Blocks until a message is received from the peer. Fails with
+    /// [`io::ErrorKind::UnexpectedEof`] once the peer has hung up.
+    fn recv(&mut self) -> io::Result<Vec<u8>>;
+}
+
+/// The default largest length of a message received by a [`StreamTransport`], in bytes.
+pub const DEFAULT_MAX_MESSAGE_LEN: usize = 1 << 28;
+
+/// A [`Transport`] over a pair of byte streams, framing every message by its length as a
+/// little-endian `u64`.
+pub struct StreamTransport<R, W> {
+    /// The stream that messages are received from.
+    reader: R,
+    /// The stream that messages are sent to.
+    writer: W,
+    /// The largest length of a received message, so that a corrupt or malicious length doesn't
+    /// make the transport allocate an arbitrary amount of memory.
+    max_message_len: usize,
+}
+
+impl<R: Read, W: Write> StreamTransport<R, W> {
+    /// Creates a [`StreamTransport`] receiving from `reader` and sending to `writer`, accepting
+    /// messages of up to [`DEFAULT_MAX_MESSAGE_LEN`] bytes.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            max_message_len: DEFAULT_MAX_MESSAGE_LEN,
+        }
+    }
+
+    /// Accepts messages of up to the given length in bytes instead, e.g. for large lattices.
+    pub fn with_max_message_len(mut self, max_message_len: usize) -> Self {
+        self.max_message_len = max_message_len;
+        self
+    }
+}
+
+impl StreamTransport<Stdin, Stdout> {
+    /// Creates a [`StreamTransport`] over the standard input and output of this process, e.g. to
+    /// serve a component from a child process.
+    pub fn stdio() -> Self {
+        Self::new(io::stdin(), io::stdout())
+    }
+}
+
+/// A [`Transport`] over a TCP connection.
+pub type TcpTransport = StreamTransport<TcpStream, TcpStream>;
+
+impl TcpTransport {
+    /// Connects to a peer listening at the given address.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::from_stream(TcpStream::connect(addr)?)
+    }
+
+    /// Creates a [`TcpTransport`] over an established connection, e.g. one accepted by a
+    /// [`TcpListener`](std::net::TcpListener).
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        // The messages are small and answered one by one, so they shouldn't wait to be batched.
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream.try_clone()?, stream))
+    }
+}
+
+impl<R: Read, W: Write> Transport for StreamTransport<R, W> {
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        self.writer
+            .write_all(&(message.len() as u64).to_le_bytes())?;
+        self.writer.write_all(message)?;
+        self.writer.flush()
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0; std::mem::size_of::<u64>()];
+        self.reader.read_exact(&mut len)?;
+        let len = usize::try_from(u64::from_le_bytes(len))
+            .ok()
+            .filter(|&len| len <= self.max_message_len)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Message longer than the limit of {} bytes",
+                        self.max_message_len
+                    ),
+                )
+            })?;
+        let mut message = vec![0; len];
+        self.reader.read_exact(&mut message)?;
+
+        Ok(message)
+    }
+}
+
+/// An in-memory [`Transport`] between two threads of the same process.
+pub struct ChannelTransport {
+    /// The channel that messages are sent to.
+    sender: mpsc::Sender<Vec<u8>>,
+    /// The channel that messages are received from.
+    receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    /// Creates the two connected ends of an in-memory transport.
+    pub fn pair() -> (Self, Self) {
+        let (sender_a, receiver_b) = mpsc::channel();
+        let (sender_b, receiver_a) = mpsc::channel();
+
+        (
+            Self {
+                sender: sender_a,
+                receiver: receiver_a,
+            },
+            Self {
+                sender: sender_b,
+                receiver: receiver_b,
+            },
+        )
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        self.sender
+            .send(message.to_vec())
+            .map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        self.receiver
+            .recv()
+            .map_err(|_| io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+/// Receives the first message of the next call, or [`None`] if the peer has hung up.
+fn recv_call(transport: &mut impl Transport) -> Result<Option<Vec<u8>>, RemoteError> {
+    match transport.recv() {
+        Ok(message) => Ok(Some(message)),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Sends a number of elements that follow as separate messages.
+fn send_len(transport: &mut impl Transport, len: usize) -> io::Result<()> {
+    transport.send(&(len as u64).to_le_bytes())
+}
+
+/// Receives a number of elements sent by [`send_len`].
+fn recv_len(transport: &mut impl Transport) -> Result<usize, RemoteError> {
+    let message = transport.recv()?;
+    let len = message
+        .try_into()
+        .map_err(|message: Vec<u8>| WireError::Length {
+            expected: std::mem::size_of::<u64>(),
+            actual: message.len(),
+        })?;
+
+    Ok(u64::from_le_bytes(len) as usize)
+}
+
+/// Runs a blocking exchange of messages with the peer on the blocking thread pool, so that
+/// waiting for the peer doesn't block the executor, and with it e.g. the simulator steps running
+/// concurrently with a remote control computation. The messages are exchanged as bytes, since
+/// the exchange has to be [`Send`] and `'static`.
+async fn unblock_exchange<Tr, R>(
+    transport: &Arc<Mutex<Tr>>,
+    exchange: impl FnOnce(&mut Tr) -> Result<R, RemoteError> + Send + 'static,
+) -> Result<R, RemoteError>
+where
+    Tr: Send + 'static,
+    R: Send + 'static,
+{
+    let transport = transport.clone();
+    blocking::unblock(move || exchange(&mut transport.lock().unwrap())).await
+}
+
+/// A [`DriverInterface`] that forwards every call over a [`Transport`] to a driver served by
+/// [`serve_driver`], e.g. in another process.
+///
+/// # Panics
+/// The driver interface is infallible, so the calls panic if the transport fails or the peer
+/// answers with a malformed message.
+pub struct RemoteDriver<Tr> {
+    /// The transport to the served driver.
+    transport: Arc<Mutex<Tr>>,
+}
+
+impl<Tr: Transport> RemoteDriver<Tr> {
+    /// Creates a [`RemoteDriver`] calling the driver at the other end of the transport.
+    pub fn new(transport: Tr) -> Self {
+        Self {
+            transport: Arc::new(Mutex::new(transport)),
+        }
+    }
+}
+
+impl<T, S, Tr> DriverInterface<T, S> for RemoteDriver<Tr>
+where
+    T: Float,
+    S: System<T>,
+    S::LatentState: WireFormat,
+    S::ControlParams: WireFormat,
+    Tr: Transport + Send + 'static,
+{
+    async fn compute_controls(
+        &self,
+        state_estimate: S::LatentState,
+        dynamics_loss: T,
+    ) -> S::ControlParams {
+        let state_estimate = state_estimate.to_bytes();
+        let dynamics_loss = dynamics_loss.to_bytes();
+        let controls = unblock_exchange(&self.transport, move |transport| {
+            transport.send(&state_estimate)?;
+            transport.send(&dynamics_loss)?;
+            Ok(transport.recv()?)
+        })
+        .await
+        .and_then(|controls| Ok(S::ControlParams::from_bytes(&controls)?));
+
+        controls.unwrap_or_else(|err| panic!("Remote driver call failed: {err}"))
+    }
+}
+
+/// Answers the calls of a [`RemoteDriver`] at the other end of the transport with the given
+/// driver, until the peer hangs up.
+pub async fn serve_driver<T, S, D>(
+    driver: &D,
+    transport: &mut impl Transport,
+) -> Result<(), RemoteError>
+where
+    T: Float,
+    S: System<T>,
+    S::LatentState: WireFormat,
+    S::ControlParams: WireFormat,
+    D: DriverInterface<T, S>,
+{
+    while let Some(state_estimate) = recv_call(transport)? {
+        let state_estimate = S::LatentState::from_bytes(&state_estimate)?;
+        let dynamics_loss = T::from_bytes(&transport.recv()?)?;
+
+        let controls = driver.compute_controls(state_estimate, dynamics_loss).await;
+        transport.send(&controls.to_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// The calls of a [`RemoteSimulator`], sent as a single byte ahead of their arguments.
+#[derive(Debug, Clone, Copy)]
+#[repr(u8)]
+enum SimulatorCall {
+    /// [`SimulatorInterface::get_observations`]
+    Observations,
+    /// [`SimulatorInterface::update`]
+    Update,
+    /// [`SimulatorInterface::get_dynamics_loss`]
+    DynamicsLoss,
+    /// [`SimulatorInterface::get_time`]
+    Time,
+}
+
+impl SimulatorCall {
+    /// All the calls, in the order of their byte values.
+    const ALL: [Self; 4] = [
+        Self::Observations,
+        Self::Update,
+        Self::DynamicsLoss,
+        Self::Time,
+    ];
+}
+
+/// A [`SimulatorInterface`] that forwards every call over a [`Transport`] to a simulator served
+/// by [`serve_simulator`], e.g. in another process. The served simulator is updated with its own
+/// instance of the system.
+///
+/// # Panics
+/// The simulator interface is infallible, so the calls panic if the transport fails or the peer
+/// answers with a malformed message.
+pub struct RemoteSimulator<Tr> {
+    /// The transport to the served simulator.
+    transport: Arc<Mutex<Tr>>,
+}
+
+impl<Tr: Transport + Send + 'static> RemoteSimulator<Tr> {
+    /// Creates a [`RemoteSimulator`] calling the simulator at the other end of the transport.
+    pub fn new(transport: Tr) -> Self {
+        Self {
+            transport: Arc::new(Mutex::new(transport)),
+        }
+    }
+
+    /// Sends the given call to the served simulator and lets `f` exchange its arguments and
+    /// results, on the blocking thread pool.
+    async fn call<R: Send + 'static>(
+        &self,
+        call: SimulatorCall,
+        f: impl FnOnce(&mut Tr) -> Result<R, RemoteError> + Send + 'static,
+    ) -> Result<R, RemoteError> {
+        unblock_exchange(&self.transport, move |transport| {
+            transport.send(&[call as u8])?;
+            f(transport)
+        })
+        .await
+    }
+}
+
+/// Panics on a failed call of a [`RemoteSimulator`], since the simulator interface is
+/// infallible.
+fn expect_call<R>(call: SimulatorCall, result: Result<R, RemoteError>) -> R {
+    result.unwrap_or_else(|err| panic!("Remote simulator call {call:?} failed: {err}"))
+}
+
+impl<T, S, Tr> SimulatorInterface<T, S> for RemoteSimulator<Tr>
+where
+    T: Float,
+    S: System<T>,
+    S::ControlSignal: WireFormat,
+    S::SystemObservation: WireFormat,
+    Tr: Transport + Send + 'static,
+{
+    async fn get_observations(&self) -> Vec<S::SystemObservation> {
+        let call = SimulatorCall::Observations;
+        let observations = self
+            .call(call, |transport| {
+                (0..recv_len(transport)?)
+                    .map(|_| Ok(transport.recv()?))
+                    .collect::<Result<Vec<_>, RemoteError>>()
+            })
+            .await
+            .and_then(|observations| {
+                observations
+                    .iter()
+                    .map(|observation| Ok(S::SystemObservation::from_bytes(observation)?))
+                    .collect()
+            });
+
+        expect_call(call, observations)
+    }
+
+    async fn update(&mut self, _system: &S, dt: T, control_signal: &S::ControlSignal) {
+        let call = SimulatorCall::Update;
+        let dt = dt.to_bytes();
+        let control_signal = control_signal.to_bytes();
+        let updated = self
+            .call(call, move |transport| {
+                transport.send(&dt)?;
+                transport.send(&control_signal)?;
+                // Waits for the update to finish, so that later calls see the updated state.
+                transport.recv()?;
+                Ok(())
+            })
+            .await;
+
+        expect_call(call, updated)
+    }
+
+    async fn get_dynamics_loss(&self) -> T {
+        let call = SimulatorCall::DynamicsLoss;
+        let dynamics_loss = self
+            .call(call, |transport| Ok(transport.recv()?))
+            .await
+            .and_then(|dynamics_loss| Ok(T::from_bytes(&dynamics_loss)?));
+
+        expect_call(call, dynamics_loss)
+    }
+
+    /// As the interface requires the time synchronously, this call blocks the executor until the
+    /// served simulator answers.
+    fn get_time(&self) -> T {
+        let call = SimulatorCall::Time;
+        let mut transport = self.transport.lock().unwrap();
+        let time = transport
+            .send(&[call as u8])
+            .and_then(|()| transport.recv())
+            .map_err(RemoteError::from)
+            .and_then(|time| Ok(T::from_bytes(&time)?));
+
+        expect_call(call, time)
+    }
+}
+
+/// Answers the calls of a [`RemoteSimulator`] at the other end of the transport with the given
+/// simulator, until the peer hangs up.
+pub async fn serve_simulator<T, S, SIM>(
+    system: &S,
+    simulator: &mut SIM,
+    transport: &mut impl Transport,
+) -> Result<(), RemoteError>
+where
+    T: Float,
+    S: System<T>,
+    S::ControlSignal: WireFormat,
+    S::SystemObservation: WireFormat,
+    SIM: SimulatorInterface<T, S>,
+{
+    while let Some(call) = recv_call(transport)? {
+        let call = match call[..] {
+            [call] => SimulatorCall::ALL
+                .get(call as usize)
+                .ok_or(RemoteError::UnknownCall(call))?,
+            _ => {
+                return Err(WireError::Length {
+                    expected: 1,
+                    actual: call.len(),
+                }
+                .into())
+            }
+        };
+
+        match call {
+            SimulatorCall::Observations => {
+                let observations = simulator.get_observations().await;
+                send_len(transport, observations.len())?;
+                for observation in &observations {
+                    transport.send(&observation.to_bytes())?;
+                }
+            }
+            SimulatorCall::Update => {
+                let dt = T::from_bytes(&transport.recv()?)?;
+                let control_signal = S::ControlSignal::from_bytes(&transport.recv()?)?;
+                simulator.update(system, dt, &control_signal).await;
+                transport.send(&[])?;
+            }
+            SimulatorCall::DynamicsLoss => {
+                transport.send(&simulator.get_dynamics_loss().await.to_bytes())?
+            }
+            SimulatorCall::Time => transport.send(&simulator.get_time().to_bytes())?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use futures::executor::block_on;
+
+    use super::{
+        serve_driver, serve_simulator, ChannelTransport, RemoteDriver, RemoteSimulator,
+        StreamTransport, TcpTransport, Transport,
+    };
+    use crate::{
+        interfaces::{DriverInterface, SimulatorInterface},
+        system::System,
+    };
+
+    /// A minimal system whose messages can all be sent over a transport.
+    struct WireSystem;
+
+    impl System<f64> for WireSystem {
+        const CONTROL_SIGNAL_SIZE: usize = 1;
+        const CONTROL_PARAMS_SIZE: usize = 2;
+        const LATENT_STATE_SIZE: usize = 1;
+        const SYSTEM_STATE_SIZE: usize = 1;
+        const OBSERVABLE_STATE_SIZE: usize = 1;
+
+        type SystemConfiguration = ();
+        type DynamicsConfiguration = ();
+
+        type SystemState = f64;
+        type LatentState = f64;
+        type ControlParams = Vec<f64>;
+        type ControlSignal = Vec<f64>;
+        type SystemObservation = f64;
+    }
+
+    /// A driver that returns its inputs as the controls.
+    struct EchoDriver;
+
+    impl DriverInterface<f64, WireSystem> for EchoDriver {
+        async fn compute_controls(&self, state_estimate: f64, dynamics_loss: f64) -> Vec<f64> {
+            vec![state_estimate, dynamics_loss]
+        }
+    }
+
+    /// A simulator whose time moves by the timestep scaled by the control signal.
+    struct ScaledTimeSimulator {
+        /// The current time.
+        time: f64,
+    }
+
+    impl SimulatorInterface<f64, WireSystem> for ScaledTimeSimulator {
+        async fn get_observations(&self) -> Vec<f64> {
+            vec![0., self.time]
+        }
+
+        async fn update(&mut self, _system: &WireSystem, dt: f64, control_signal: &Vec<f64>) {
+            self.time += dt * control_signal[0];
+        }
+
+        async fn get_dynamics_loss(&self) -> f64 {
+            -self.time
+        }
+
+        fn get_time(&self) -> f64 {
+            self.time
+        }
+    }
+
+    #[test]
+    fn test_remote_driver() {
+        let (client, mut server) = ChannelTransport::pair();
+        let server = thread::spawn(move || {
+            block_on(serve_driver::<f64, WireSystem, _>(&EchoDriver, &mut server))
+        });
+
+        let driver = RemoteDriver::new(client);
+        for (state_estimate, dynamics_loss) in [(1.5, 0.25), (-2., 8.)] {
+            let controls = block_on(DriverInterface::<f64, WireSystem>::compute_controls(
+                &driver,
+                state_estimate,
+                dynamics_loss,
+            ));
+            assert_eq!(controls, [state_estimate, dynamics_loss]);
+        }
+
+        drop(driver);
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_simulator() {
+        let (client, mut server) = ChannelTransport::pair();
+        let server = thread::spawn(move || {
+            let mut simulator = ScaledTimeSimulator { time: 0. };
+            block_on(serve_simulator(&WireSystem, &mut simulator, &mut server))
+        });
+
+        let mut simulator = RemoteSimulator::new(client);
+        block_on(async {
+            simulator.update(&WireSystem, 0.5, &vec![3.]).await;
+            assert_eq!(
+                SimulatorInterface::<f64, WireSystem>::get_time(&simulator),
+                1.5
+            );
+            assert_eq!(
+                SimulatorInterface::<f64, WireSystem>::get_observations(&simulator).await,
+                [0., 1.5]
+            );
+            assert_eq!(
+                SimulatorInterface::<f64, WireSystem>::get_dynamics_loss(&simulator).await,
+                -1.5
+            );
+        });
+
+        drop(simulator);
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_tcp_transport() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut transport = TcpTransport::from_stream(listener.accept().unwrap().0).unwrap();
+            let message = transport.recv().unwrap();
+            transport.send(&message).unwrap();
+            transport.send(&[]).unwrap();
+        });
+
+        let mut transport = TcpTransport::connect(addr).unwrap();
+        transport.send(&[1, 2, 3]).unwrap();
+        assert_eq!(transport.recv().unwrap(), [1, 2, 3]);
+        assert_eq!(transport.recv().unwrap(), []);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_remote_driver_doesnt_block_executor() {
+        let (client, mut server) = ChannelTransport::pair();
+        let (release, released) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let state_estimate = server.recv().unwrap();
+            let dynamics_loss = server.recv().unwrap();
+            // Only answer once the client executor has made progress on something else.
+            let unblocked = released.recv_timeout(Duration::from_secs(5)).is_ok();
+            server.send(&state_estimate).unwrap();
+            server.send(&dynamics_loss).unwrap();
+            unblocked
+        });
+
+        let driver = RemoteDriver::new(client);
+        let other_ran = AtomicBool::new(false);
+        // A single-threaded executor, on which a blocking call would starve the other future.
+        block_on(futures::future::join(
+            DriverInterface::<f64, WireSystem>::compute_controls(&driver, 1., 2.),
+            async {
+                other_ran.store(true, Ordering::SeqCst);
+                release.send(()).unwrap();
+            },
+        ));
+
+        assert!(other_ran.load(Ordering::SeqCst));
+        assert!(
+            server.join().unwrap(),
+            "Expected the executor to make progress while waiting for the remote driver"
+        );
+    }
+
+    #[test]
+    fn test_stream_transport_max_message_len() {
+        let mut frame = 1024u64.to_le_bytes().to_vec();
+        frame.extend([0; 1024]);
+
+        let mut transport = StreamTransport::new(&frame[..], Vec::new()).with_max_message_len(16);
+        let err = transport.recv().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            io::ErrorKind::InvalidData,
+            "Expected a message over the limit to be rejected, got {err}"
+        );
+
+        let mut transport = StreamTransport::new(&frame[..], Vec::new());
+        assert_eq!(transport.recv().unwrap().len(), 1024);
+    }
+}
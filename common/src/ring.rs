@@ -0,0 +1,36 @@
+/// Computes the indices of `DEPTH` slots of a ring buffer with capacity `cap`, starting at
+/// `offset` and advancing by `step` slots at a time, wrapping around at the end of the buffer.
+/// Walking backwards by `n` slots is done with a `step` of `cap - n`, so no signed arithmetic is
+/// needed.
+///
+/// For example, a buffer of `DEPTH + 1` states whose newest entry is at `offset` gives its `DEPTH`
+/// older states in chronological order with `ring_indices::<DEPTH>(offset + 1, 1, DEPTH + 1)`.
+pub fn ring_indices<const DEPTH: usize>(offset: usize, step: usize, cap: usize) -> [usize; DEPTH] {
+    debug_assert!(cap > 0, "Expected a ring buffer with a positive capacity");
+    std::array::from_fn(|i| (offset + i * step) % cap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ring_indices;
+
+    #[test]
+    fn test_ring_indices_forwards() {
+        assert_eq!(ring_indices::<4>(0, 1, 5), [0, 1, 2, 3]);
+        assert_eq!(ring_indices::<4>(3, 1, 5), [3, 4, 0, 1]);
+        assert_eq!(ring_indices::<4>(5, 1, 5), [0, 1, 2, 3]);
+        assert_eq!(ring_indices::<3>(4, 2, 7), [4, 6, 1]);
+    }
+
+    #[test]
+    fn test_ring_indices_backwards() {
+        for offset in 0..7 {
+            assert_eq!(
+                ring_indices::<3>(offset, 7 - 2, 7),
+                [offset, (offset + 5) % 7, (offset + 3) % 7],
+                "Expected every other slot backwards from {offset}"
+            );
+        }
+        assert_eq!(ring_indices::<4>(1, 4, 5), [1, 0, 4, 3]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::{Index, IndexMut};
+use std::ops::{Add, Index, IndexMut};
 
 use smallvec::{SmallVec, ToSmallVec};
 
@@ -8,8 +8,10 @@ const SMALLVEC_LEN: usize = 4;
 /// A "rope" of immutable slices.
 #[derive(Debug, Clone)]
 pub struct Rope<'a, S> {
-    /// Offsets into the slices.
+    /// Offsets into the slices. See [`slice_index`] for the invariants.
     offsets: SmallVec<[usize; SMALLVEC_LEN]>,
+    /// The total length of the slices.
+    len: usize,
     /// The immutable slices in question.
     data: SmallVec<[&'a [S]; SMALLVEC_LEN]>,
 }
@@ -17,37 +19,56 @@ pub struct Rope<'a, S> {
 /// A "rope" of mutable slices.
 #[derive(Debug)]
 pub struct RopeMut<'a, S> {
-    /// Offsets into the slices.
+    /// Offsets into the slices. See [`slice_index`] for the invariants.
     offsets: SmallVec<[usize; SMALLVEC_LEN]>,
+    /// The total length of the slices.
+    len: usize,
     /// The mutable slices in question.
     data: SmallVec<[&'a mut [S]; SMALLVEC_LEN]>,
 }
 
+/// Finds the index of the slice containing the element at `index`, given the offsets of the
+/// slices in a rope.
+///
+/// The offsets are non-decreasing, with the `i`-th offset being the total length of the slices
+/// before the `i`-th slice. Empty slices share their offset with the following slice, so the last
+/// slice whose offset is at most `index` is chosen, which is always non-empty when `index` is in
+/// bounds.
+///
+/// # Panics
+/// If the rope has no slices, or is empty.
+fn slice_index(offsets: &[usize], index: usize) -> usize {
+    offsets
+        .partition_point(|&i| i <= index)
+        .checked_sub(1)
+        .expect("Expected a non-empty rope to index into")
+}
+
+/// Computes the offsets of slices with the given lengths in a rope, along with their total length.
+fn offsets(lens: impl Iterator<Item = usize>) -> (SmallVec<[usize; SMALLVEC_LEN]>, usize) {
+    let mut len = 0;
+    let offsets = lens
+        .map(|i| {
+            let offset = len;
+            len += i;
+            offset
+        })
+        .collect();
+
+    (offsets, len)
+}
+
 impl<'a, S> Rope<'a, S> {
     /// Create a new [`Rope`] containing data from a vector of immutable slices.
     pub fn new(data: &[&'a [S]]) -> Self {
         let data: SmallVec<[&[S]; SMALLVEC_LEN]> = data.to_smallvec();
-        Self {
-            offsets: data
-                .iter()
-                .map(|i| i.len())
-                .scan(0, |a, b| {
-                    let ret = Some(*a);
-                    *a += b;
-                    ret
-                })
-                .collect(),
-            data,
-        }
+        let (offsets, len) = offsets(data.iter().map(|i| i.len()));
+        Self { offsets, len, data }
     }
 
     /// Get the length of the [`Rope`].
     pub fn len(&self) -> usize {
-        self.offsets
-            .last()
-            .zip(self.data.last())
-            .map(|(i, data)| i + data.len())
-            .unwrap_or(0)
+        self.len
     }
 
     /// Checks if the [`Rope`] is empty.
@@ -60,36 +81,75 @@ impl<'a, S> Rope<'a, S> {
         let len = self.len();
         self.offsets
             .extend(rope2.offsets.into_iter().map(|i| i + len));
+        self.len += rope2.len;
         self.data.extend(rope2.data);
 
         self
     }
+
+    /// Produces an iterator over the elements of the [`Rope`] in index order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a S> + '_ {
+        self.data.iter().flat_map(|&data| data.iter())
+    }
+
+    /// Computes the dot product of two ropes, viewed as flat vectors.
+    ///
+    /// # Panics
+    /// If the ropes don't have the same length.
+    pub fn dot(&self, other: &Rope<'_, S>) -> S
+    where
+        S: Copy + num::Zero + std::ops::Mul<Output = S>,
+    {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "Expected ropes of the same length but got {} and {}",
+            self.len(),
+            other.len()
+        );
+
+        self.iter()
+            .zip(other.iter())
+            .fold(S::zero(), |acc, (&a, &b)| acc + a * b)
+    }
+
+    /// Computes the Euclidean distance between two ropes, viewed as flat vectors.
+    ///
+    /// # Panics
+    /// If the ropes don't have the same length.
+    pub fn distance(&self, other: &Rope<'_, S>) -> S
+    where
+        S: num::Float,
+    {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "Expected ropes of the same length but got {} and {}",
+            self.len(),
+            other.len()
+        );
+
+        self.iter()
+            .zip(other.iter())
+            .fold(S::zero(), |acc, (&a, &b)| acc + (a - b) * (a - b))
+            .sqrt()
+    }
 }
 
 impl<'a, S> RopeMut<'a, S> {
     /// Create a new [`RopeMut`] containing data from a vector of mutable slices.
     pub fn new<const N: usize>(data: [&'a mut [S]; N]) -> Self {
+        let (offsets, len) = offsets(data.iter().map(|i| i.len()));
         Self {
-            offsets: data
-                .iter()
-                .map(|i| i.len())
-                .scan(0, |a, b| {
-                    let ret = Some(*a);
-                    *a += b;
-                    ret
-                })
-                .collect(),
+            offsets,
+            len,
             data: data.into_iter().collect(),
         }
     }
 
     /// Get the length of the [`RopeMut`].
     pub fn len(&self) -> usize {
-        self.offsets
-            .last()
-            .zip(self.data.last())
-            .map(|(i, data)| i + data.len())
-            .unwrap_or(0)
+        self.len
     }
 
     /// Checks if the [`RopeMut`] is empty.
@@ -102,11 +162,27 @@ impl<'a, S> RopeMut<'a, S> {
         let len = self.len();
         self.offsets
             .extend(rope2.offsets.into_iter().map(|i| i + len));
+        self.len += rope2.len;
         self.data.extend(rope2.data);
 
         self
     }
 
+    /// Produces an iterator over the elements of the [`RopeMut`] in index order.
+    pub fn iter(&self) -> impl Iterator<Item = &S> + '_ {
+        self.data.iter().flat_map(|data| data.iter())
+    }
+
+    /// Produces a mutable iterator over the elements of the [`RopeMut`] in index order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut S> + '_ {
+        self.data
+            .iter_mut()
+            .map(|data| &mut **data)
+            .collect::<SmallVec<[&mut [S]; SMALLVEC_LEN]>>()
+            .into_iter()
+            .flat_map(|data| data.iter_mut())
+    }
+
     /// Clones the data from the slice into the underlying data in the [`RopeMut`]
     ///
     /// # Panics
@@ -156,22 +232,67 @@ impl<'a, S> RopeMut<'a, S> {
                 data.copy_from_slice(&slice[offset..(offset + len)])
             })
     }
+
+    /// Accumulates `values[k]` into the element at index `indices[k]` of the [`RopeMut`], for
+    /// every `k`. Repeated indices accumulate all of their values, e.g. to inject the boundary
+    /// controls of a lattice into a buffer over all of its sites.
+    ///
+    /// # Panics
+    /// If `indices` and `values` don't have the same length, or an index is out of bounds.
+    pub fn scatter_add(&mut self, indices: &[usize], values: &[S])
+    where
+        S: Add<Output = S> + Copy,
+    {
+        assert_eq!(
+            indices.len(),
+            values.len(),
+            "Expected `indices` and `values` to have the same length but got {} and {}, \
+             respectively",
+            indices.len(),
+            values.len()
+        );
+
+        indices.iter().zip(values).for_each(|(&index, &value)| {
+            self[index] = self[index] + value;
+        });
+    }
 }
 
 impl<'a, S> From<RopeMut<'a, S>> for Rope<'a, S> {
     fn from(value: RopeMut<'a, S>) -> Self {
         Self {
             offsets: value.offsets,
+            len: value.len,
             data: value.data.into_iter().map(|i| &*i).collect(),
         }
     }
 }
 
+/// Ropes are equal if they contain equal elements in the same order, regardless of how the
+/// elements are partitioned into slices.
+impl<'a, 'b, S: PartialEq> PartialEq<Rope<'b, S>> for Rope<'a, S> {
+    fn eq(&self, other: &Rope<'b, S>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, S: Eq> Eq for Rope<'a, S> {}
+
+/// Ropes are equal if they contain equal elements in the same order, regardless of how the
+/// elements are partitioned into slices.
+impl<'a, 'b, S: PartialEq> PartialEq<RopeMut<'b, S>> for RopeMut<'a, S> {
+    fn eq(&self, other: &RopeMut<'b, S>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, S: Eq> Eq for RopeMut<'a, S> {}
+
 impl<'a, S> Index<usize> for Rope<'a, S> {
     type Output = S;
 
     fn index(&self, index: usize) -> &Self::Output {
-        let idx = self.offsets.partition_point(|&i| i < index) - 1;
+        let idx = slice_index(&self.offsets, index);
         &self.data[idx][index - self.offsets[idx]]
     }
 }
@@ -180,14 +301,14 @@ impl<'a, S> Index<usize> for RopeMut<'a, S> {
     type Output = S;
 
     fn index(&self, index: usize) -> &Self::Output {
-        let idx = self.offsets.partition_point(|&i| i < index) - 1;
+        let idx = slice_index(&self.offsets, index);
         &self.data[idx][index - self.offsets[idx]]
     }
 }
 
 impl<'a, S> IndexMut<usize> for RopeMut<'a, S> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        let idx = self.offsets.partition_point(|&i| i < index) - 1;
+        let idx = slice_index(&self.offsets, index);
         &mut self.data[idx][index - self.offsets[idx]]
     }
 }
@@ -280,7 +401,7 @@ impl<'a, S> Iterator for RopeIteratorMut<'a, S> {
 
 #[cfg(test)]
 mod tests {
-    use super::Rope;
+    use super::{Rope, RopeMut};
 
     #[test]
     fn test_rope_simple() {
@@ -289,4 +410,106 @@ mod tests {
 
         todo!();
     }
+
+    #[test]
+    fn test_rope_index_empty_slices() {
+        let rope = Rope::new(&[&[0, 1], &[], &[2]]);
+        assert_eq!(
+            (0..3).map(|i| rope[i]).collect::<Vec<_>>(),
+            [0, 1, 2],
+            "Expected indexing to skip the interior empty slice"
+        );
+
+        let rope = Rope::new(&[&[], &[0], &[], &[], &[1, 2], &[]]);
+        assert_eq!(
+            (0..3).map(|i| rope[i]).collect::<Vec<_>>(),
+            [0, 1, 2],
+            "Expected indexing to skip leading, repeated and trailing empty slices"
+        );
+
+        let (mut a, mut b) = ([0, 1], [2]);
+        {
+            let mut rope = RopeMut::new([&mut a[..], &mut [], &mut b[..]]);
+            rope[2] = 5;
+            assert_eq!(rope[2], 5, "Expected to index mutably past the empty slice");
+        }
+        assert_eq!(b, [5], "Expected the write to land in the last slice");
+    }
+
+    #[test]
+    fn test_rope_len_after_merge() {
+        let rope = Rope::new(&[&[0, 1], &[]]).merge(Rope::new(&[&[2, 3, 4]]));
+        assert_eq!(rope.len(), 5, "Expected the lengths to add up");
+        assert_eq!(rope.len(), rope.iter().count());
+        assert_eq!(
+            rope[4], 4,
+            "Expected the offsets to be shifted by the first length"
+        );
+
+        let empty = Rope::<i32>::new(&[]).merge(Rope::new(&[&[]]));
+        assert!(
+            empty.is_empty(),
+            "Expected merging empty ropes to stay empty"
+        );
+
+        let (mut a, mut b) = ([0, 1], [2]);
+        let rope = RopeMut::new([&mut a[..]]).merge(RopeMut::new([&mut [], &mut b[..]]));
+        assert_eq!(rope.len(), 3, "Expected the mutable lengths to add up");
+        assert_eq!(
+            Rope::from(rope).len(),
+            3,
+            "Expected the length to survive conversion"
+        );
+    }
+
+    #[test]
+    fn test_rope_scatter_add() {
+        let (mut a, mut b) = ([0, 0], [0, 0, 0]);
+        RopeMut::new([&mut a[..], &mut [], &mut b[..]])
+            .scatter_add(&[1, 3, 1, 4, 1], &[1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            a,
+            [0, 9],
+            "Expected the values at the repeated index to accumulate"
+        );
+        assert_eq!(
+            b,
+            [0, 2, 4],
+            "Expected the values to land past the empty slice"
+        );
+    }
+
+    #[test]
+    fn test_rope_distance() {
+        let a = Rope::new(&[&[1., 2.], &[3.]]);
+        let b = Rope::new(&[&[1.], &[-2., 6.]]);
+
+        assert_eq!(a.distance(&b), 5., "Expected sqrt(0 + 16 + 9)");
+        assert_eq!(a.distance(&a), 0., "Expected zero distance to itself");
+    }
+
+    #[test]
+    fn test_rope_eq_different_partitioning() {
+        assert_eq!(Rope::new(&[&[0, 1], &[2]]), Rope::new(&[&[0, 1, 2]]));
+        assert_eq!(
+            Rope::new(&[&[0], &[], &[1, 2]]),
+            Rope::new(&[&[0, 1], &[2]])
+        );
+        assert_eq!(Rope::<i32>::new(&[]), Rope::new(&[&[]]));
+
+        let (mut a, mut b) = ([0, 1, 2], [0, 1, 2]);
+        let (a1, a2) = a.split_at_mut(1);
+        assert_eq!(RopeMut::new([a1, a2]), RopeMut::new([&mut b[..]]));
+    }
+
+    #[test]
+    fn test_rope_ne() {
+        assert_ne!(Rope::new(&[&[0, 1], &[2]]), Rope::new(&[&[0, 1, 3]]));
+        assert_ne!(Rope::new(&[&[0, 1], &[2]]), Rope::new(&[&[0, 1]]));
+        assert_ne!(Rope::new(&[&[2], &[0, 1]]), Rope::new(&[&[0, 1], &[2]]));
+
+        let (mut a, mut b) = ([0, 1, 2], [0, 1]);
+        assert_ne!(RopeMut::new([&mut a[..]]), RopeMut::new([&mut b[..]]));
+    }
 }
@@ -0,0 +1,95 @@
+use std::ops::{AddAssign, MulAssign};
+
+/// Element-wise bulk operations on slices, used by [`Vector::add_assign_slice`] and
+/// [`Vector::scale`]. The provided methods are plain scalar loops; with the `simd` feature, the
+/// implementations for [`f32`] and [`f64`] process several lanes at once using [`wide`].
+///
+/// [`Vector::add_assign_slice`]: crate::vector::Vector::add_assign_slice
+/// [`Vector::scale`]: crate::vector::Vector::scale
+pub trait BulkOps: Copy + AddAssign + MulAssign {
+    /// Adds `other` to `data` element-wise.
+    ///
+    /// # Panics
+    /// If the slices don't have the same length.
+    fn add_assign_slice(data: &mut [Self], other: &[Self]) {
+        add_assign_slice_scalar(data, other);
+    }
+
+    /// Multiplies every element of `data` by `factor`.
+    fn scale_slice(data: &mut [Self], factor: Self) {
+        scale_slice_scalar(data, factor);
+    }
+}
+
+/// Checks that the slices of a bulk operation have the same length.
+///
+/// # Panics
+/// If the slices don't have the same length.
+fn assert_same_len<T>(data: &[T], other: &[T]) {
+    assert_eq!(
+        data.len(),
+        other.len(),
+        "Expected slices of the same length but got {} and {}",
+        data.len(),
+        other.len()
+    );
+}
+
+/// The scalar fallback of [`BulkOps::add_assign_slice`].
+///
+/// # Panics
+/// If the slices don't have the same length.
+pub fn add_assign_slice_scalar<T: Copy + AddAssign>(data: &mut [T], other: &[T]) {
+    assert_same_len(data, other);
+    data.iter_mut().zip(other).for_each(|(a, &b)| *a += b);
+}
+
+/// The scalar fallback of [`BulkOps::scale_slice`].
+pub fn scale_slice_scalar<T: Copy + MulAssign>(data: &mut [T], factor: T) {
+    data.iter_mut().for_each(|a| *a *= factor);
+}
+
+/// Implements [`BulkOps`] for a float type, using the given [`wide`] vector type with the given
+/// number of lanes with the `simd` feature.
+macro_rules! impl_bulk_ops {
+    ($($t:ty => $simd:ident[$lanes:literal]),*) => {
+        $(
+            #[cfg(not(feature = "simd"))]
+            impl BulkOps for $t {}
+
+            #[cfg(feature = "simd")]
+            impl BulkOps for $t {
+                fn add_assign_slice(data: &mut [Self], other: &[Self]) {
+                    /// The number of lanes of the vector type.
+                    const LANES: usize = $lanes;
+                    assert_same_len(data, other);
+
+                    let mut data = data.chunks_exact_mut(LANES);
+                    let mut other = other.chunks_exact(LANES);
+                    for (a, b) in (&mut data).zip(&mut other) {
+                        let sum = wide::$simd::from(<[$t; LANES]>::try_from(&*a).unwrap())
+                            + wide::$simd::from(<[$t; LANES]>::try_from(b).unwrap());
+                        a.copy_from_slice(&sum.to_array());
+                    }
+                    add_assign_slice_scalar(data.into_remainder(), other.remainder());
+                }
+
+                fn scale_slice(data: &mut [Self], factor: Self) {
+                    /// The number of lanes of the vector type.
+                    const LANES: usize = $lanes;
+                    let factor_lanes = wide::$simd::splat(factor);
+
+                    let mut data = data.chunks_exact_mut(LANES);
+                    for a in &mut data {
+                        let scaled = wide::$simd::from(<[$t; LANES]>::try_from(&*a).unwrap())
+                            * factor_lanes;
+                        a.copy_from_slice(&scaled.to_array());
+                    }
+                    scale_slice_scalar(data.into_remainder(), factor);
+                }
+            }
+        )*
+    };
+}
+
+impl_bulk_ops!(f32 => f32x8[8], f64 => f64x4[4]);
@@ -1,10 +1,14 @@
 use crate::{
     rope::{Rope, RopeMut},
-    Float,
+    Float, Scalar,
 };
 
 /// Represents a non-contiguous collection of data.
-pub trait DynamicVector<S: Float> {
+pub trait DynamicVector<S: Scalar> {
+    /// The number of elements in the vector, if it is the same for every instance of the type.
+    /// This lets generic code preallocate buffers of the exact size.
+    const LEN: Option<usize> = None;
+
     /// Create `Self` by copying data from a slice of data.
     fn copy_from_slice(&mut self, v: &[S]) {
         self.get_rope_mut().copy_from_slice(v);
@@ -16,7 +20,7 @@ pub trait DynamicVector<S: Float> {
     fn get_rope_mut(&mut self) -> RopeMut<S>;
 }
 
-impl<S: Float> DynamicVector<S> for Vec<S> {
+impl<S: Scalar> DynamicVector<S> for Vec<S> {
     fn copy_from_slice(&mut self, v: &[S]) {
         <[S]>::copy_from_slice(self, v);
     }
@@ -30,7 +34,9 @@ impl<S: Float> DynamicVector<S> for Vec<S> {
     }
 }
 
-impl<S: Float, const DIMS: usize> DynamicVector<S> for [S; DIMS] {
+impl<S: Scalar, const DIMS: usize> DynamicVector<S> for [S; DIMS] {
+    const LEN: Option<usize> = Some(DIMS);
+
     fn copy_from_slice(&mut self, v: &[S]) {
         <[S]>::copy_from_slice(&mut *self, v);
     }
@@ -44,7 +50,7 @@ impl<S: Float, const DIMS: usize> DynamicVector<S> for [S; DIMS] {
     }
 }
 
-impl<S: Float> DynamicVector<S> for [S] {
+impl<S: Scalar> DynamicVector<S> for [S] {
     fn copy_from_slice(&mut self, v: &[S]) {
         <[S]>::copy_from_slice(&mut *self, v);
     }
@@ -58,7 +64,9 @@ impl<S: Float> DynamicVector<S> for [S] {
     }
 }
 
-impl<S: Float> DynamicVector<S> for S {
+impl<S: Scalar> DynamicVector<S> for S {
+    const LEN: Option<usize> = Some(1);
+
     fn copy_from_slice(&mut self, v: &[S]) {
         std::slice::from_mut(self).copy_from_slice(v)
     }
@@ -72,7 +80,9 @@ impl<S: Float> DynamicVector<S> for S {
     }
 }
 
-impl<S: Float> DynamicVector<S> for () {
+impl<S: Scalar> DynamicVector<S> for () {
+    const LEN: Option<usize> = Some(0);
+
     fn copy_from_slice(&mut self, v: &[S]) {
         [].copy_from_slice(v)
     }
@@ -86,6 +96,26 @@ impl<S: Float> DynamicVector<S> for () {
     }
 }
 
+/// Computes the Gram matrix of a window of vectors, i.e. the dot products of every pair of
+/// flattened ropes, e.g. as kernel features of an observation history. The matrix is symmetric,
+/// with the squared norms of the vectors on its diagonal.
+///
+/// # Panics
+/// If the vectors don't all have the same length.
+pub fn gram_matrix<S: Scalar>(window: &[impl DynamicVector<S>]) -> Vec<Vec<S>> {
+    let ropes = window.iter().map(|v| v.get_rope()).collect::<Vec<_>>();
+    let mut gram = vec![vec![S::zero(); ropes.len()]; ropes.len()];
+    for (i, a) in ropes.iter().enumerate() {
+        for (j, b) in ropes.iter().enumerate().skip(i) {
+            let dot = a.dot(b);
+            gram[i][j] = dot;
+            gram[j][i] = dot;
+        }
+    }
+
+    gram
+}
+
 /// The description of a physical system.
 pub trait System<S: Float> {
     /// The number of parameters in the [`System::ControlSignal`] type.
@@ -109,11 +139,111 @@ pub trait System<S: Float> {
     /// The latent state of the system
     type LatentState: DynamicVector<S>;
     /// The control parameters of the system
-    type ControlParams: DynamicVector<S>;
+    type ControlParams: DynamicVector<S> + Clone;
     /// The control signal of the system
     type ControlSignal: DynamicVector<S>;
     /// The observation of the system
-    type SystemObservation: DynamicVector<S>;
+    type SystemObservation: DynamicVector<S> + ObservationExt<S>;
+
+    /// Gets the number of parameters in each of the associated types for the given
+    /// configuration. Defaults to the `*_SIZE` constants, and should be overridden by systems
+    /// whose sizes depend on their configuration.
+    fn sizes(_config: &Self::SystemConfiguration) -> SystemSizes {
+        SystemSizes {
+            control_signal: Self::CONTROL_SIGNAL_SIZE,
+            control_params: Self::CONTROL_PARAMS_SIZE,
+            latent_state: Self::LATENT_STATE_SIZE,
+            system_state: Self::SYSTEM_STATE_SIZE,
+            observable_state: Self::OBSERVABLE_STATE_SIZE,
+        }
+    }
+}
+
+/// The number of parameters in each of the associated types of a [`System`], see
+/// [`System::sizes`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SystemSizes {
+    /// The number of parameters in the [`System::ControlSignal`] type.
+    pub control_signal: usize,
+    /// The number of parameters in the [`System::ControlParams`] type.
+    pub control_params: usize,
+    /// The number of parameters in the [`System::LatentState`] type.
+    pub latent_state: usize,
+    /// The number of parameters in the [`System::SystemState`] type.
+    pub system_state: usize,
+    /// The number of parameters in the [`System::SystemObservation`] type.
+    pub observable_state: usize,
+}
+
+/// Asserts that the flattened lengths of the given instances of the associated types of a
+/// [`System`] match the [`System::sizes`] for the given configuration. Meant to be called from
+/// the tests of each system, with instances constructed from that same configuration.
+///
+/// # Panics
+/// If any of the lengths doesn't match its size.
+pub fn assert_system_sizes_consistent<T: Float, S: System<T>>(
+    config: &S::SystemConfiguration,
+    state: &S::SystemState,
+    latent: &S::LatentState,
+    params: &S::ControlParams,
+    signal: &S::ControlSignal,
+    observation: &S::SystemObservation,
+) {
+    let sizes = S::sizes(config);
+    let lens = SystemSizes {
+        control_signal: signal.get_rope().len(),
+        control_params: params.get_rope().len(),
+        latent_state: latent.get_rope().len(),
+        system_state: state.get_rope().len(),
+        observable_state: observation.get_rope().len(),
+    };
+    assert_eq!(lens, sizes, "Expected the sizes {sizes:?} but got {lens:?}");
+}
+
+/// Gives generic access to the metadata of a [`System::SystemObservation`].
+pub trait ObservationExt<S: Float> {
+    /// Gets the time at which the observation was made.
+    fn time(&self) -> S;
+}
+
+/// A bare float observation is taken to be its own timestamp.
+impl<S: Float> ObservationExt<S> for S {
+    fn time(&self) -> S {
+        *self
+    }
+}
+
+/// A [`System`] whose state evolves under second-order Newtonian dynamics, so that it can be
+/// stepped by any [`Integrator`](crate::integrator::Integrator). The positions and velocities are
+/// exposed as flat slices of the same length.
+pub trait PhysicsSystem<S: Float>: System<S> {
+    /// Gets the time of the system state.
+    fn time(state: &Self::SystemState) -> S;
+
+    /// Sets the time of the system state.
+    fn set_time(state: &mut Self::SystemState, time: S);
+
+    /// Gets the flattened positions of the system state.
+    fn position(state: &Self::SystemState) -> &[S];
+
+    /// Gets the flattened velocities of the system state.
+    fn velocity(state: &Self::SystemState) -> &[S];
+
+    /// Gets the flattened positions and velocities of the system state mutably.
+    fn phase_space_mut(state: &mut Self::SystemState) -> (&mut [S], &mut [S]);
+
+    /// Computes the flattened accelerations of the system state under the given control signal,
+    /// saving them into `acceleration`.
+    fn acceleration(
+        &self,
+        state: &Self::SystemState,
+        control_signal: &Self::ControlSignal,
+        acceleration: &mut [S],
+    );
+
+    /// Computes the total energy of the system state, i.e. its kinetic plus potential energy,
+    /// which is conserved in the absence of a control signal.
+    fn energy(&self, state: &Self::SystemState) -> S;
 }
 
 /// Gets the associated [`System::SystemConfiguration`] for some given system.
@@ -136,3 +266,31 @@ pub type ControlSignal<T, S> = <S as System<T>>::ControlSignal;
 
 /// Gets the associated [`System::SystemObservation`] for some given system.
 pub type SystemObservation<T, S> = <S as System<T>>::SystemObservation;
+
+#[cfg(test)]
+mod tests {
+    use super::gram_matrix;
+
+    #[test]
+    fn test_gram_matrix() {
+        let window = [[1., 2.], [0., -1.], [3., 0.5]];
+        let gram = gram_matrix(&window);
+
+        assert_eq!(
+            gram,
+            [[5., -2., 4.], [-2., 1., -0.5], [4., -0.5, 9.25]],
+            "Expected the pairwise dot products"
+        );
+        for (i, row) in gram.iter().enumerate() {
+            assert_eq!(
+                row[i],
+                window[i].iter().map(|x| x * x).sum::<f64>(),
+                "Expected the squared norm on the diagonal"
+            );
+            for (j, &value) in row.iter().enumerate() {
+                assert_eq!(value, gram[j][i], "Expected a symmetric matrix");
+            }
+        }
+        assert!(gram_matrix::<f64>(&[] as &[Vec<f64>]).is_empty());
+    }
+}
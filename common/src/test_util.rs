@@ -0,0 +1,39 @@
+use crate::{
+    interfaces::SimulatorInterface,
+    system::{PhysicsSystem, System},
+    Float,
+};
+
+/// Steps the simulator `steps` times by `dt` without any control signal, and returns the
+/// minimum and maximum [`PhysicsSystem::energy`] of the full states along the way, including the
+/// initial one. Since the energy is conserved by the uncontrolled dynamics, the spread between
+/// the two measures the energy drift of the integrator used by the simulator.
+///
+/// # Panics
+/// If the simulator doesn't expose its full state through
+/// [`SimulatorInterface::peek_full_state`].
+pub async fn energy_drift<T, S, SIM>(system: &S, simulator: &mut SIM, dt: T, steps: usize) -> (T, T)
+where
+    T: Float,
+    S: PhysicsSystem<T>,
+    S::ControlSignal: Default,
+    SIM: SimulatorInterface<T, S>,
+{
+    let control_signal = <S as System<T>>::ControlSignal::default();
+    let energy = |simulator: &SIM| {
+        let state = simulator
+            .peek_full_state()
+            .expect("Expected the simulator to expose its full state");
+        system.energy(&state)
+    };
+
+    let initial_energy = energy(simulator);
+    let mut bounds = (initial_energy, initial_energy);
+    for _ in 0..steps {
+        simulator.update(system, dt, &control_signal).await;
+        let energy = energy(simulator);
+        bounds = (bounds.0.min(energy), bounds.1.max(energy));
+    }
+
+    bounds
+}
@@ -1,11 +1,17 @@
 use bytemuck::{Pod, Zeroable};
+use num::traits::{FromBytes, ToBytes};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use std::ops::{Index, IndexMut};
+use std::{
+    hash::{Hash, Hasher},
+    ops::{Index, IndexMut},
+};
 
 use crate::{
     rope::{Rope, RopeMut},
+    simd::BulkOps,
     system::DynamicVector,
-    Float,
+    Scalar,
 };
 
 /// This attempts to compile invalid types
@@ -32,6 +38,28 @@ where
 }
 impl<T, const DIMS: usize> Copy for Vector<T, DIMS> where [T; DIMS]: Copy {}
 
+/// An opt-in wrapper that hashes and compares a [`Pod`] value, such as a float [`Vector`], by its
+/// bits. This makes float vectors usable as keys for memoizing per-state computations.
+///
+/// Note that this differs from the float equality: `0.0` and `-0.0` are distinct, while a NaN is
+/// equal to another NaN only if their bits, including the payload, are identical.
+#[derive(Debug, Clone, Copy)]
+pub struct BitHash<V>(pub V);
+
+impl<V: Pod> Hash for BitHash<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        bytemuck::bytes_of(&self.0).hash(state);
+    }
+}
+
+impl<V: Pod> PartialEq for BitHash<V> {
+    fn eq(&self, other: &Self) -> bool {
+        bytemuck::bytes_of(&self.0) == bytemuck::bytes_of(&other.0)
+    }
+}
+
+impl<V: Pod> Eq for BitHash<V> {}
+
 impl<T: Default, const DIMS: usize> Default for Vector<T, DIMS> {
     fn default() -> Self {
         Self(std::array::from_fn(|_| T::default()))
@@ -44,6 +72,20 @@ impl<T: num::Zero, const DIMS: usize> Vector<T, DIMS> {
     pub fn zero() -> Self {
         Vector::from_idx(|_| T::zero())
     }
+
+    /// Returns a vector of the same dimension as `self` with all components zero.
+    #[inline]
+    pub fn zeros_like(&self) -> Self {
+        Self::zero()
+    }
+}
+
+impl<T: num::One, const DIMS: usize> Vector<T, DIMS> {
+    /// Returns a vector of the same dimension as `self` with all components one.
+    #[inline]
+    pub fn ones_like(&self) -> Self {
+        Vector::from_idx(|_| T::one())
+    }
 }
 
 impl<T, const DIMS: usize> Vector<T, DIMS> {
@@ -85,6 +127,28 @@ impl<T, const DIMS: usize> Vector<T, DIMS> {
         &mut self.0
     }
 
+    /// Consumes the [`Vector`] and returns the interior array
+    pub fn into_array(self) -> [T; DIMS] {
+        self.0
+    }
+
+    /// Resizes the vector to `M` dimensions, truncating it if `M < DIMS` and padding it with
+    /// `fill` if `M > DIMS`.
+    pub fn resize<const M: usize>(self, fill: T) -> Vector<T, M>
+    where
+        T: Copy,
+    {
+        Vector::from_idx(|i| self.0.get(i).copied().unwrap_or(fill))
+    }
+
+    /// Cyclically shifts the components `shift` positions towards higher indices, wrapping the
+    /// last ones around to the front, so that `[1, 2, 3]` rotated by 1 is `[3, 1, 2]`. Useful to
+    /// relabel the axes between differently oriented coordinate conventions.
+    pub fn rotate_dims(mut self, shift: usize) -> Self {
+        self.0.rotate_right(shift % DIMS.max(1));
+        self
+    }
+
     /// Produces an iterator given by the underlying slice iterator.
     pub fn iter(&self) -> std::slice::Iter<T> {
         self.0.iter()
@@ -114,6 +178,33 @@ impl<T, const DIMS: usize> Vector<T, DIMS> {
         Self(std::array::from_fn(idx_fn))
     }
 
+    /// Given a fallible map of type [`Fn(usize) -> Result<T, E>`], produces a vector by passing
+    /// in each index from `0..DIMS` to the map, stopping at the first error. This is the fallible
+    /// counterpart of [`Vector::from_idx`].
+    pub fn try_from_idx<E>(idx_fn: impl Fn(usize) -> Result<T, E>) -> Result<Self, E> {
+        let data = (0..DIMS).map(idx_fn).collect::<Result<Vec<_>, E>>()?;
+        let data = data
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("Expected exactly {DIMS} components"));
+
+        Ok(Self(data))
+    }
+
+    /// Given a map of type [`Fn(usize) -> T`], produces a vector by passing in each index from
+    /// `0..DIMS` to the map in parallel using [`rayon`]. This is useful for large vectors with an
+    /// expensive per-index initialization, otherwise prefer [`Vector::from_idx`].
+    pub fn par_from_idx(idx_fn: impl Fn(usize) -> T + Sync + Send) -> Self
+    where
+        T: Send,
+    {
+        let data = (0..DIMS).into_par_iter().map(idx_fn).collect::<Vec<_>>();
+
+        Self(
+            data.try_into()
+                .unwrap_or_else(|_| unreachable!("Collected exactly `DIMS` elements")),
+        )
+    }
+
     /// Given a type that is "additively reducible", compute the sum over all elements of the
     /// vector of that type. The addition need not be commutative and is performed from left to
     /// right. If the vector is empty, then the additive-identity element of the type is returned.
@@ -136,6 +227,73 @@ impl<T, const DIMS: usize> Vector<T, DIMS> {
     {
         self.0.into_iter().reduce(|a, b| a * b).unwrap_or(T::one())
     }
+
+    /// Computes the inclusive prefix sums of the vector, i.e. the `i`-th element is the sum of the
+    /// first `i + 1` elements, added from left to right.
+    #[inline]
+    pub fn cumsum(mut self) -> Self
+    where
+        T: Copy + std::ops::Add<Output = T>,
+    {
+        for i in 1..DIMS {
+            self.0[i] = self.0[i - 1] + self.0[i];
+        }
+        self
+    }
+
+    /// Computes the inclusive prefix products of the vector, i.e. the `i`-th element is the product
+    /// of the first `i + 1` elements, multiplied from left to right.
+    #[inline]
+    pub fn cumprod(mut self) -> Self
+    where
+        T: Copy + std::ops::Mul<Output = T>,
+    {
+        for i in 1..DIMS {
+            self.0[i] = self.0[i - 1] * self.0[i];
+        }
+        self
+    }
+
+    /// Computes the dot product of two vectors, i.e. the sum of their elementwise products.
+    #[inline]
+    pub fn dot(self, other: Self) -> T
+    where
+        T: Copy + std::ops::Mul<Output = T> + std::ops::Add<Output = T> + num::Zero,
+    {
+        (self * other).sum()
+    }
+
+    /// Adds the dot product of two vectors onto `acc`, i.e. `*acc += self · other`, without
+    /// forming the elementwise products as an intermediate vector. This fuses the accumulation of
+    /// many dot products, e.g. in a reduction over a lattice.
+    #[inline]
+    pub fn add_dot_into(self, other: Self, acc: &mut T)
+    where
+        T: Copy + std::ops::Mul<Output = T> + std::ops::Add<Output = T>,
+    {
+        for (a, b) in self.0.into_iter().zip(other.0) {
+            *acc = *acc + a * b;
+        }
+    }
+
+    /// Computes the weighted dot product `sum_i w_i * x_i * y_i` of two vectors.
+    #[inline]
+    pub fn weighted_dot(self, other: Self, weights: Self) -> T
+    where
+        T: Copy + std::ops::Mul<Output = T> + std::ops::Add<Output = T> + num::Zero,
+    {
+        (self * other * weights).sum()
+    }
+
+    /// Computes the weighted sum of squares `sum_i w_i * x_i^2`, i.e. the square of the weighted
+    /// Euclidean norm of the vector.
+    #[inline]
+    pub fn weighted_sum_squares(self, weights: Self) -> T
+    where
+        T: Copy + std::ops::Mul<Output = T> + std::ops::Add<Output = T> + num::Zero,
+    {
+        self.weighted_dot(self, weights)
+    }
 }
 
 impl<T, const DIMS: usize> IntoIterator for Vector<T, DIMS> {
@@ -168,6 +326,61 @@ impl<T, const DIMS: usize> From<[T; DIMS]> for Vector<T, DIMS> {
     }
 }
 
+impl<T, const DIMS: usize> From<Vector<T, DIMS>> for [T; DIMS] {
+    fn from(value: Vector<T, DIMS>) -> Self {
+        value.into_array()
+    }
+}
+
+/// A scalar is the single component of a one-dimensional vector.
+impl<T> From<T> for Vector<T, 1> {
+    fn from(value: T) -> Self {
+        Self([value])
+    }
+}
+
+/// Implements the conversion of one-dimensional vectors back into their single component for the
+/// given scalar types. This can't be implemented for a generic scalar due to the orphan rules.
+macro_rules! impl_from_vector_1 {
+    ($($t:ty),*) => {
+        $(
+            impl From<Vector<$t, 1>> for $t {
+                fn from(Vector([value]): Vector<$t, 1>) -> Self {
+                    value
+                }
+            }
+        )*
+    };
+}
+
+impl_from_vector_1!(f32, f64, usize);
+
+impl<T, const DIMS: usize> AsRef<[T]> for Vector<T, DIMS> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const DIMS: usize> AsMut<[T]> for Vector<T, DIMS> {
+    fn as_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Copy + num::Zero, const DIMS: usize> std::iter::Sum for Vector<T, DIMS> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |a, b| a + b)
+    }
+}
+
+impl<'a, T: Copy + num::Zero, const DIMS: usize> std::iter::Sum<&'a Vector<T, DIMS>>
+    for Vector<T, DIMS>
+{
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |a, &b| a + b)
+    }
+}
+
 impl<T: num::Zero + num::One, const DIMS: usize> Vector<T, DIMS> {
     /// Produces a basis vector in the direction of the `idx`-th dimension.
     pub fn basis(idx: usize) -> Self {
@@ -178,6 +391,215 @@ impl<T: num::Zero + num::One, const DIMS: usize> Vector<T, DIMS> {
     }
 }
 
+impl<T: Copy + num::Zero, const DIMS: usize, const N: usize> Vector<Vector<T, DIMS>, N> {
+    /// Sums the inner vectors of a nested vector element-wise, reducing over the outer dimension.
+    /// If the outer dimension is empty, the zero vector is returned.
+    pub fn sum_outer(self) -> Vector<T, DIMS> {
+        self.into_iter().sum()
+    }
+}
+
+impl<T: Copy, const C: usize, const R: usize> Vector<Vector<T, C>, R> {
+    /// Transposes a nested vector of `R` rows of `C` elements into `C` rows of `R` elements, e.g.
+    /// to convert a row-major latent tensor into a column-major one.
+    pub fn transpose(self) -> Vector<Vector<T, R>, C> {
+        Vector::from_idx(|c| Vector::from_idx(|r| self[r][c]))
+    }
+}
+
+/// Overflow-aware element-wise arithmetic for integer vectors, e.g. the [`Vector<usize, DIMS>`]
+/// lattice indices, for which the plain operators silently wrap around in release builds.
+impl<T: num::PrimInt, const DIMS: usize> Vector<T, DIMS> {
+    /// Adds two vectors element-wise, clamping each component at the bounds of `T` rather than
+    /// overflowing.
+    #[inline]
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self::from_idx(|i| self.0[i].saturating_add(other.0[i]))
+    }
+
+    /// Adds two vectors element-wise, wrapping each component around at the bounds of `T`.
+    #[inline]
+    pub fn wrapping_add(self, other: Self) -> Self
+    where
+        T: num::traits::WrappingAdd,
+    {
+        Self::from_idx(|i| self.0[i].wrapping_add(&other.0[i]))
+    }
+
+    /// Adds two vectors element-wise, returning [`None`] if any component overflows.
+    #[inline]
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Self::try_from_idx(|i| self.0[i].checked_add(&other.0[i]).ok_or(())).ok()
+    }
+
+    /// Multiplies two vectors element-wise, returning [`None`] if any component overflows.
+    #[inline]
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::try_from_idx(|i| self.0[i].checked_mul(&other.0[i]).ok_or(())).ok()
+    }
+}
+
+impl<T: num::Float, const DIMS: usize> Vector<T, DIMS> {
+    /// Checks whether all components of the vector are finite, i.e. neither infinite nor NaN.
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        self.into_iter().all(T::is_finite)
+    }
+
+    /// Clamps every component of the vector to the range between the corresponding components
+    /// of `min` and `max`, i.e. into the axis-aligned box spanned by them.
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Self::from_idx(|i| self[i].max(min[i]).min(max[i]))
+    }
+
+    /// Checks whether any component of the vector is NaN.
+    #[inline]
+    pub fn has_nan(self) -> bool {
+        self.into_iter().any(T::is_nan)
+    }
+
+    /// Takes the reciprocal `1 / x` of every component of the vector.
+    #[inline]
+    pub fn recip(self) -> Self {
+        self.map(T::recip)
+    }
+
+    /// Takes the square root of every component of the vector, e.g. to turn variances into
+    /// standard deviations. Negative components become NaN.
+    #[inline]
+    pub fn sqrt(self) -> Self {
+        self.map(T::sqrt)
+    }
+
+    /// Reflects the vector about the hyperplane with the given normal, e.g. a velocity bouncing
+    /// off a wall. The normal is assumed to be a unit vector.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (T::from(2).unwrap() * self.dot(normal))
+    }
+
+    /// Computes the angle between two vectors in `[0, π]`, e.g. to measure the alignment of a
+    /// control with a target direction. The cosine is clamped to `[-1, 1]` so that rounding can't
+    /// produce NaN, and the angle is zero if either vector has zero length.
+    pub fn angle_between(self, other: Self) -> T {
+        let norms = (self.dot(self) * other.dot(other)).sqrt();
+        if norms.is_zero() {
+            return T::zero();
+        }
+
+        (self.dot(other) / norms)
+            .max(-T::one())
+            .min(T::one())
+            .acos()
+    }
+
+    /// Produces `DIMS` evenly spaced values from `start` to `end`, both inclusive, e.g. the
+    /// coordinates of a grid. A single value is just `start`.
+    pub fn linspace(start: T, end: T) -> Self {
+        let intervals = T::from(DIMS.saturating_sub(1).max(1)).unwrap();
+        Self::from_idx(|i| match i {
+            0 => start,
+            i if i + 1 == DIMS => end,
+            i => start + (end - start) * T::from(i).unwrap() / intervals,
+        })
+    }
+
+    /// Produces the `DIMS` values `start, start + step, start + 2 * step, ...`.
+    pub fn arange(start: T, step: T) -> Self {
+        Self::from_idx(|i| start + step * T::from(i).unwrap())
+    }
+
+    /// Projects the vector onto the direction of `dir`, which need not be a unit vector.
+    pub fn project_onto(self, dir: Self) -> Self {
+        dir * (self.dot(dir) / dir.dot(dir))
+    }
+}
+
+impl<T: ToBytes, const DIMS: usize> Vector<T, DIMS> {
+    /// Serializes the components into little-endian bytes, regardless of the host byte order,
+    /// e.g. for portable checkpoint files. Casting with [`bytemuck`] uses the host byte order.
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(T::to_le_bytes)
+    }
+
+    /// Serializes the components into big-endian bytes, regardless of the host byte order.
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(T::to_be_bytes)
+    }
+
+    /// Serializes the components with the given conversion of each component into bytes.
+    fn to_bytes_with(&self, to_bytes: impl Fn(&T) -> T::Bytes) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(DIMS * std::mem::size_of::<T>());
+        for x in self.iter() {
+            bytes.extend_from_slice(to_bytes(x).as_ref());
+        }
+
+        bytes
+    }
+}
+
+impl<T, const DIMS: usize> Vector<T, DIMS>
+where
+    T: num::Zero + ToBytes + FromBytes<Bytes = <T as ToBytes>::Bytes>,
+{
+    /// Deserializes the components from little-endian bytes, as produced by
+    /// [`Vector::to_le_bytes`].
+    ///
+    /// # Panics
+    /// If `bytes` doesn't hold exactly `DIMS` components.
+    pub fn from_le_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes_with(bytes, T::from_le_bytes)
+    }
+
+    /// Deserializes the components from big-endian bytes, as produced by
+    /// [`Vector::to_be_bytes`].
+    ///
+    /// # Panics
+    /// If `bytes` doesn't hold exactly `DIMS` components.
+    pub fn from_be_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes_with(bytes, T::from_be_bytes)
+    }
+
+    /// Deserializes the components with the given conversion of the bytes of each component.
+    ///
+    /// # Panics
+    /// If `bytes` doesn't hold exactly `DIMS` components.
+    fn from_bytes_with(bytes: &[u8], from_bytes: impl Fn(&<T as ToBytes>::Bytes) -> T) -> Self {
+        let mut buffer = T::zero().to_le_bytes();
+        let size = buffer.as_ref().len();
+        assert_eq!(
+            bytes.len(),
+            DIMS * size,
+            "Expected {} bytes for {DIMS} components but got {}",
+            DIMS * size,
+            bytes.len()
+        );
+
+        let mut chunks = bytes.chunks_exact(size);
+        Self(std::array::from_fn(|_| {
+            buffer.as_mut().copy_from_slice(chunks.next().unwrap());
+            from_bytes(&buffer)
+        }))
+    }
+}
+
+impl<T: BulkOps, const DIMS: usize> Vector<T, DIMS> {
+    /// Adds `other` to the vector element-wise in place. With the `simd` feature, this is
+    /// vectorized for [`f32`] and [`f64`], which pays off for large vectors such as latent states.
+    ///
+    /// # Panics
+    /// If `other` doesn't have length `DIMS`.
+    pub fn add_assign_slice(&mut self, other: &[T]) {
+        T::add_assign_slice(&mut self.0, other);
+    }
+
+    /// Multiplies the vector by `factor` in place. With the `simd` feature, this is vectorized
+    /// for [`f32`] and [`f64`].
+    pub fn scale(&mut self, factor: T) {
+        T::scale_slice(&mut self.0, factor);
+    }
+}
+
 /// Given a scalar unary operation, construct the associated vector operation.
 macro_rules! impl_unary_operation {
     ($op:ident) => {
@@ -192,12 +614,24 @@ macro_rules! impl_unary_operation {
                     Vector::from_idx(|i| self[i].[< $op:lower >]())
                 }
             }
+
+            impl<'a, T, U, const DIMS: usize> std::ops::$op for &'a Vector<T, DIMS>
+            where
+                &'a T: std::ops::$op<Output = U>,
+            {
+                type Output = Vector<U, DIMS>;
+
+                fn [< $op:lower >](self) -> Self::Output {
+                    Vector::from_idx(|i| (&self[i]).[< $op:lower >]())
+                }
+            }
         }
     };
 }
 
 /// Given a scalar binary operation, construct the associated vector operation for the pairs
-/// `(Vector, Vector)` and `(Vector, Scalar)`.
+/// `(Vector, Vector)`, `(&Vector, &Vector)` and `(Vector, Scalar)`. The by-reference operation
+/// doesn't require `Copy`, and avoids copying large vectors.
 macro_rules! impl_binary_operation {
     ($($op:ident),+$(,)?) => {
         paste::paste! {
@@ -213,6 +647,19 @@ macro_rules! impl_binary_operation {
             })+
         }
 
+        paste::paste! {
+            $(impl<'a, 'b, T, U, V, const DIMS: usize> std::ops::$op<&'b Vector<U, DIMS>> for &'a Vector<T, DIMS>
+            where
+                &'a T: std::ops::$op<&'b U, Output = V>,
+            {
+                type Output = Vector<V, DIMS>;
+
+                fn [< $op:lower >](self, rhs: &'b Vector<U, DIMS>) -> Self::Output {
+                    Vector::from_idx(|i| (&self[i]).[< $op:lower >](&rhs[i]))
+                }
+            })+
+        }
+
         paste::paste! {
             $(impl<T: Copy, U: Copy + num::Num, V, const DIMS: usize> std::ops::$op<U> for Vector<T, DIMS>
             where
@@ -266,7 +713,9 @@ impl_unary_operation!(Neg);
 impl_binary_operation!(Add, Sub, Mul, Div, Rem);
 impl_binary_assign_operation!(Add, Sub, Mul, Div, Rem);
 
-impl<S: Float, const DIMS: usize> DynamicVector<S> for Vector<S, DIMS> {
+impl<S: Scalar, const DIMS: usize> DynamicVector<S> for Vector<S, DIMS> {
+    const LEN: Option<usize> = Some(DIMS);
+
     fn copy_from_slice(&mut self, v: &[S]) {
         <[S]>::copy_from_slice(&mut *self.as_array_mut(), v);
     }
@@ -282,7 +731,16 @@ impl<S: Float, const DIMS: usize> DynamicVector<S> for Vector<S, DIMS> {
 
 #[cfg(test)]
 mod tests {
-    use super::Vector;
+    use std::{
+        collections::HashSet,
+        hash::{BuildHasher, RandomState},
+    };
+
+    use super::{BitHash, Vector};
+    use crate::{
+        simd::{add_assign_slice_scalar, scale_slice_scalar},
+        system::DynamicVector,
+    };
 
     #[test]
     fn test_addition_u8() {
@@ -395,4 +853,370 @@ mod tests {
 
         assert_eq!(x + y, Vector([1.3, 24.0]));
     }
+
+    #[test]
+    fn test_into_array_round_trip() {
+        let x = Vector([1.5f64, -2., 3.25]);
+
+        assert_eq!(Vector::new(x.into_array()), x);
+        assert_eq!(<[f64; 3]>::from(x), [1.5, -2., 3.25]);
+    }
+
+    #[test]
+    fn test_scalar_round_trip() {
+        let x = Vector::from(2.5f64);
+        assert_eq!(x, Vector([2.5]));
+        assert_eq!(f64::from(x), 2.5);
+
+        let y: f32 = (Vector::from(-1f32) * 3.).into();
+        assert_eq!(y, -3.);
+    }
+
+    #[test]
+    fn test_as_ref_as_mut() {
+        let mut x = Vector([1u32, 2, 3]);
+        x.as_mut()[1] = 5;
+
+        assert_eq!(AsRef::<[u32]>::as_ref(&x), &[1, 5, 3]);
+    }
+
+    #[test]
+    fn test_iterator_sum() {
+        let vectors = [Vector([1i32, 2]), Vector([3, -4]), Vector([5, 6])];
+
+        assert_eq!(vectors.iter().sum::<Vector<i32, 2>>(), Vector([9, 4]));
+        assert_eq!(vectors.into_iter().sum::<Vector<i32, 2>>(), Vector([9, 4]));
+    }
+
+    #[cfg(feature = "complex")]
+    #[test]
+    fn test_complex_arithmetic() {
+        use crate::system::DynamicVector;
+        use num::Complex;
+
+        let i = Complex::new(0f64, 1.);
+        let x = Vector([Complex::new(1., 2.), Complex::new(-3., 0.5)]);
+        let y = Vector([Complex::new(0.5, -1.), i]);
+
+        let sum = Vector([Complex::new(1.5, 1.), Complex::new(-3., 1.5)]);
+        let product = Vector([Complex::new(-2., 1.), Complex::new(-0.5, -3.)]);
+        assert_eq!(x + y, sum);
+        assert_eq!(x * i, product);
+        assert_eq!((x - x).map(|z| z.norm_sqr()).sum(), 0.);
+
+        let mut z = Vector::<Complex<f64>, 2>::zero();
+        z.copy_from_slice(&[Complex::new(1., 1.), i]);
+        assert_eq!(z.get_rope().len(), 2);
+        assert_eq!(z, Vector([Complex::new(1., 1.), i]));
+    }
+
+    #[test]
+    fn test_dot() {
+        let x = Vector([1., 2., 3.]);
+        let y = Vector([4., -5., 6.]);
+
+        assert_eq!(x.dot(y), 12.);
+    }
+
+    #[test]
+    fn test_add_dot_into() {
+        let vectors = [
+            Vector([1., 2., 3.]),
+            Vector([4., -5., 6.]),
+            Vector([0.5, 0., -2.]),
+        ];
+
+        let mut acc = 1.;
+        for v in vectors {
+            v.add_dot_into(v, &mut acc);
+        }
+
+        assert_eq!(acc, 1. + vectors.iter().map(|&v| v.dot(v)).sum::<f64>());
+        assert_eq!(acc, 1. + 14. + 77. + 4.25);
+    }
+
+    #[test]
+    fn test_zeros_ones_like() {
+        let v = Vector([3., -1., 2.5]);
+
+        assert_eq!(v.zeros_like(), Vector([0., 0., 0.]));
+        assert_eq!(v.ones_like(), Vector([1., 1., 1.]));
+        assert_eq!(Vector([7u8, 9]).ones_like(), Vector([1, 1]));
+    }
+
+    #[test]
+    fn test_bulk_ops_match_scalar() {
+        // An odd length, so that the vectorized paths also have a scalar remainder.
+        let x = Vector::<f64, 1027>::from_idx(|i| (i as f64 * 0.37).sin());
+        let y = Vector::<f64, 1027>::from_idx(|i| (i as f64 * 0.11).cos());
+
+        let mut bulk = x;
+        bulk.add_assign_slice(y.as_ref());
+        bulk.scale(-1.5);
+        let mut scalar = x;
+        add_assign_slice_scalar(scalar.as_mut(), y.as_ref());
+        scale_slice_scalar(scalar.as_mut(), -1.5);
+        assert_eq!(
+            bulk, scalar,
+            "Expected the f64 bulk and scalar paths to agree"
+        );
+
+        let x = x.map(|x| x as f32);
+        let mut bulk = x;
+        bulk.add_assign_slice(x.as_ref());
+        bulk.scale(0.25);
+        assert_eq!(
+            bulk,
+            x.map(|x| (x + x) * 0.25),
+            "Expected the f32 bulk and scalar paths to agree"
+        );
+    }
+
+    #[test]
+    fn test_resize() {
+        let v = Vector([1., 2., 3., 4.]);
+
+        assert_eq!(v.resize::<2>(0.), Vector([1., 2.]), "Expected truncation");
+        assert_eq!(
+            v.resize::<6>(-1.),
+            Vector([1., 2., 3., 4., -1., -1.]),
+            "Expected padding with the fill value"
+        );
+        assert_eq!(v.resize::<4>(0.), v, "Expected no change for the same size");
+    }
+
+    #[test]
+    fn test_dynamic_vector_len() {
+        assert_eq!(<Vector<f64, 3> as DynamicVector<f64>>::LEN, Some(3));
+        assert_eq!(<Vector<f32, 0> as DynamicVector<f32>>::LEN, Some(0));
+        assert_eq!(<Vec<f64> as DynamicVector<f64>>::LEN, None);
+        assert_eq!(
+            <Vector<f64, 3> as DynamicVector<f64>>::LEN,
+            Some(Vector([1., 2., 3.]).get_rope().len()),
+            "Expected the const length to match the rope length"
+        );
+    }
+
+    #[test]
+    fn test_finite_predicates() {
+        let finite = Vector([1., -2., 0.]);
+        let nan = Vector([1., f64::NAN, 0.]);
+        let infinite = Vector([f64::NEG_INFINITY, -2., 0.]);
+
+        assert!(finite.is_finite(), "Expected finite components");
+        assert!(!finite.has_nan(), "Expected no NaN in {finite:?}");
+        assert!(!nan.is_finite(), "Expected NaN not to be finite");
+        assert!(nan.has_nan(), "Expected NaN in {nan:?}");
+        assert!(!infinite.is_finite(), "Expected infinity not to be finite");
+        assert!(!infinite.has_nan(), "Expected no NaN in {infinite:?}");
+    }
+
+    #[test]
+    fn test_weighted_sums() {
+        let x = Vector([1., -2., 3.]);
+        let y = Vector([4., 5., -1.]);
+        let weights = Vector([0.5, 2., 0.25]);
+
+        assert_eq!(x.weighted_dot(y, weights), 0.5 * 4. - 2. * 10. - 0.25 * 3.);
+        assert_eq!(x.weighted_sum_squares(weights), 0.5 + 2. * 4. + 0.25 * 9.);
+        assert_eq!(x.weighted_sum_squares(Vector::broadcast(1.)), x.dot(x));
+    }
+
+    #[test]
+    fn test_reflect_axis_aligned_wall() {
+        let velocity = Vector([3., -4.]);
+
+        assert_eq!(velocity.reflect(Vector([0., 1.])), Vector([3., 4.]));
+        assert_eq!(velocity.reflect(Vector([-1., 0.])), Vector([-3., -4.]));
+    }
+
+    #[test]
+    fn test_recip_sqrt() {
+        let v = Vector([4., 2., 0.25]);
+
+        assert_eq!(v.sqrt(), Vector([2., 2f64.sqrt(), 0.5]));
+        assert_eq!(v.recip(), Vector([0.25, 0.5, 4.]));
+        assert_eq!(v * v.recip(), Vector::broadcast(1.));
+        assert!(Vector([-1f64, 1.]).sqrt().has_nan());
+        assert_eq!(Vector([0f64]).recip(), Vector([f64::INFINITY]));
+    }
+
+    #[test]
+    fn test_angle_between() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+        let x = Vector([2., 0., 0.]);
+        assert_eq!(x.angle_between(Vector([0., 0., -3.])), FRAC_PI_2);
+        assert_eq!(x.angle_between(x * 0.1), 0.);
+        assert_eq!(x.angle_between(-x), PI);
+        assert!((Vector([1., 0.]).angle_between(Vector([1., 1.])) - FRAC_PI_4).abs() < 1e-12);
+        assert_eq!(x.angle_between(Vector::zero()), 0.);
+
+        // Rounding pushes the cosine of nearly parallel vectors slightly past one.
+        let v = Vector([0.1f64, 0.2, 0.3]);
+        assert!(!v.angle_between(v * 3.).is_nan());
+    }
+
+    #[test]
+    fn test_project_onto() {
+        let velocity = Vector([3., -4.]);
+
+        assert_eq!(velocity.project_onto(Vector([2., 0.])), Vector([3., 0.]));
+        assert_eq!(velocity.project_onto(Vector([0., -1.])), Vector([0., -4.]));
+        assert_eq!(
+            velocity.project_onto(Vector([0., 1.])) + velocity.project_onto(Vector([1., 0.])),
+            velocity
+        );
+    }
+
+    #[test]
+    fn test_bit_hash() {
+        let hasher = RandomState::new();
+        let x = BitHash(Vector([1.5f64, -2., f64::NAN]));
+        let y = BitHash(Vector([1.5f64, -2., f64::NAN]));
+
+        assert_eq!(x, y);
+        assert_eq!(hasher.hash_one(x), hasher.hash_one(y));
+        assert_ne!(BitHash(Vector([0f64])), BitHash(Vector([-0f64])));
+
+        let set = HashSet::from([x, y, BitHash(Vector([1.5, -2., 0.]))]);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn test_reference_arithmetic() {
+        let a = Vector([1.5, -2., 3.25, 0.5]);
+        let b = Vector([0.5, 4., -1., 2.]);
+
+        assert_eq!(&a + &b, a + b, "Expected `&a + &b == a + b`");
+        assert_eq!(&a - &b, a - b, "Expected `&a - &b == a - b`");
+        assert_eq!(&a * &b, a * b, "Expected `&a * &b == a * b`");
+        assert_eq!(&a / &b, a / b, "Expected `&a / &b == a / b`");
+        assert_eq!(&a % &b, a % b, "Expected `&a % &b == a % b`");
+        assert_eq!(-&a, -a, "Expected `-&a == -a`");
+    }
+
+    #[test]
+    fn test_sum_outer() {
+        let nested = Vector([Vector([1., 2.]), Vector([3., 4.]), Vector([-0.5, 0.5])]);
+
+        assert_eq!(nested.sum_outer(), Vector([3.5, 6.5]));
+        assert_eq!(
+            Vector::<Vector<f64, 2>, 0>([]).sum_outer(),
+            Vector([0., 0.])
+        );
+    }
+
+    #[test]
+    fn test_transpose() {
+        let nested = Vector([Vector([1, 2, 3]), Vector([4, 5, 6])]);
+
+        assert_eq!(
+            nested.transpose(),
+            Vector([Vector([1, 4]), Vector([2, 5]), Vector([3, 6])])
+        );
+        assert_eq!(nested.transpose().transpose(), nested);
+    }
+
+    #[test]
+    fn test_clamp() {
+        let min = Vector([0., -1.]);
+        let max = Vector([1., 1.]);
+
+        assert_eq!(Vector([0.5, -2.]).clamp(min, max), Vector([0.5, -1.]));
+        assert_eq!(Vector([3., 0.]).clamp(min, max), Vector([1., 0.]));
+    }
+
+    #[test]
+    fn test_explicit_endian_bytes() {
+        let v = Vector([1f32, -2.5, 1e-3]);
+
+        assert_eq!(&v.to_le_bytes()[..4], [0x00, 0x00, 0x80, 0x3f]);
+        assert_eq!(&v.to_be_bytes()[..4], [0x3f, 0x80, 0x00, 0x00]);
+        assert_eq!(Vector::<f32, 3>::from_le_bytes(&v.to_le_bytes()), v);
+        assert_eq!(Vector::<f32, 3>::from_be_bytes(&v.to_be_bytes()), v);
+    }
+
+    #[test]
+    fn test_cross_endian_bytes() {
+        let v = Vector([0.1f64, -7., 3e10]);
+        let mut swapped = v.to_le_bytes();
+        swapped.chunks_exact_mut(8).for_each(<[u8]>::reverse);
+
+        assert_eq!(swapped, v.to_be_bytes());
+        assert_eq!(Vector::<f64, 3>::from_be_bytes(&swapped), v);
+    }
+
+    #[test]
+    fn test_rotate_dims() {
+        let v = Vector([1, 2, 3]);
+
+        assert_eq!(v.rotate_dims(1), Vector([3, 1, 2]));
+        assert_eq!(v.rotate_dims(2), Vector([2, 3, 1]));
+        assert_eq!(v.rotate_dims(4), v.rotate_dims(1));
+        assert_eq!(Vector::<i32, 0>([]).rotate_dims(1), Vector([]));
+    }
+
+    #[test]
+    fn test_par_from_idx() {
+        let f = |i: usize| (i as f64).sin() * i as f64;
+
+        assert_eq!(
+            Vector::<f64, 1024>::par_from_idx(f),
+            Vector::<f64, 1024>::from_idx(f)
+        );
+    }
+
+    #[test]
+    fn test_linspace_arange() {
+        assert_eq!(
+            Vector::<f64, 5>::linspace(0., 1.),
+            Vector([0., 0.25, 0.5, 0.75, 1.])
+        );
+        assert_eq!(Vector::<f64, 1>::linspace(2., 3.), Vector([2.]));
+        assert_eq!(
+            Vector::<f32, 4>::arange(1., -0.5),
+            Vector([1., 0.5, 0., -0.5])
+        );
+    }
+
+    #[test]
+    fn test_cumsum_cumprod() {
+        assert_eq!(Vector([1, 2, 3, 4]).cumsum(), Vector([1, 3, 6, 10]));
+        assert_eq!(Vector([2., -1., 0.5]).cumsum(), Vector([2., 1., 1.5]));
+        assert_eq!(Vector([1, 2, 3, 4]).cumprod(), Vector([1, 2, 6, 24]));
+        assert_eq!(Vector([2., -1., 0.5]).cumprod(), Vector([2., -2., -1.]));
+        assert_eq!(Vector::<u8, 0>([]).cumsum(), Vector([]));
+    }
+
+    #[test]
+    fn test_try_from_idx() {
+        let parsed = Vector::<u8, 3>::try_from_idx(|i| ["1", "2", "3"][i].parse());
+        assert_eq!(parsed, Ok(Vector([1, 2, 3])));
+
+        let calls = std::cell::Cell::new(0);
+        let failed = Vector::<u8, 4>::try_from_idx(|i| {
+            calls.set(calls.get() + 1);
+            u8::try_from(i as i32 * 200 - 100)
+        });
+        assert!(
+            failed.is_err(),
+            "Expected the negative first component to fail"
+        );
+        assert_eq!(calls.get(), 1, "Expected to stop at the first error");
+    }
+
+    #[test]
+    fn test_integer_overflow_variants() {
+        let x = Vector([usize::MAX - 1, 2]);
+        let y = Vector([3, 4]);
+
+        assert_eq!(x.saturating_add(y), Vector([usize::MAX, 6]));
+        assert_eq!(x.wrapping_add(y), Vector([1, 6]));
+        assert_eq!(x.checked_add(y), None);
+        assert_eq!(y.checked_add(y), Some(Vector([6, 8])));
+        assert_eq!(x.checked_mul(y), None);
+        assert_eq!(y.checked_mul(y), Some(Vector([9, 16])));
+    }
 }
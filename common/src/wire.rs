@@ -0,0 +1,183 @@
+use crate::{Float, FloatType};
+
+/// The length of the header of an encoded message: the dtype byte followed by the number of
+/// elements as a little-endian `u64`.
+const HEADER_LEN: usize = 1 + std::mem::size_of::<u64>();
+
+/// The error returned when decoding a message fails.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WireError {
+    /// The message is too short to hold its header.
+    #[error("Expected a message of at least {HEADER_LEN} bytes but got {0}")]
+    Truncated(usize),
+    /// The message holds elements of another float type.
+    #[error("Expected elements of type {expected} but got dtype {actual:?}")]
+    DType {
+        /// The float type that was expected.
+        expected: FloatType,
+        /// The dtype byte of the message.
+        actual: char,
+    },
+    /// The payload doesn't match the number of elements given in the header.
+    #[error("Expected a payload of {expected} bytes but got {actual}")]
+    Length {
+        /// The length of the payload announced by the header.
+        expected: usize,
+        /// The actual length of the payload.
+        actual: usize,
+    },
+}
+
+/// A compact binary encoding of messages between the components of an experiment, e.g. when
+/// running them on different machines.
+pub trait WireFormat: Sized {
+    /// Encodes the message into bytes.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Decodes a message encoded by [`WireFormat::to_bytes`].
+    fn from_bytes(bytes: &[u8]) -> Result<Self, WireError>;
+}
+
+/// Converts the encoded elements of type `T` between native and little-endian byte order in
+/// place. This is a no-op on little-endian targets.
+fn swap_to_le<T: Float>(payload: &mut [u8]) {
+    if cfg!(target_endian = "big") {
+        payload
+            .chunks_exact_mut(std::mem::size_of::<T>())
+            .for_each(<[u8]>::reverse);
+    }
+}
+
+/// Encodes a slice of floats, prefixed by a header of their dtype (the `array.array` specifier
+/// [`FloatType::type`]) and number. Like the header, the elements are encoded in little-endian
+/// byte order, so messages can be exchanged between hosts of either endianness.
+pub fn encode<T: Float>(data: &[T]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + std::mem::size_of_val(data));
+    bytes.push(T::float_type().r#type() as u8);
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(bytemuck::cast_slice(data));
+    swap_to_le::<T>(&mut bytes[HEADER_LEN..]);
+
+    bytes
+}
+
+/// Decodes a slice of floats encoded by [`encode`], checking the dtype and length in the header.
+pub fn decode<T: Float>(bytes: &[u8]) -> Result<Vec<T>, WireError> {
+    let Some((&dtype, rest)) = bytes.split_first() else {
+        return Err(WireError::Truncated(bytes.len()));
+    };
+    let Some((len, payload)) = rest.split_first_chunk::<{ HEADER_LEN - 1 }>() else {
+        return Err(WireError::Truncated(bytes.len()));
+    };
+
+    let expected = T::float_type();
+    if dtype != expected.r#type() as u8 {
+        return Err(WireError::DType {
+            expected,
+            actual: dtype as char,
+        });
+    }
+    let len = u64::from_le_bytes(*len) as usize;
+    if payload.len() != len * std::mem::size_of::<T>() {
+        return Err(WireError::Length {
+            expected: len * std::mem::size_of::<T>(),
+            actual: payload.len(),
+        });
+    }
+
+    // The payload need not be aligned, so it is copied rather than cast in place.
+    let mut data = vec![T::zero(); len];
+    let data_bytes = bytemuck::cast_slice_mut(&mut data);
+    data_bytes.copy_from_slice(payload);
+    swap_to_le::<T>(data_bytes);
+
+    Ok(data)
+}
+
+impl<T: Float> WireFormat for Vec<T> {
+    fn to_bytes(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        decode(bytes)
+    }
+}
+
+/// A bare float is encoded as a message of a single element.
+impl<T: Float> WireFormat for T {
+    fn to_bytes(&self) -> Vec<u8> {
+        encode(std::slice::from_ref(self))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        match decode(bytes)?[..] {
+            [value] => Ok(value),
+            ref data => Err(WireError::Length {
+                expected: std::mem::size_of::<T>(),
+                actual: std::mem::size_of_val(data),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, WireError, WireFormat};
+    use crate::FloatType;
+
+    #[test]
+    fn test_round_trip() {
+        let data = vec![1.5f64, -2., 0., f64::INFINITY];
+        assert_eq!(Vec::<f64>::from_bytes(&data.to_bytes()), Ok(data));
+
+        let data = [0.25f32; 5];
+        assert_eq!(decode::<f32>(&encode(&data)).unwrap(), data);
+        assert_eq!(decode::<f32>(&encode::<f32>(&[])), Ok(vec![]));
+        assert_eq!(f64::from_bytes(&0.125f64.to_bytes()), Ok(0.125));
+    }
+
+    #[test]
+    fn test_little_endian_payload() {
+        let bytes = encode(&[1.5f32, -2.]);
+
+        assert_eq!(
+            bytes[9..],
+            [1.5f32.to_le_bytes(), (-2f32).to_le_bytes()].concat(),
+            "Expected the elements to be encoded in little-endian byte order"
+        );
+        assert_eq!(
+            decode::<f64>(&[&[b'd'][..], &1u64.to_le_bytes(), &0.75f64.to_le_bytes()].concat()),
+            Ok(vec![0.75]),
+            "Expected a little-endian payload to decode on any host"
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = encode(&[1f32, 2.]);
+
+        assert_eq!(
+            decode::<f64>(&bytes),
+            Err(WireError::DType {
+                expected: FloatType::F64,
+                actual: 'f'
+            })
+        );
+        assert_eq!(decode::<f32>(&bytes[..4]), Err(WireError::Truncated(4)));
+        assert_eq!(
+            decode::<f32>(&bytes[..bytes.len() - 1]),
+            Err(WireError::Length {
+                expected: 8,
+                actual: 7
+            })
+        );
+        assert_eq!(
+            f32::from_bytes(&bytes),
+            Err(WireError::Length {
+                expected: 4,
+                actual: 8
+            })
+        );
+    }
+}
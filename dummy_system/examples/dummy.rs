@@ -1,6 +1,6 @@
-use common::coordinator::experiment;
+use common::coordinator::{experiment, ExperimentConfig};
 use dummy_system::{
-    TrivialSystem, TrivialSystemAgent, TrivialSystemGenerator, TrivialSystemSimulator, TrivialSystemState, TrivialSystemStatePredictor
+    TrivialSystem, TrivialSystemAgent, TrivialSystemGenerator, TrivialSystemSimulator, TrivialSystemState, TrivialWindowPredictor
 };
 use smol::block_on;
 
@@ -14,14 +14,19 @@ fn main() {
         requested_time: 0.,
     };
     let driver = TrivialSystemAgent { time: (0.).into() };
-    let state_predictor = TrivialSystemStatePredictor;
+    let state_predictor = TrivialWindowPredictor;
 
-    block_on(experiment(
+    let result = block_on(experiment(
         &system,
         driver,
         generator,
         simulator,
         state_predictor,
-        1e-3,
+        ExperimentConfig::new(1e-3).max_steps(100).progress(true),
     ));
+
+    println!(
+        "Finished after {} steps at time {} with dynamics loss {}",
+        result.steps, result.time, result.dynamics_loss
+    );
 }
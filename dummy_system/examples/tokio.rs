@@ -0,0 +1,36 @@
+use common::coordinator::{experiment, ExperimentConfig};
+use dummy_system::{
+    TrivialSystem, TrivialSystemAgent, TrivialSystemGenerator, TrivialSystemSimulator,
+    TrivialSystemState, TrivialWindowPredictor,
+};
+
+/// Runs the dummy experiment on a single-threaded `tokio` runtime, as part of a larger async
+/// application would.
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let system = TrivialSystem;
+    let simulator = TrivialSystemSimulator {
+        states: vec![TrivialSystemState { time: 0. }; 24].into(),
+    };
+    let generator = TrivialSystemGenerator {
+        time: 0.,
+        requested_time: 0.,
+    };
+    let driver = TrivialSystemAgent { time: (0.).into() };
+    let state_predictor = TrivialWindowPredictor;
+
+    let result = experiment(
+        &system,
+        driver,
+        generator,
+        simulator,
+        state_predictor,
+        ExperimentConfig::new(1e-3).max_steps(20),
+    )
+    .await;
+
+    println!(
+        "Finished after {} steps at time {} with dynamics loss {}",
+        result.steps, result.time, result.dynamics_loss
+    );
+}
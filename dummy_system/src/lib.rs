@@ -19,6 +19,8 @@ pub struct TrivialSystemState {
 }
 
 impl DynamicVector<f64> for TrivialSystemState {
+    const LEN: Option<usize> = Some(1);
+
     fn copy_from_slice(&mut self, v: &[f64]) {
         std::slice::from_mut(&mut self.time).clone_from_slice(v);
     }
@@ -72,6 +74,8 @@ pub struct TrivialSystemAgent {
 
 pub struct TrivialSystemStatePredictor;
 
+pub struct TrivialWindowPredictor;
+
 impl SimulatorInterface<f64, TrivialSystem> for TrivialSystemSimulator {
     async fn update(&mut self, _system: &TrivialSystem, dt: f64, _control_signal: &()) {
         println!("{}", "TrivialSystemSimulator::update".green());
@@ -134,3 +138,84 @@ impl StatePredictionInterface<f64, TrivialSystem> for TrivialSystemStatePredicto
         *observation.last().unwrap()
     }
 }
+
+impl StatePredictionInterface<f64, TrivialSystem> for TrivialWindowPredictor {
+    async fn predict_state(
+        &mut self,
+        observation: &[<TrivialSystem as System<f64>>::SystemObservation],
+    ) -> <TrivialSystem as System<f64>>::LatentState {
+        observation.iter().sum::<f64>() / observation.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use common::{
+        coordinator::{experiment, sweep, ExperimentConfig, ExperimentSetup},
+        interfaces::StatePredictionInterface,
+    };
+    use smol::block_on;
+
+    use super::{
+        TrivialSystem, TrivialSystemAgent, TrivialSystemGenerator, TrivialSystemSimulator,
+        TrivialSystemState, TrivialSystemStatePredictor, TrivialWindowPredictor,
+    };
+
+    #[test]
+    fn test_experiment_reports_max_steps() {
+        let simulator = TrivialSystemSimulator {
+            states: VecDeque::from(vec![TrivialSystemState { time: 0. }; 4]),
+        };
+        let generator = TrivialSystemGenerator {
+            time: 0.,
+            requested_time: 0.,
+        };
+        let driver = TrivialSystemAgent { time: (0.).into() };
+
+        let result = block_on(experiment(
+            &TrivialSystem,
+            driver,
+            generator,
+            simulator,
+            TrivialSystemStatePredictor,
+            ExperimentConfig::new(0.25).max_steps(5),
+        ));
+
+        assert_eq!(result.steps, 5);
+        assert_eq!(result.time, 1.25);
+    }
+
+    #[test]
+    fn test_sweep_runs_every_seed() {
+        let setup = ExperimentSetup::<f64, TrivialSystem>::new((), (), 0.25, 0).max_steps(3);
+
+        // Start every run at its seed, to tell the results apart.
+        let results = block_on(sweep(&TrivialSystem, &setup, &[1, 2, 3], |seed| {
+            let simulator = TrivialSystemSimulator {
+                states: VecDeque::from(vec![TrivialSystemState { time: seed as f64 }; 2]),
+            };
+            let generator = TrivialSystemGenerator {
+                time: 0.,
+                requested_time: 0.,
+            };
+            let driver = TrivialSystemAgent { time: (0.).into() };
+            (driver, generator, simulator, TrivialSystemStatePredictor)
+        }));
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.steps == 3));
+        assert_eq!(
+            results.iter().map(|result| result.time).collect::<Vec<_>>(),
+            [1.75, 2.75, 3.75]
+        );
+    }
+
+    #[test]
+    fn test_window_predictor_averages() {
+        let latent = block_on(TrivialWindowPredictor.predict_state(&[0.5, 1., 1.5, 3.]));
+
+        assert_eq!(latent, 1.5);
+    }
+}
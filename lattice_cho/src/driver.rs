@@ -1,7 +1,10 @@
 use pyo3::prelude::PyAnyMethods;
 use std::marker::PhantomData;
 
-use crate::system::{ControlParameterState, CoupledHarmonicOscillator, StateTensor};
+use crate::system::{
+    ControlParameterState, CoupledHarmonicOscillator, ShapeSignatures, SimulationConfig,
+    StateTensor,
+};
 use common::{interfaces::DriverInterface, python::set_venv_site_packages, system::System, Float};
 use pyo3::{
     types::{IntoPyDict, PyDict, PyModule},
@@ -15,13 +18,18 @@ pub struct PythonDriver<T: Float, const DIMS: usize> {
     globals: Py<PyDict>,
     /// The set of locally accessible variables.
     locals: Py<PyDict>,
+    /// The shape signatures of the configurations the driver has been used with.
+    signatures: ShapeSignatures,
+    /// The configuration of the simulation that the driver is used with.
+    config: SimulationConfig<T, DIMS>,
     /// Phantom to make `T` and `DIMS` relevant.
     _phantom: PhantomData<[T; DIMS]>,
 }
 
 impl<T: Float, const DIMS: usize> PythonDriver<T, DIMS> {
-    /// Produces an instance of the [`PythonDriver`].
-    pub fn new() -> Self {
+    /// Produces an instance of the [`PythonDriver`] for the simulation with the given
+    /// configuration.
+    pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
         pyo3::prepare_freethreaded_python();
 
         let [jax, np, driver] = Python::with_gil(|py| {
@@ -47,6 +55,8 @@ impl<T: Float, const DIMS: usize> PythonDriver<T, DIMS> {
         Self {
             globals,
             locals,
+            signatures: ShapeSignatures::default(),
+            config,
             _phantom: PhantomData,
         }
     }
@@ -81,12 +91,8 @@ impl<T: Float, const DIMS: usize> DriverInterface<T, CoupledHarmonicOscillator<T
         state_estimate: StateTensor<T, DIMS>,
         dynamics_loss: T,
     ) -> ControlParameterState<T, DIMS> {
+        // Logs once per new shape signature, since it triggers a JAX recompilation.
+        self.signatures.observe("PythonDriver", &self.config);
         todo!()
     }
 }
-
-impl<T: Float, const DIMS: usize> Default for PythonDriver<T, DIMS> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
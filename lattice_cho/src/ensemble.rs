@@ -0,0 +1,251 @@
+use common::{interfaces::SimulatorInterface, Float};
+use rayon::prelude::*;
+
+use crate::{
+    simulator::RustSimulator,
+    system::{ControlSignalState, CoupledHarmonicOscillator, Observation, SimulationConfig},
+};
+
+/// Simulates an ensemble of independent [`CoupledHarmonicOscillator`] lattices that are stepped
+/// together, for batched training.
+///
+/// The control signal of the ensemble is the concatenation of the control signals of its
+/// members, and the observations of the ensemble are the concatenation of the observations of
+/// its members, both in member order.
+pub struct EnsembleSimulator<T: Float, const DIMS: usize> {
+    /// The simulators of the members of the ensemble.
+    members: Vec<RustSimulator<T, DIMS>>,
+}
+
+impl<T: Float, const DIMS: usize> EnsembleSimulator<T, DIMS> {
+    /// Creates an ensemble of `size` lattices sharing the given config.
+    pub fn new(config: SimulationConfig<T, DIMS>, size: usize) -> Self {
+        Self::from_members((0..size).map(|_| RustSimulator::new(config)).collect())
+    }
+
+    /// Creates an ensemble from the simulators of its members, e.g. to give them different
+    /// forcings.
+    ///
+    /// # Panics
+    /// If there are no members.
+    pub fn from_members(members: Vec<RustSimulator<T, DIMS>>) -> Self {
+        assert!(!members.is_empty(), "Expected a non-empty ensemble");
+        Self { members }
+    }
+
+    /// The simulators of the members of the ensemble.
+    pub fn members(&self) -> &[RustSimulator<T, DIMS>] {
+        &self.members
+    }
+}
+
+impl<T: Float + Send + Sync, const DIMS: usize>
+    SimulatorInterface<T, CoupledHarmonicOscillator<T, DIMS>> for EnsembleSimulator<T, DIMS>
+{
+    async fn get_observations(&self) -> Vec<Observation<T, DIMS>> {
+        let mut observations = Vec::new();
+        for member in &self.members {
+            observations.extend(member.get_observations().await);
+        }
+
+        observations
+    }
+
+    async fn update(
+        &mut self,
+        system: &CoupledHarmonicOscillator<T, DIMS>,
+        dt: T,
+        control_signal: &ControlSignalState<T, DIMS>,
+    ) {
+        self.step_n(system, dt, control_signal, 1).await;
+    }
+
+    /// Steps the members in parallel, each through its own [`SimulatorInterface::step_n`] under
+    /// its own slice of the control signal, so that their recorders see their own controls.
+    ///
+    /// # Panics
+    /// If the control signal isn't the concatenation of a control signal for each member.
+    async fn step_n(
+        &mut self,
+        system: &CoupledHarmonicOscillator<T, DIMS>,
+        dt: T,
+        control_signal: &ControlSignalState<T, DIMS>,
+        n: usize,
+    ) {
+        let per_member_len = self.members[0].control_len();
+        assert_eq!(
+            control_signal.0.len(),
+            self.members.len() * per_member_len,
+            "Expected a control signal of length {per_member_len} for each of the {} members",
+            self.members.len()
+        );
+        let controls = control_signal
+            .0
+            .chunks_exact(per_member_len)
+            .map(|chunk| ControlSignalState::new(chunk.to_vec()))
+            .collect::<Vec<_>>();
+
+        let (tx, rx) = futures::channel::oneshot::channel();
+        let members = &mut self.members;
+
+        rayon::scope(|s| {
+            s.spawn(move |_| {
+                // A member's `step_n` finishes its work inside its own `rayon::scope`, so blocking
+                // on it only waits for work that has already completed.
+                members
+                    .par_iter_mut()
+                    .zip(&controls)
+                    .for_each(|(member, control)| {
+                        futures::executor::block_on(member.step_n(system, dt, control, n))
+                    });
+            });
+
+            tx.send(()).unwrap()
+        });
+
+        rx.await.unwrap();
+    }
+
+    fn get_time(&self) -> T {
+        self.members[0].get_time()
+    }
+
+    /// The mean dynamics loss of the members.
+    async fn get_dynamics_loss(&self) -> T {
+        let mut loss = T::zero();
+        for member in &self.members {
+            loss = loss + member.get_dynamics_loss().await;
+        }
+
+        loss / T::from(self.members.len()).unwrap()
+    }
+
+    async fn is_stable(&self) -> bool {
+        for member in &self.members {
+            if !member.is_stable().await {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use common::{interfaces::SimulatorInterface, recorder::TrajectoryRecorder, vector::Vector};
+    use futures::executor::block_on;
+
+    use super::EnsembleSimulator;
+    use crate::{
+        simulator::RustSimulator,
+        system::{
+            ControlSignalState, CoupledHarmonicOscillator, SimulationConfig, SimulationState,
+            DELAY_DEPTH,
+        },
+    };
+
+    /// A recorder that logs the recorded control signals in memory.
+    struct ControlLog(Arc<Mutex<Vec<Vec<f64>>>>);
+
+    impl TrajectoryRecorder<f64, CoupledHarmonicOscillator<f64, 2>> for ControlLog {
+        fn record_state(&mut self, _time: f64, _state: &SimulationState<f64, 2>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn record_control(
+            &mut self,
+            _time: f64,
+            control: &ControlSignalState<f64, 2>,
+        ) -> io::Result<()> {
+            self.0.lock().unwrap().push(control.0.to_vec());
+            Ok(())
+        }
+    }
+
+    /// A small lattice configuration for testing.
+    fn config() -> SimulationConfig<f64, 2> {
+        SimulationConfig {
+            size: 4,
+            stiffness: 1.,
+            origin_stiffness: 1.,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        }
+    }
+
+    /// A member simulator driven by a constant forcing depending on its index in the ensemble.
+    fn member(i: usize) -> RustSimulator<f64, 2> {
+        let force = Vector::new([0.1 * i as f64, -0.05]);
+        RustSimulator::new(config()).with_forcing(move |_, _| force)
+    }
+
+    #[test]
+    fn test_ensemble_matches_members() {
+        let system = CoupledHarmonicOscillator::default();
+        let control = ControlSignalState::default(config());
+        let mut ensemble = EnsembleSimulator::from_members((0..3).map(member).collect());
+        let mut members = (0..3).map(member).collect::<Vec<_>>();
+
+        let ensemble_control = ControlSignalState::new(control.0.repeat(3));
+        for _ in 0..5 {
+            block_on(ensemble.update(&system, 1e-2, &ensemble_control));
+        }
+        for member in &mut members {
+            block_on(member.step_n(&system, 1e-2, &control, 5));
+        }
+
+        assert_eq!(ensemble.get_time(), members[0].get_time());
+        for (i, (actual, expected)) in ensemble.members().iter().zip(&members).enumerate() {
+            assert_eq!(
+                actual.interior_displacement_norm(),
+                expected.interior_displacement_norm(),
+                "Expected member {i} to evolve as if stepped on its own"
+            );
+        }
+        assert_eq!(
+            block_on(ensemble.get_observations()).len(),
+            3 * DELAY_DEPTH,
+            "Expected the observations of every member"
+        );
+    }
+
+    #[test]
+    fn test_members_record_own_controls() {
+        let system = CoupledHarmonicOscillator::default();
+        let logs = (0..3)
+            .map(|_| Arc::new(Mutex::new(Vec::new())))
+            .collect::<Vec<_>>();
+        let mut ensemble = EnsembleSimulator::from_members(
+            logs.iter()
+                .map(|log| RustSimulator::new(config()).with_recorder(ControlLog(log.clone())))
+                .collect(),
+        );
+
+        let control = (0..3).flat_map(|i| [i as f64; 12]).collect::<Vec<_>>();
+        block_on(ensemble.step_n(&system, 1e-2, &ControlSignalState::new(control), 2));
+
+        for (i, log) in logs.iter().enumerate() {
+            assert_eq!(
+                *log.lock().unwrap(),
+                [vec![i as f64; 12], vec![i as f64; 12]],
+                "Expected member {i} to record its own slice of the control signal at every step"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "for each of the 3 members")]
+    fn test_rejects_uneven_control() {
+        let mut ensemble = EnsembleSimulator::new(config(), 3);
+        let control = ControlSignalState::new(vec![0.; 3 * 4]);
+        block_on(ensemble.update(&CoupledHarmonicOscillator::default(), 1e-2, &control));
+    }
+}
@@ -1,27 +1,29 @@
 use crate::system::{
     ControlParameterState, ControlSignalState, CoupledHarmonicOscillator, SimulationConfig,
 };
-use common::{
-    interfaces::GeneratorInterface,
-    system::System,
-    Float,
-};
+use common::{interfaces::GeneratorInterface, Float};
 use futures::lock::Mutex;
 
 /// Generates a signal given the last set [`ControlParameterState`] and the time since being set.
 /// This is designed for the [`CoupledHarmonicOscillator`] system.
-pub struct SignalGenerator<T: Float, const DIMS: usize>(
-    Mutex<(ControlParameterState<T, DIMS>, T)>,
-    ControlSignalState<T, DIMS>,
-);
+pub struct SignalGenerator<T: Float, const DIMS: usize> {
+    /// The last set control parameters and the time they were set at.
+    parameters: Mutex<(ControlParameterState<T, DIMS>, T)>,
+    /// The last generated control signal, shared by the returned signals until the parameters
+    /// change.
+    signal: ControlSignalState<T, DIMS>,
+    /// Whether the parameters changed since the control signal was last generated.
+    dirty: bool,
+}
 
 impl<T: Float, const DIMS: usize> SignalGenerator<T, DIMS> {
     /// Instantiates a new [`SignalGenerator`] based on the given [`SimulationConfig`].
     pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
-        SignalGenerator(
-            Mutex::new((ControlParameterState::default(config), T::zero())),
-            ControlSignalState::default(config),
-        )
+        SignalGenerator {
+            parameters: Mutex::new((ControlParameterState::default(config), T::zero())),
+            signal: ControlSignalState::default(config),
+            dirty: false,
+        }
     }
 }
 
@@ -29,26 +31,137 @@ impl<T: Float, const DIMS: usize> GeneratorInterface<T, CoupledHarmonicOscillato
     for SignalGenerator<T, DIMS>
 {
     async fn set_parameters(&mut self, controls: ControlParameterState<T, DIMS>, time: T) {
-        let mut lock = self.0.lock().await;
-        lock.0 .0.clear();
-        lock.0 .0.copy_from_slice(&controls.0);
-        lock.1 = time;
+        *self.parameters.lock().await = (controls, time);
+        self.dirty = true;
     }
 
+    /// Only regenerates the signal if the parameters changed since the last call, and otherwise
+    /// returns a cheap clone of the cached signal.
     fn control_signal(&mut self, _time: T) -> ControlSignalState<T, DIMS> {
-        ControlSignalState::new(self.1 .0.clone())
+        if self.dirty {
+            let (parameters, _) = self.parameters.get_mut();
+            self.signal = ControlSignalState::new(parameters.0.clone());
+            self.dirty = false;
+        }
+
+        self.signal.clone()
     }
 }
 
 /// Produces a constant zero signal.
-pub struct DummySignalGenerator;
+pub struct DummySignalGenerator<T: Float, const DIMS: usize> {
+    /// The zero signal, shaped for the configured lattice.
+    signal: ControlSignalState<T, DIMS>,
+}
+
+impl<T: Float, const DIMS: usize> DummySignalGenerator<T, DIMS> {
+    /// Instantiates a new [`DummySignalGenerator`] based on the given [`SimulationConfig`].
+    pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
+        Self {
+            signal: ControlSignalState::default(config),
+        }
+    }
+}
 
 impl<T: Float, const DIMS: usize> GeneratorInterface<T, CoupledHarmonicOscillator<T, DIMS>>
-    for DummySignalGenerator
+    for DummySignalGenerator<T, DIMS>
 {
     async fn set_parameters(&mut self, _controls: ControlParameterState<T, DIMS>, _time: T) {}
 
     fn control_signal(&mut self, _time: T) -> ControlSignalState<T, DIMS> {
-        ControlSignalState::new(vec![T::zero(); CoupledHarmonicOscillator::<T, DIMS>::CONTROL_SIGNAL_SIZE])
+        self.signal.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::{generator::SumGenerator, interfaces::GeneratorInterface};
+    use futures::executor::block_on;
+
+    use super::{DummySignalGenerator, SignalGenerator};
+    use crate::system::{
+        ControlParameterState, ControlSignalState, CoupledHarmonicOscillator, SimulationConfig,
+    };
+
+    /// A generator producing a constant bias on every boundary site.
+    struct BiasGenerator(ControlSignalState<f64, 2>);
+
+    impl GeneratorInterface<f64, CoupledHarmonicOscillator<f64, 2>> for BiasGenerator {
+        async fn set_parameters(&mut self, _controls: ControlParameterState<f64, 2>, _time: f64) {}
+
+        fn control_signal(&mut self, _time: f64) -> ControlSignalState<f64, 2> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_control_signal_cached() {
+        let config = SimulationConfig::<_, 2> {
+            size: 4,
+            stiffness: 1f64,
+            origin_stiffness: 1.,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        };
+        let mut generator = SignalGenerator::new(config);
+
+        let first = generator.control_signal(0.);
+        let second = generator.control_signal(0.1);
+        assert!(
+            Arc::ptr_eq(&first.0, &second.0),
+            "Expected repeated calls to share the cached signal"
+        );
+
+        let parameters = (0..12).map(f64::from).collect::<Vec<_>>();
+        block_on(generator.set_parameters(ControlParameterState::new(parameters.clone()), 0.2));
+        let third = generator.control_signal(0.2);
+        assert!(
+            !Arc::ptr_eq(&second.0, &third.0),
+            "Expected new parameters to regenerate the signal"
+        );
+        assert_eq!(*third.0, parameters[..]);
+        assert!(
+            Arc::ptr_eq(&third.0, &generator.control_signal(0.3).0),
+            "Expected the regenerated signal to be cached"
+        );
+    }
+
+    #[test]
+    fn test_sum_generator() {
+        let config = SimulationConfig::<_, 2> {
+            size: 4,
+            stiffness: 1f64,
+            origin_stiffness: 1.,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        };
+        let bias = (0..12).map(|i| f64::from(i) / 4.).collect::<Vec<_>>();
+        let mut generator = SumGenerator::new(
+            SignalGenerator::new(config),
+            SumGenerator::new(
+                DummySignalGenerator::new(config),
+                BiasGenerator(ControlSignalState::new(bias.clone())),
+            ),
+        );
+
+        assert_eq!(
+            *generator.control_signal(0.).0,
+            bias[..],
+            "Expected the bias on top of the zero signals"
+        );
+
+        let parameters = vec![1.; 12];
+        block_on(generator.set_parameters(ControlParameterState::new(parameters), 0.1));
+        assert_eq!(
+            *generator.control_signal(0.1).0,
+            bias.iter().map(|x| x + 1.).collect::<Vec<_>>()[..],
+            "Expected the parameters to be forwarded to the learned signal"
+        );
     }
 }
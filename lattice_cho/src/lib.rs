@@ -27,6 +27,10 @@ pub mod generator;
 /// Defines the time evolution for our system.
 pub mod simulator;
 
+/// Defines the [`EnsembleSimulator`](crate::ensemble::EnsembleSimulator) stepping several
+/// independent lattices together.
+pub mod ensemble;
+
 /// Contains the system definition and relevant types for a simple coupled harmonic oscillator
 /// system. Defines the
 /// [`CoupledHarmonicOscillator<T: Scalar, const DIMS: usize>`](crate::system::CoupledHarmonicOscillator)
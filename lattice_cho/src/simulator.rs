@@ -1,9 +1,19 @@
-use common::{interfaces::SimulatorInterface, vector::Vector, Float};
+use std::time::{Duration, Instant};
+
+use common::{
+    interfaces::{axis_labels, SimulatorInterface},
+    noise::{GaussianNoise, Rng},
+    python::JaxArray,
+    recorder::TrajectoryRecorder,
+    ring::ring_indices,
+    system::DynamicVector,
+    vector::Vector,
+    Float,
+};
 
 use crate::system::{
-    deindex, index, ControlSignalState, CoupledHarmonicOscillator,
-    ObservableSimulationState, ObservableState, Observation, SimulationConfig, SimulationState,
-    DELAY_DEPTH,
+    deindex, index, ControlSignalState, CoupledHarmonicOscillator, ObservableSimulationState,
+    Observation, ObservationRef, SimulationConfig, SimulationState, DELAY_DEPTH,
 };
 use rayon::prelude::*;
 
@@ -17,16 +27,92 @@ trait Two: num::Num {
 
 impl<T: num::Num> Two for T {}
 
+/// The largest displacement of a lattice point from its equilibrium position for which the
+/// simulation is still considered stable.
+const MAX_STABLE_DISPLACEMENT: f64 = 1e6;
+
+/// The number of preallocated acceleration buffers that the forces are computed into, see
+/// [`RustSimulator::warm_pool`].
+const WARM_POOL_SIZE: usize = 2;
+
+/// An analytic external forcing term `F(x, t)`, given the grid coordinate `x` of a lattice point
+/// and the current time `t`.
+pub type Forcing<T, const DIMS: usize> =
+    dyn Fn(Vector<T, DIMS>, T) -> Vector<T, DIMS> + Send + Sync;
+
+/// A [`TrajectoryRecorder`] of the [`CoupledHarmonicOscillator`] that can be held by the
+/// [`RustSimulator`].
+pub type Recorder<T, const DIMS: usize> =
+    dyn TrajectoryRecorder<T, CoupledHarmonicOscillator<T, DIMS>> + Send + Sync;
+
+/// The wall-clock durations of the sub-steps of a single Verlet step, as reported by
+/// [`RustSimulator::update_timed`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StepTimings {
+    /// The time spent computing the forces on the lattice points.
+    pub force: Duration,
+    /// The time spent updating (and constraining) the positions of the lattice points.
+    pub position: Duration,
+    /// The time spent updating the velocities of the lattice points.
+    pub velocity: Duration,
+}
+
+impl StepTimings {
+    /// The total time spent in the timed sub-steps.
+    pub fn total(&self) -> Duration {
+        self.force + self.position + self.velocity
+    }
+}
+
 /// The [`RustSimulator`] simulates the [`CoupledHarmonicOscillator`] system.
+///
+/// The simulator is [`Send`] and [`Sync`], as are the futures returned by its
+/// [`SimulatorInterface`] methods, so it can be driven on a multithreaded executor. This relies on
+/// the states being [`Send`] and [`Sync`] (as [`Float`] requires of `T`), since the Verlet steps
+/// borrow them from the threads of a [`rayon::scope`].
+///
+/// Unlike the `SHOSimulator` of the SHO system, it isn't generic over a
+/// [`common::integrator::Integrator`]: its Verlet step computes the forces in parallel into the
+/// pooled buffers and applies the forcing, damping and bounds in between, none of which the
+/// per-step, single-threaded integrators can express.
 pub struct RustSimulator<T: Float, const DIMS: usize> {
-    /// The last `[DELAY_DEPTH] + 1` steps in the system's evolution.
-    simulation_states: [SimulationState<T, DIMS>; DELAY_DEPTH + 1],
-    /// The last `[DELAY_DEPTH] + 1` steps in the system's observations.
-    observable_substates: [ObservableSimulationState<T, DIMS>; DELAY_DEPTH + 1],
-    /// The last `[DELAY_DEPTH] + 1` steps in the system's observations.
-    control_states: [ControlSignalState<T, DIMS>; DELAY_DEPTH + 1],
+    /// The last `observation_stride * [DELAY_DEPTH] + 1` steps in the system's evolution.
+    simulation_states: Box<[SimulationState<T, DIMS>]>,
+    /// The last `observation_stride * [DELAY_DEPTH] + 1` steps in the system's observations.
+    observable_substates: Box<[ObservableSimulationState<T, DIMS>]>,
+    /// The last `observation_stride * [DELAY_DEPTH] + 1` steps in the system's controls.
+    control_states: Box<[ControlSignalState<T, DIMS>]>,
     /// The index of the current system state.
     offset: usize,
+    /// The number of steps between consecutive observations returned by
+    /// [`SimulatorInterface::get_observations`].
+    observation_stride: usize,
+    /// The external forcing applied to each lattice point independent of the control signal.
+    forcing: Option<Box<Forcing<T, DIMS>>>,
+    /// The noise added to the observed state, if any.
+    noise: Option<GaussianNoise<T>>,
+    /// The number of steps over which the stiffnesses are ramped up from zero, if any.
+    stiffness_ramp: Option<usize>,
+    /// The number of steps taken so far.
+    steps: usize,
+    /// Whether only the boundary lattice points are integrated, with the interior held fixed.
+    boundary_only: bool,
+    /// The rate at which the velocities of the lattice points decay, if any.
+    damping: Option<T>,
+    /// The recorder of the states and controls of every update, if any.
+    recorder: Option<Box<Recorder<T, DIMS>>>,
+    /// The preallocated scratch buffers that the forces are computed into, so that stepping never
+    /// allocates. The first buffer is swapped with the accelerations of the new state every step,
+    /// and the pool is then rotated.
+    warm_pool: [Box<[Vector<T, DIMS>]>; WARM_POOL_SIZE],
+}
+
+/// Whether the lattice point with the given scalar index lies on the boundary of a lattice of
+/// side-length `size`.
+fn is_boundary<const DIMS: usize>(i: usize, size: usize) -> bool {
+    deindex::<DIMS>(i, size)
+        .iter()
+        .any(|&x| x == 0 || x + 1 == size)
 }
 
 /// Index immutably twice into the array, where the first index parameter is less than the second
@@ -86,14 +172,197 @@ fn double_index_mut<T>(arr: &mut [T], i1: usize, i2: usize) -> (&mut T, &mut T)
 impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
     /// Create a new [`RustSimulator<T, DIMS>`] given a config: [`SimulationConfig`].
     pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
-        let simulation_states = std::array::from_fn(|_| SimulationState::new(config));
-        let observable_substates = std::array::from_fn(|_| ObservableSimulationState::new(config));
-        let control_states = std::array::from_fn(|_| ControlSignalState::default(config));
+        let simulation_states = vec![SimulationState::new(config); DELAY_DEPTH + 1];
+        let observable_substates = vec![ObservableSimulationState::new(config); DELAY_DEPTH + 1];
+        let control_states = vec![ControlSignalState::default(config); DELAY_DEPTH + 1];
+        let warm_pool = std::array::from_fn(|_| simulation_states[0].acceleration.clone());
         Self {
-            simulation_states,
-            observable_substates,
-            control_states,
+            simulation_states: simulation_states.into_boxed_slice(),
+            observable_substates: observable_substates.into_boxed_slice(),
+            control_states: control_states.into_boxed_slice(),
             offset: 0,
+            observation_stride: 1,
+            forcing: None,
+            noise: None,
+            stiffness_ramp: None,
+            steps: 0,
+            boundary_only: false,
+            damping: None,
+            recorder: None,
+            warm_pool,
+        }
+    }
+
+    /// Adds a time-dependent external forcing `F(x, t)` to every lattice point, where `x` is the
+    /// grid coordinate of the lattice point. The forcing is evaluated in parallel across the
+    /// lattice, so it must be [`Sync`].
+    pub fn with_forcing(
+        mut self,
+        forcing: impl Fn(Vector<T, DIMS>, T) -> Vector<T, DIMS> + Send + Sync + 'static,
+    ) -> Self {
+        self.forcing = Some(Box::new(forcing));
+        self
+    }
+
+    /// Adds Gaussian noise with the given standard deviation to the observed positions and
+    /// velocities, reproducibly drawn from a random number generator seeded by `seed`.
+    pub fn with_observation_noise(mut self, stddev: T, seed: u64) -> Self {
+        self.noise = Some(GaussianNoise::new(stddev, seed));
+        self
+    }
+
+    /// Adds Gaussian noise with the given standard deviation to the observed positions and
+    /// velocities, drawn from the
+    /// given random number generator, e.g. a [`JaxKey`](common::python::JaxKey).
+    pub fn with_observation_noise_rng(mut self, stddev: T, rng: impl Rng<T> + 'static) -> Self {
+        self.noise = Some(GaussianNoise::with_rng(stddev, rng));
+        self
+    }
+
+    /// Ramps the `stiffness` and `origin_stiffness` of the lattice linearly from zero up to their
+    /// configured values over the first `steps` steps, to avoid shocking the system when starting
+    /// from an arbitrary initial condition.
+    pub fn with_stiffness_ramp(mut self, steps: usize) -> Self {
+        self.stiffness_ramp = Some(steps);
+        self
+    }
+
+    /// Damps the motion of every lattice point by decaying its velocity by a factor of
+    /// `exp(-damping * dt)` each step.
+    pub fn with_damping(mut self, damping: T) -> Self {
+        self.damping = Some(damping);
+        self
+    }
+
+    /// Records the state and the control signal at the start of every update with the given
+    /// recorder, e.g. a [`CsvRecorder`](common::recorder::CsvRecorder).
+    pub fn with_recorder(
+        mut self,
+        recorder: impl TrajectoryRecorder<T, CoupledHarmonicOscillator<T, DIMS>> + Send + Sync + 'static,
+    ) -> Self {
+        self.recorder = Some(Box::new(recorder));
+        self
+    }
+
+    /// Only integrates the boundary lattice points, freezing the interior in its current state as
+    /// a fixed bath for reduced-order experiments. The interior still exerts forces on the
+    /// boundary, and bond constraints only ever move boundary points.
+    ///
+    /// The lattice doesn't respond to the control signal yet, so the boundary can only be driven
+    /// by an external forcing, see [`RustSimulator::with_forcing`].
+    pub fn with_boundary_only(mut self) -> Self {
+        self.boundary_only = true;
+        self
+    }
+
+    /// Spaces the observations returned by [`SimulatorInterface::get_observations`] `stride` steps
+    /// apart rather than taking the last [`DELAY_DEPTH`] consecutive steps, giving the state
+    /// predictor a wider temporal view. This enlarges the history buffers to
+    /// `stride * [DELAY_DEPTH] + 1` entries, all initialized to the current state.
+    ///
+    /// # Panics
+    /// If `stride` is zero.
+    pub fn with_observation_stride(mut self, stride: usize) -> Self {
+        assert!(stride > 0, "Expected a positive observation stride");
+        let len = stride * DELAY_DEPTH + 1;
+
+        self.simulation_states = vec![self.simulation_states[self.offset].clone(); len].into();
+        self.observable_substates =
+            vec![self.observable_substates[self.offset].clone(); len].into();
+        self.control_states = vec![self.control_states[self.offset].clone(); len].into();
+        self.offset = 0;
+        self.observation_stride = stride;
+        self
+    }
+
+    /// Sets the position of every lattice point in the current state to `f(x)`, where `x` is the
+    /// grid coordinate of the lattice point. Convenient for analytic initial conditions such as
+    /// plane waves or Gaussians.
+    pub fn init_positions_from(&mut self, f: impl Fn(Vector<usize, DIMS>) -> Vector<T, DIMS>) {
+        let state = &mut self.simulation_states[self.offset];
+        let size = state.size;
+
+        for (i, position) in state.position.iter_mut().enumerate() {
+            *position = f(deindex(i, size));
+        }
+    }
+
+    /// The fraction of the configured stiffnesses that is applied after the given number of steps.
+    fn stiffness_scale(stiffness_ramp: Option<usize>, steps: usize) -> T {
+        match stiffness_ramp {
+            Some(ramp) if steps < ramp => T::from(steps).unwrap() / T::from(ramp).unwrap(),
+            _ => T::one(),
+        }
+    }
+
+    /// The `(stiffness, origin_stiffness)` currently applied to the lattice, taking the stiffness
+    /// ramp into account.
+    pub fn effective_stiffness(&self) -> (T, T) {
+        let state = &self.simulation_states[self.offset];
+        let scale = Self::stiffness_scale(self.stiffness_ramp, self.steps);
+
+        (state.stiffness * scale, state.origin_stiffness * scale)
+    }
+
+    /// Exports the full current state as a [`JaxArray`] of shape `[size; DIMS] ++ [DIMS, 3]`, where
+    /// the last axis holds the position, velocity and acceleration of each lattice point in that
+    /// order.
+    pub fn state_to_jax(&self) -> JaxArray {
+        let state = &self.simulation_states[self.offset];
+        let data = state
+            .position
+            .iter()
+            .zip(&state.velocity)
+            .zip(&state.acceleration)
+            .flat_map(|((p, v), a)| (0..DIMS).flat_map(move |i| [p[i], v[i], a[i]]))
+            .collect::<Vec<_>>();
+        let shape = [state.size; DIMS]
+            .into_iter()
+            .chain([DIMS, 3])
+            .collect::<Vec<_>>();
+
+        JaxArray::new_nd(&data, &shape)
+    }
+
+    /// Borrows the last [`DELAY_DEPTH`] observations, most recent first and
+    /// [`RustSimulator::with_observation_stride`] steps apart, without copying them. Unlike
+    /// [`SimulatorInterface::get_observations`], no observation noise is applied.
+    pub fn observation_refs(&self) -> impl Iterator<Item = ObservationRef<'_, T, DIMS>> {
+        let len = self.simulation_states.len();
+        ring_indices::<DELAY_DEPTH>(self.offset, len - self.observation_stride, len)
+            .into_iter()
+            .map(move |i| ObservationRef {
+                time: self.simulation_states[i].time,
+                position: &self.observable_substates[i].position,
+                velocity: &self.observable_substates[i].velocity,
+                controls: &self.control_states[i],
+            })
+    }
+
+    /// Computes the sum of the squared displacements of the interior lattice points of the current
+    /// state, i.e. those not on the observed boundary. This measures how far motion has spread
+    /// from the boundary into the bulk of the lattice.
+    pub fn interior_displacement_norm(&self) -> T {
+        let state = &self.simulation_states[self.offset];
+        let size = state.size;
+
+        state
+            .position
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !is_boundary::<DIMS>(i, size))
+            .fold(T::zero(), |mut acc, (_, &p)| {
+                p.add_dot_into(p, &mut acc);
+                acc
+            })
+    }
+}
+
+/// Flushes the buffered records, so that they aren't lost if the experiment stops early.
+impl<T: Float, const DIMS: usize> Drop for RustSimulator<T, DIMS> {
+    fn drop(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            let _ = recorder.flush();
         }
     }
 }
@@ -102,47 +371,61 @@ impl<T: Float + Send + Sync, const DIMS: usize>
     SimulatorInterface<T, CoupledHarmonicOscillator<T, DIMS>> for RustSimulator<T, DIMS>
 {
     async fn get_observations(&self) -> Vec<Observation<T, DIMS>> {
-        std::array::from_fn::<_, DELAY_DEPTH, _>(|i| {
-            let i = (self.offset + i) % (DELAY_DEPTH + 1);
-            Observation {
-                time: self.observable_substates[i].time,
-                state: ObservableState {
-                    position: self.observable_substates[i].position.to_vec(),
-                    velocity: self.observable_substates[i].velocity.to_vec(),
-                },
-                controls: self.control_states[i].clone(),
+        let mut observations = self
+            .observation_refs()
+            .map(|observation| observation.to_owned())
+            .collect::<Vec<_>>();
+
+        if let Some(noise) = &self.noise {
+            for observation in &mut observations {
+                noise.apply(observation.state.get_rope_mut());
             }
-        })
-        .to_vec()
+        }
+
+        observations
+    }
+
+    async fn latest_observation(&self) -> Observation<T, DIMS> {
+        let mut observation = self
+            .observation_refs()
+            .next()
+            .expect("Expected at least one observation")
+            .to_owned();
+        if let Some(noise) = &self.noise {
+            noise.apply(observation.state.get_rope_mut());
+        }
+
+        observation
     }
 
     /// The update function here uses [Verlet
     /// integration](https://en.wikipedia.org/wiki/Verlet_integration#Velocity_Verlet)
     async fn update(
+        &mut self,
+        system: &CoupledHarmonicOscillator<T, DIMS>,
+        dt: T,
+        control_signal: &ControlSignalState<T, DIMS>,
+    ) {
+        self.step_n(system, dt, control_signal, 1).await;
+    }
+
+    /// Runs all `n` Verlet steps inside a single [`rayon::scope`].
+    async fn step_n(
         &mut self,
         _system: &CoupledHarmonicOscillator<T, DIMS>,
         dt: T,
-        _control_signal: &ControlSignalState<T, DIMS>,
+        control_signal: &ControlSignalState<T, DIMS>,
+        n: usize,
     ) {
-        let next_offset = (self.offset + 1) % (DELAY_DEPTH + 1);
         let (tx, rx) = futures::channel::oneshot::channel();
 
         rayon::scope(|s| {
-            let (current_state, next_state) =
-                double_index_mut(&mut self.simulation_states, self.offset, next_offset);
-
-            s.spawn(move |_| {
-                Self::par_update_position(current_state, dt);
-                Self::par_compute_forces(current_state, &mut next_state.acceleration);
-                Self::par_update_velocity(current_state, dt, &next_state.acceleration);
-                Self::update_time(current_state, next_state, dt);
-            });
+            s.spawn(|_| self.advance(dt, control_signal, n));
 
             tx.send(()).unwrap()
         });
 
         rx.await.unwrap();
-        self.offset += 1;
     }
 
     fn get_time(&self) -> T {
@@ -152,9 +435,212 @@ impl<T: Float + Send + Sync, const DIMS: usize>
     async fn get_dynamics_loss(&self) -> T {
         T::zero()
     }
+
+    fn observation_labels(&self) -> Vec<String> {
+        let observable = &self.observable_substates[self.offset];
+        let points = |prefix| {
+            (0..observable.position.len())
+                .flat_map(move |i| axis_labels(&format!("{prefix}_{i}"), DIMS).collect::<Vec<_>>())
+        };
+
+        points("pos")
+            .chain(points("vel"))
+            .chain((0..self.control_states[self.offset].0.len()).map(|i| format!("ctrl_{i}")))
+            .collect()
+    }
+
+    async fn is_stable(&self) -> bool {
+        let threshold = T::from(MAX_STABLE_DISPLACEMENT).unwrap();
+        let SimulationState {
+            ref position,
+            ref velocity,
+            ..
+        } = self.simulation_states[self.offset];
+
+        position
+            .par_iter()
+            .all(|p| p.iter().all(|x| x.abs() < threshold))
+            && velocity.par_iter().all(|v| v.is_finite())
+    }
 }
 
 impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
+    /// The length of the control signal the simulator expects.
+    pub(crate) fn control_len(&self) -> usize {
+        self.control_states[self.offset].0.len()
+    }
+
+    /// Synchronously performs `n` Verlet steps of size `dt` under the given control signal,
+    /// updating the lattice in parallel with [`rayon`]. The state and the control signal are
+    /// recorded before every step.
+    pub(crate) fn advance(&mut self, dt: T, control_signal: &ControlSignalState<T, DIMS>, n: usize)
+    where
+        T: Send + Sync,
+    {
+        for _ in 0..n {
+            if let Some(recorder) = &mut self.recorder {
+                let state = &self.simulation_states[self.offset];
+                // Failing to record the trajectory shouldn't stop the experiment.
+                let _ = recorder.record_state(state.time, state);
+                let _ = recorder.record_control(state.time, control_signal);
+            }
+            self.update_timed(dt);
+        }
+        self.observe_current();
+    }
+
+    /// Extracts the observable data of the current state into its slot of the observation history.
+    /// The earlier states are observed while their successors are integrated, see
+    /// [`RustSimulator::update_timed`], so only the latest state is left to observe.
+    fn observe_current(&mut self) {
+        let Self {
+            simulation_states,
+            observable_substates,
+            offset,
+            ..
+        } = self;
+        simulation_states[*offset].observe(&mut observable_substates[*offset]);
+    }
+
+    /// Steps the lattice with the constant control signal `control` until the
+    /// [`SimulationState::distance`] between consecutive states drops below `tol`, or until
+    /// `max_steps` steps have been taken. Returns the number of steps taken. Useful to find the
+    /// equilibrium of a damped lattice under a fixed control, see [`RustSimulator::with_damping`].
+    ///
+    /// The distance has to stay below `tol` for two steps in a row, since the positions barely
+    /// change over a single step at the turning points of an oscillation, or when starting at
+    /// rest before the accelerations were first computed.
+    ///
+    /// # Panics
+    /// If the control signal is non-zero, since the lattice doesn't respond to the control signal
+    /// yet and the equilibrium would silently ignore it.
+    pub fn relax(
+        &mut self,
+        control: &ControlSignalState<T, DIMS>,
+        dt: T,
+        tol: T,
+        max_steps: usize,
+    ) -> usize
+    where
+        T: Send + Sync,
+    {
+        assert!(
+            control.0.iter().all(|x| x.is_zero()),
+            "Expected a zero control signal, as the lattice doesn't respond to the control yet"
+        );
+
+        let len = self.simulation_states.len();
+        let mut was_still = false;
+        let steps = (1..=max_steps)
+            .find(|_| {
+                self.update_timed(dt);
+
+                let previous = &self.simulation_states[(self.offset + len - 1) % len];
+                let is_still = self.simulation_states[self.offset].distance(previous) < tol;
+                std::mem::replace(&mut was_still, is_still) && is_still
+            })
+            .unwrap_or(max_steps);
+        self.observe_current();
+
+        steps
+    }
+
+    /// Synchronously performs a single Verlet step of size `dt` like [`SimulatorInterface::update`],
+    /// reporting how long the force computation and the position and velocity updates took. Useful
+    /// for profiling where the time of a step goes, e.g. to tune the parallelism.
+    ///
+    /// The observable data of the state before the step is extracted concurrently with the step,
+    /// which only reads that state, so the observation reflects the state before the update. The
+    /// state after the step is left unobserved.
+    pub fn update_timed(&mut self, dt: T) -> StepTimings
+    where
+        T: Send + Sync,
+    {
+        let Self {
+            simulation_states,
+            offset,
+            forcing,
+            stiffness_ramp,
+            steps,
+            boundary_only,
+            damping,
+            warm_pool,
+            observable_substates,
+            ..
+        } = self;
+
+        let next_offset = (*offset + 1) % simulation_states.len();
+        let (current_state, next_state) = double_index_mut(simulation_states, *offset, next_offset);
+        let current_state = &*current_state;
+        let stiffness_scale = Self::stiffness_scale(*stiffness_ramp, *steps + 1);
+        let [scratch, ..] = warm_pool;
+        let (timings, ()) = rayon::join(
+            || {
+                Self::verlet_step(
+                    current_state,
+                    next_state,
+                    scratch,
+                    forcing.as_deref(),
+                    stiffness_scale,
+                    *boundary_only,
+                    *damping,
+                    dt,
+                )
+            },
+            || current_state.observe(&mut observable_substates[*offset]),
+        );
+        warm_pool.rotate_left(1);
+        *offset = next_offset;
+        *steps += 1;
+
+        timings
+    }
+
+    /// Performs a single [velocity
+    /// Verlet](https://en.wikipedia.org/wiki/Verlet_integration#Velocity_Verlet) step from
+    /// `state`, saving the result into `next_state` and returning the timings of the sub-steps. The
+    /// forces are computed into the `scratch` buffer, which is then swapped with the accelerations
+    /// of `next_state`. If `boundary_only` is set, the interior lattice points are left unchanged.
+    /// If `damping` is set, the velocities are decayed at that rate.
+    #[allow(clippy::too_many_arguments)]
+    fn verlet_step(
+        state: &SimulationState<T, DIMS>,
+        next_state: &mut SimulationState<T, DIMS>,
+        scratch: &mut Box<[Vector<T, DIMS>]>,
+        forcing: Option<&Forcing<T, DIMS>>,
+        stiffness_scale: T,
+        boundary_only: bool,
+        damping: Option<T>,
+        dt: T,
+    ) -> StepTimings
+    where
+        T: Send + Sync,
+    {
+        let start = Instant::now();
+        Self::par_update_position(state, next_state, boundary_only, dt);
+        Self::constrain_bonds(next_state, boundary_only);
+        Self::clamp_positions(next_state, boundary_only);
+        Self::update_time(state, next_state, dt);
+        let position = start.elapsed();
+
+        let start = Instant::now();
+        Self::par_compute_forces(next_state, forcing, stiffness_scale, scratch);
+        Self::swap_buffers(next_state, scratch);
+        let force = start.elapsed();
+
+        let start = Instant::now();
+        let decay = damping.map_or(T::one(), |damping| (-damping * dt).exp());
+        Self::par_update_velocity(state, next_state, boundary_only, decay, dt);
+        Self::bounce_off_walls(next_state, boundary_only);
+        let velocity = start.elapsed();
+
+        StepTimings {
+            force,
+            position,
+            velocity,
+        }
+    }
+
     /// Swaps the acceleration buffers between [`SimulationState`] and [`Box<\[Vector<T, DIMS>\]>`] by
     /// swapping pointers.
     fn swap_buffers(
@@ -165,87 +651,128 @@ impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
     }
 
     /// Compute the forces on a state in parallel using [`rayon`] and save the accelerations into
-    /// the [`Box<\[Vector<T, DIMS>\]>`] reference passed into `tmp_acceleration`.
+    /// the [`Box<\[Vector<T, DIMS>\]>`] reference passed into `tmp_acceleration`. The stiffnesses
+    /// of the state are scaled by `stiffness_scale`.
     fn par_compute_forces(
         state: &SimulationState<T, DIMS>,
+        forcing: Option<&Forcing<T, DIMS>>,
+        stiffness_scale: T,
         tmp_acceleration: &mut Box<[Vector<T, DIMS>]>,
     ) where
         T: Send + Sync,
     {
         let SimulationState {
+            time,
             origin_stiffness,
             size,
             stiffness,
             ref position,
             ..
         } = state;
+        let stiffness = *stiffness * stiffness_scale;
+        let origin_stiffness = *origin_stiffness * stiffness_scale;
 
         tmp_acceleration[..]
             .par_iter_mut()
             .enumerate()
             .for_each(|(i, acc)| {
-                *acc = -position[i] * *origin_stiffness;
+                *acc = -position[i] * origin_stiffness;
 
                 let idx = deindex::<DIMS>(i, *size);
+                if let Some(forcing) = forcing {
+                    *acc += forcing(idx.map(|x| T::from(x).unwrap()), *time);
+                }
                 for dim in 0..DIMS {
+                    let basis = Vector::<usize, DIMS>::basis(dim);
                     if idx[dim] > 0 {
-                        let j = index(idx - Vector::<usize, DIMS>::basis(dim), *size);
-                        *acc += (position[j] - position[i]) * *stiffness;
+                        let j = index(idx - basis, *size);
+                        *acc += (position[j] - position[i]) * stiffness;
+                    }
+                    if idx[dim] + 1 < *size {
+                        let j = index(idx + basis, *size);
+                        *acc += (position[j] - position[i]) * stiffness;
                     }
                 }
             });
     }
 
     /// Compute the forces on a state sequentially and save the accelerations into the
-    /// [`Box<\[Vector<T, DIMS>\]>`] reference passed into `tmp_acceleration`.
+    /// [`Box<\[Vector<T, DIMS>\]>`] reference passed into `tmp_acceleration`. The stiffnesses of
+    /// the state are scaled by `stiffness_scale`.
     fn compute_forces(
         state: &SimulationState<T, DIMS>,
+        forcing: Option<&Forcing<T, DIMS>>,
+        stiffness_scale: T,
         tmp_acceleration: &mut Box<[Vector<T, DIMS>]>,
     ) where
         T: Send + Sync,
     {
         let SimulationState {
+            time,
             origin_stiffness,
             size,
             stiffness,
             ref position,
             ..
         } = state;
+        let stiffness = *stiffness * stiffness_scale;
+        let origin_stiffness = *origin_stiffness * stiffness_scale;
 
         tmp_acceleration[..]
             .iter_mut()
             .enumerate()
             .for_each(|(i, acc)| {
-                *acc = -position[i] * *origin_stiffness;
+                *acc = -position[i] * origin_stiffness;
 
                 let idx = deindex::<DIMS>(i, *size);
+                if let Some(forcing) = forcing {
+                    *acc += forcing(idx.map(|x| T::from(x).unwrap()), *time);
+                }
                 for dim in 0..DIMS {
+                    let basis = Vector::<usize, DIMS>::basis(dim);
                     if idx[dim] > 0 {
-                        let j = index(idx - Vector::<usize, DIMS>::basis(dim), *size);
-                        *acc += (position[j] - position[i]) * *stiffness;
+                        let j = index(idx - basis, *size);
+                        *acc += (position[j] - position[i]) * stiffness;
+                    }
+                    if idx[dim] + 1 < *size {
+                        let j = index(idx + basis, *size);
+                        *acc += (position[j] - position[i]) * stiffness;
                     }
                 }
             });
     }
 
     /// Timesteps the positions with a simple first-order update `p(t + dt) = p(t) + dt * v(t) +
-    /// (dt^2 / 2) * a(t)` in parallel.
-    fn par_update_position(state: &mut SimulationState<T, DIMS>, dt: T)
-    where
+    /// (dt^2 / 2) * a(t)` in parallel, saving the result into `next_state`. If `boundary_only` is
+    /// set, the interior positions are copied over unchanged.
+    fn par_update_position(
+        state: &SimulationState<T, DIMS>,
+        next_state: &mut SimulationState<T, DIMS>,
+        boundary_only: bool,
+        dt: T,
+    ) where
         T: Send + Sync,
     {
         let SimulationState {
-            ref mut position,
+            size,
+            ref position,
             ref velocity,
             ref acceleration,
             ..
-        } = state;
+        } = *state;
 
-        position
+        next_state
+            .position
             .par_iter_mut()
+            .zip(position.par_iter())
             .zip(velocity.par_iter().zip(acceleration.par_iter()))
-            .for_each(|(p, (v, a))| {
-                *p += *v * dt + *a * dt * dt / (T::one() + T::one());
+            .enumerate()
+            .for_each(|(i, ((next_p, p), (v, a)))| {
+                *next_p = if boundary_only && !is_boundary::<DIMS>(i, size) {
+                    *p
+                } else {
+                    *p + *v * dt + *a * dt * dt / T::two()
+                };
             });
     }
 
@@ -258,12 +785,116 @@ impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
         let SimulationState {
             ref mut position,
             ref velocity,
+            ref acceleration,
             ..
         } = state;
 
-        position.iter_mut().zip(velocity.iter()).for_each(|(p, v)| {
-            *p += *v * dt;
-        });
+        position
+            .iter_mut()
+            .zip(velocity.iter().zip(acceleration.iter()))
+            .for_each(|(p, (v, a))| {
+                *p = *p + *v * dt + *a * dt * dt / T::two();
+            });
+    }
+
+    /// Projects neighboring lattice points apart so that no bond is shorter than the state's
+    /// `min_bond_length` or inverted, where the lattice points rest on a grid with unit spacing.
+    /// Neighboring bonds share lattice points, so the bonds are relaxed in sequence. If
+    /// `boundary_only` is set, the interior lattice points are held fixed and the boundary points
+    /// take up the whole correction.
+    fn constrain_bonds(state: &mut SimulationState<T, DIMS>, boundary_only: bool) {
+        let Some(min_bond_length) = state.min_bond_length else {
+            return;
+        };
+        let size = state.size;
+
+        for i in 0..state.position.len() {
+            let idx = deindex::<DIMS>(i, size);
+            for dim in 0..DIMS {
+                if idx[dim] > 0 {
+                    let j = index(idx - Vector::<usize, DIMS>::basis(dim), size);
+                    let (p_i, p_j) = double_index_mut(&mut state.position, i, j);
+
+                    let bond = Vector::<T, DIMS>::basis(dim) + *p_i - *p_j;
+                    let length = bond.map(|x| x * x).sum().sqrt();
+                    let correction = if bond[dim] <= T::zero() {
+                        // The points passed through each other within a single step, so push
+                        // them back along the bond's rest direction.
+                        Vector::<T, DIMS>::basis(dim) * (min_bond_length - bond[dim])
+                    } else if length < min_bond_length {
+                        bond * ((min_bond_length - length) / length)
+                    } else {
+                        continue;
+                    };
+
+                    let free = |k| !boundary_only || is_boundary::<DIMS>(k, size);
+                    match (free(i), free(j)) {
+                        (true, true) => {
+                            *p_i += correction / T::two();
+                            *p_j -= correction / T::two();
+                        }
+                        (true, false) => *p_i += correction,
+                        (false, true) => *p_j -= correction,
+                        (false, false) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Clamps the positions of the lattice points into the state's `position_bounds`, if any. If
+    /// `boundary_only` is set, the interior lattice points are left unchanged.
+    fn clamp_positions(state: &mut SimulationState<T, DIMS>, boundary_only: bool)
+    where
+        T: Send + Sync,
+    {
+        let Some((lower, upper)) = state.position_bounds else {
+            return;
+        };
+        let size = state.size;
+
+        state
+            .position
+            .par_iter_mut()
+            .enumerate()
+            .filter(|&(i, _)| !boundary_only || is_boundary::<DIMS>(i, size))
+            .for_each(|(_, p)| *p = p.clamp(lower, upper));
+    }
+
+    /// Absorbs or reflects the velocity components of the lattice points on a wall of the state's
+    /// `position_bounds` that point into the wall, depending on its `absorbing_bounds`. If
+    /// `boundary_only` is set, the interior lattice points are left unchanged.
+    fn bounce_off_walls(state: &mut SimulationState<T, DIMS>, boundary_only: bool)
+    where
+        T: Send + Sync,
+    {
+        let Some((lower, upper)) = state.position_bounds else {
+            return;
+        };
+        let SimulationState {
+            size,
+            absorbing_bounds,
+            ref position,
+            ref mut velocity,
+            ..
+        } = *state;
+
+        velocity
+            .par_iter_mut()
+            .zip(position.par_iter())
+            .enumerate()
+            .filter(|&(i, _)| !boundary_only || is_boundary::<DIMS>(i, size))
+            .for_each(|(_, (v, p))| {
+                *v = v.update(|dim, v_dim| {
+                    let into_lower = p[dim] <= lower[dim] && v_dim < T::zero();
+                    let into_upper = p[dim] >= upper[dim] && v_dim > T::zero();
+                    match (into_lower || into_upper, absorbing_bounds) {
+                        (false, _) => v_dim,
+                        (true, true) => T::zero(),
+                        (true, false) => -v_dim,
+                    }
+                });
+            });
     }
 
     /// Timesteps the time.
@@ -276,25 +907,41 @@ impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
     }
 
     /// Timesteps the positions with a first-order update `v(t + dt) = v(t) + (dt / 2) * (a(t) +
-    /// a(t + dt))` in parallel.
+    /// a(t + dt))` in parallel, where `a(t + dt)` is the acceleration already saved in
+    /// `next_state`, saving the result into `next_state` after scaling it by `decay`. If
+    /// `boundary_only` is set, the interior velocities are copied over unchanged.
     fn par_update_velocity(
-        state: &mut SimulationState<T, DIMS>,
+        state: &SimulationState<T, DIMS>,
+        next_state: &mut SimulationState<T, DIMS>,
+        boundary_only: bool,
+        decay: T,
         dt: T,
-        tmp_acceleration: &[Vector<T, DIMS>],
     ) where
         T: Send + Sync,
     {
         let SimulationState {
-            ref mut velocity,
+            size,
+            ref velocity,
             ref acceleration,
             ..
-        } = state;
+        } = *state;
+        let SimulationState {
+            velocity: ref mut next_velocity,
+            acceleration: ref next_acceleration,
+            ..
+        } = next_state;
 
-        velocity
+        next_velocity
             .par_iter_mut()
-            .zip(acceleration.par_iter().zip(tmp_acceleration.par_iter()))
-            .for_each(|(v, (a1, a2))| {
-                *v += (*a1 + *a2) * dt / T::two();
+            .zip(velocity.par_iter())
+            .zip(acceleration.par_iter().zip(next_acceleration.par_iter()))
+            .enumerate()
+            .for_each(|(i, ((next_v, v), (a1, a2)))| {
+                *next_v = if boundary_only && !is_boundary::<DIMS>(i, size) {
+                    *v
+                } else {
+                    (*v + (*a1 + *a2) * dt / T::two()) * decay
+                };
             });
     }
 
@@ -321,3 +968,674 @@ impl<T: Float, const DIMS: usize> RustSimulator<T, DIMS> {
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use common::{
+        interfaces::SimulatorInterface, recorder::TrajectoryRecorder, system::DynamicVector,
+        vector::Vector,
+    };
+    use futures::executor::block_on;
+
+    use super::RustSimulator;
+    use crate::system::{
+        index, ControlSignalState, CoupledHarmonicOscillator, ObservableSimulationState,
+        SimulationConfig, SimulationState, DELAY_DEPTH,
+    };
+
+    /// The times and values of the recorded control signals.
+    type Controls = Vec<(f64, Vec<f64>)>;
+
+    /// A recorder that logs the recorded control signals in memory.
+    struct ControlLog(Arc<Mutex<Controls>>);
+
+    impl TrajectoryRecorder<f64, CoupledHarmonicOscillator<f64, 2>> for ControlLog {
+        fn record_state(&mut self, _time: f64, _state: &SimulationState<f64, 2>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn record_control(
+            &mut self,
+            time: f64,
+            control: &ControlSignalState<f64, 2>,
+        ) -> io::Result<()> {
+            self.0.lock().unwrap().push((time, control.0.to_vec()));
+            Ok(())
+        }
+    }
+
+    /// A small lattice configuration for testing.
+    fn config() -> SimulationConfig<f64, 2> {
+        SimulationConfig {
+            size: 4,
+            stiffness: 1.,
+            origin_stiffness: 1.,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        }
+    }
+
+    #[test]
+    fn test_is_stable() {
+        let mut simulator = RustSimulator::new(config());
+        assert!(
+            block_on(simulator.is_stable()),
+            "Expected a resting lattice to be stable"
+        );
+
+        simulator.simulation_states[simulator.offset].position[5] = Vector::new([f64::NAN, 0.]);
+        assert!(
+            !block_on(simulator.is_stable()),
+            "Expected a NaN position to be unstable"
+        );
+
+        simulator.simulation_states[simulator.offset].position[5] = Vector::new([1e12, 0.]);
+        assert!(
+            !block_on(simulator.is_stable()),
+            "Expected an exploded lattice to be unstable"
+        );
+    }
+
+    #[test]
+    fn test_constant_forcing_shifts_equilibrium() {
+        let config = config();
+        let force = Vector::new([0.5, -0.25]);
+        let equilibrium = force / config.origin_stiffness;
+
+        let mut simulator = RustSimulator::new(config).with_forcing(move |_, _| force);
+        simulator.simulation_states[simulator.offset]
+            .position
+            .fill(equilibrium);
+        let control = ControlSignalState::default(config);
+
+        for _ in 0..10 {
+            block_on(simulator.update(&CoupledHarmonicOscillator::default(), 1e-2, &control));
+        }
+
+        let state = &simulator.simulation_states[simulator.offset];
+        for (p, v) in state.position.iter().zip(state.velocity.iter()) {
+            assert!(
+                (*p - equilibrium).map(f64::abs).sum() < 1e-12,
+                "Expected lattice to stay at the shifted equilibrium, got {p:?}"
+            );
+            assert!(
+                v.map(f64::abs).sum() < 1e-12,
+                "Expected lattice to stay at rest, got {v:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_min_bond_length() {
+        let min_bond_length = 0.5;
+        let config = SimulationConfig {
+            stiffness: 0.,
+            origin_stiffness: 0.,
+            min_bond_length: Some(min_bond_length),
+            ..config()
+        };
+        let (left, right) = (Vector::new([0, 0]), Vector::new([1, 0]));
+
+        let mut simulator = RustSimulator::new(config).with_forcing(move |x, _| {
+            if x == left.map(|x| x as f64) {
+                Vector::new([100., 0.])
+            } else if x == right.map(|x| x as f64) {
+                Vector::new([-100., 0.])
+            } else {
+                Vector::zero()
+            }
+        });
+        let control = ControlSignalState::default(config);
+
+        for _ in 0..100 {
+            block_on(simulator.update(&CoupledHarmonicOscillator::default(), 1e-2, &control));
+        }
+
+        let state = &simulator.simulation_states[simulator.offset];
+        let bond = Vector::<f64, 2>::basis(0) + state.position[index(right, config.size)]
+            - state.position[index(left, config.size)];
+        let length = bond.map(|x| x * x).sum().sqrt();
+        assert!(
+            (length - min_bond_length).abs() < 1e-9,
+            "Expected the points to stop at the minimum separation, got {length}"
+        );
+    }
+
+    #[test]
+    fn test_position_bounds() {
+        let config = SimulationConfig {
+            stiffness: 0.,
+            origin_stiffness: 0.,
+            position_bounds: Some((Vector::broadcast(-0.5), Vector::broadcast(0.5))),
+            absorbing_bounds: true,
+            ..config()
+        };
+        let site = Vector::new([1, 2]);
+        let system = CoupledHarmonicOscillator::default();
+        let control = ControlSignalState::default(config);
+
+        let mut simulator = RustSimulator::new(config).with_forcing(move |x, _| {
+            if x == site.map(|x| x as f64) {
+                Vector::new([100., 0.])
+            } else {
+                Vector::zero()
+            }
+        });
+        for _ in 0..100 {
+            block_on(simulator.update(&system, 1e-2, &control));
+        }
+
+        let state = &simulator.simulation_states[simulator.offset];
+        let i = index(site, config.size);
+        assert_eq!(
+            state.position[i],
+            Vector::new([0.5, 0.]),
+            "Expected the site to be clamped onto the wall"
+        );
+        assert_eq!(
+            state.velocity[i],
+            Vector::zero(),
+            "Expected the wall to absorb the normal velocity"
+        );
+
+        let mut simulator = RustSimulator::new(SimulationConfig {
+            absorbing_bounds: false,
+            ..config
+        });
+        simulator.simulation_states[simulator.offset].velocity[i] = Vector::new([0., -100.]);
+        block_on(simulator.update(&system, 1e-2, &control));
+
+        let state = &simulator.simulation_states[simulator.offset];
+        assert_eq!(state.position[i], Vector::new([0., -0.5]));
+        assert_eq!(
+            state.velocity[i],
+            Vector::new([0., 100.]),
+            "Expected the wall to reflect the normal velocity"
+        );
+    }
+
+    #[test]
+    fn test_step_n_matches_update() {
+        let config = config();
+        let system = CoupledHarmonicOscillator::default();
+        let control = ControlSignalState::default(config);
+        let displaced = |mut simulator: RustSimulator<f64, 2>| {
+            simulator.simulation_states[simulator.offset].position[5] = Vector::new([0.3, -0.1]);
+            simulator
+        };
+
+        let mut batched = displaced(RustSimulator::new(config));
+        let mut stepped = displaced(RustSimulator::new(config));
+        block_on(batched.step_n(&system, 1e-2, &control, 5));
+        for _ in 0..5 {
+            block_on(stepped.update(&system, 1e-2, &control));
+        }
+
+        assert_eq!(batched.offset, stepped.offset, "Expected the same offset");
+        let (batched, stepped) = (
+            &batched.simulation_states[batched.offset],
+            &stepped.simulation_states[stepped.offset],
+        );
+        assert_eq!(batched.time, stepped.time, "Expected the same time");
+        assert_eq!(
+            batched.position, stepped.position,
+            "Expected the same positions"
+        );
+        assert_eq!(
+            batched.velocity, stepped.velocity,
+            "Expected the same velocities"
+        );
+    }
+
+    #[test]
+    fn test_observation_noise() {
+        let observe = |simulator: RustSimulator<f64, 2>| block_on(simulator.get_observations());
+        let exact = observe(RustSimulator::new(config()));
+
+        let noiseless = observe(RustSimulator::new(config()).with_observation_noise(0., 3));
+        assert_eq!(
+            noiseless, exact,
+            "Expected zero noise to give exact observations"
+        );
+
+        let noisy = observe(RustSimulator::new(config()).with_observation_noise(0.1, 3));
+        assert_ne!(noisy, exact, "Expected noise to perturb the observations");
+        assert_eq!(
+            noisy,
+            observe(RustSimulator::new(config()).with_observation_noise(0.1, 3)),
+            "Expected the same seed to give the same perturbations"
+        );
+        for (noisy, exact) in noisy.iter().zip(&exact) {
+            assert_eq!(noisy.time, exact.time, "Expected exact observation times");
+            assert_eq!(noisy.controls, exact.controls, "Expected exact controls");
+        }
+    }
+
+    #[test]
+    fn test_observation_labels() {
+        let simulator = RustSimulator::<f64, 2>::new(config());
+        let observations = block_on(simulator.get_observations());
+        let labels = simulator.observation_labels();
+
+        assert_eq!(
+            labels.len(),
+            observations[0].get_rope().len(),
+            "Expected a label for every observation component"
+        );
+        assert_eq!(labels[..3], ["pos_0_x", "pos_0_y", "pos_1_x"]);
+        assert_eq!(labels.last().unwrap(), "ctrl_11");
+    }
+
+    #[test]
+    fn test_compute_forces_small_lattice() {
+        let config = SimulationConfig {
+            size: 3,
+            stiffness: 2.,
+            origin_stiffness: 0.5,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        };
+        let displacement = Vector::new([0.1, -0.3]);
+        let center = index(Vector::new([1, 1]), config.size);
+
+        let mut state = SimulationState::new(config);
+        state.position[center] = displacement;
+
+        let mut par_acceleration = state.acceleration.clone();
+        let mut acceleration = state.acceleration.clone();
+        RustSimulator::par_compute_forces(&state, None, 1., &mut par_acceleration);
+        RustSimulator::compute_forces(&state, None, 1., &mut acceleration);
+        assert_eq!(
+            par_acceleration, acceleration,
+            "Expected the parallel and sequential forces to agree"
+        );
+
+        // The center is pulled back by its origin spring and its 4 neighbors, which are each
+        // pulled towards it, while the corners feel no force.
+        for (i, &acc) in acceleration.iter().enumerate() {
+            let expected = match i {
+                4 => -displacement * (0.5 + 4. * 2.),
+                1 | 3 | 5 | 7 => displacement * 2.,
+                _ => Vector::zero(),
+            };
+            assert!(
+                (acc - expected).map(f64::abs).sum() < 1e-12,
+                "Expected acceleration {expected:?} at site {i}, got {acc:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_position_matches_parallel() {
+        let mut state = SimulationState::new(config());
+        state.position[5] = Vector::new([0.3, -0.1]);
+        state.velocity[2] = Vector::new([0.5, 0.25]);
+        let mut acceleration = state.acceleration.clone();
+        RustSimulator::par_compute_forces(&state, None, 1., &mut acceleration);
+        state.acceleration = acceleration;
+
+        let mut next_state = state.clone();
+        RustSimulator::par_update_position(&state, &mut next_state, false, 1e-2);
+        RustSimulator::update_position(&mut state, 1e-2);
+
+        assert_eq!(
+            state.position, next_state.position,
+            "Expected the sequential and parallel position updates to agree"
+        );
+    }
+
+    #[test]
+    fn test_interior_displacement_norm() {
+        let config = config();
+        let mut simulator = RustSimulator::new(config);
+        simulator.simulation_states[simulator.offset].position[index(Vector::new([0, 1]), 4)] =
+            Vector::new([1., 0.]);
+        let control = ControlSignalState::default(config);
+        assert_eq!(
+            simulator.interior_displacement_norm(),
+            0.,
+            "Expected a boundary displacement to leave the interior at rest"
+        );
+
+        let mut norm = 0.;
+        for _ in 0..4 {
+            block_on(simulator.step_n(&CoupledHarmonicOscillator::default(), 1e-2, &control, 5));
+
+            let next_norm = simulator.interior_displacement_norm();
+            assert!(
+                next_norm > norm,
+                "Expected the interior norm to grow, got {next_norm} after {norm}"
+            );
+            norm = next_norm;
+        }
+    }
+
+    #[test]
+    fn test_init_positions_from() {
+        let mut simulator = RustSimulator::new(config());
+        simulator.init_positions_from(|x| x.map(|x| x as f64 * 0.5));
+
+        let position = &simulator.simulation_states[simulator.offset].position;
+        for (x, expected) in [
+            ([0, 0], [0., 0.]),
+            ([1, 3], [0.5, 1.5]),
+            ([3, 2], [1.5, 1.]),
+        ] {
+            assert_eq!(
+                position[index(Vector::new(x), 4)],
+                Vector::new(expected),
+                "Expected the ramp at {x:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_timed() {
+        let config = SimulationConfig {
+            size: 64,
+            ..config()
+        };
+        let mut timed = RustSimulator::new(config);
+        let mut untimed = RustSimulator::new(config);
+        timed.init_positions_from(|x| x.map(|x| (x as f64 * 0.1).sin()));
+        untimed.init_positions_from(|x| x.map(|x| (x as f64 * 0.1).sin()));
+
+        let timings = timed.update_timed(1e-2);
+        let control = ControlSignalState::default(config);
+        block_on(untimed.update(&CoupledHarmonicOscillator::default(), 1e-2, &control));
+
+        assert!(
+            timings.force > Duration::ZERO
+                && timings.position > Duration::ZERO
+                && timings.velocity > Duration::ZERO,
+            "Expected all timings to be populated, got {timings:?}"
+        );
+        assert_eq!(
+            timings.total(),
+            timings.force + timings.position + timings.velocity
+        );
+        assert_eq!(
+            timed.simulation_states[timed.offset].position,
+            untimed.simulation_states[untimed.offset].position,
+            "Expected a timed step to match an untimed one"
+        );
+    }
+
+    #[test]
+    fn test_boundary_only() {
+        let config = config();
+        let interior = [[1, 1], [1, 2], [2, 1], [2, 2]].map(|x| index(Vector::new(x), 4));
+        let mut simulator = RustSimulator::new(config)
+            .with_boundary_only()
+            // The boundary is driven by the forcing, as the lattice ignores the control signal.
+            .with_forcing(|x, _| {
+                if x[0] == 0. {
+                    Vector::new([1., 0.])
+                } else {
+                    Vector::zero()
+                }
+            });
+        let state = &mut simulator.simulation_states[simulator.offset];
+        state.position[interior[0]] = Vector::new([0.2, 0.]);
+        let initial = state.position.clone();
+
+        let control = ControlSignalState::default(config);
+        block_on(simulator.step_n(&CoupledHarmonicOscillator::default(), 1e-2, &control, 50));
+
+        let position = &simulator.simulation_states[simulator.offset].position;
+        for i in interior {
+            assert_eq!(
+                position[i], initial[i],
+                "Expected interior point {i} to stay fixed"
+            );
+        }
+        assert!(
+            position[index(Vector::new([0, 1]), 4)][0] > 0.,
+            "Expected the forced boundary to respond, got {position:?}"
+        );
+    }
+
+    #[test]
+    fn test_warm_pool_no_allocation() {
+        let mut simulator = RustSimulator::new(config()).with_forcing(|x, t| x * t.sin());
+        let buffers = |simulator: &RustSimulator<f64, 2>| {
+            let mut buffers = simulator
+                .simulation_states
+                .iter()
+                .map(|state| state.acceleration.as_ptr())
+                .chain(simulator.warm_pool.iter().map(|buffer| buffer.as_ptr()))
+                .collect::<Vec<_>>();
+            buffers.sort();
+            buffers
+        };
+        let initial = buffers(&simulator);
+
+        for _ in 0..10 {
+            simulator.update_timed(1e-2);
+            assert_eq!(
+                buffers(&simulator),
+                initial,
+                "Expected the acceleration buffers to be reused rather than reallocated"
+            );
+        }
+    }
+
+    #[test]
+    fn test_records_controls() {
+        let config = config();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut simulator = RustSimulator::new(config).with_recorder(ControlLog(log.clone()));
+
+        let controls = (0..3)
+            .map(|i| ControlSignalState::new(vec![i as f64; 12]))
+            .collect::<Vec<_>>();
+        for control in &controls {
+            block_on(simulator.update(&CoupledHarmonicOscillator::default(), 0.5, control));
+        }
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [(0., vec![0.; 12]), (0.5, vec![1.; 12]), (1., vec![2.; 12])],
+            "Expected the control signal of every update, at the time it was applied"
+        );
+    }
+
+    #[test]
+    fn test_step_n_records_every_step() {
+        let config = config();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut simulator = RustSimulator::new(config).with_recorder(ControlLog(log.clone()));
+
+        let control = ControlSignalState::new(vec![1.; 12]);
+        block_on(simulator.step_n(&CoupledHarmonicOscillator::default(), 0.5, &control, 3));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [(0., vec![1.; 12]), (0.5, vec![1.; 12]), (1., vec![1.; 12])],
+            "Expected the control signal to be recorded at every step"
+        );
+    }
+
+    #[test]
+    fn test_relax() {
+        let config = config();
+        let mut simulator = RustSimulator::new(config).with_damping(0.5);
+        simulator.init_positions_from(|x| Vector::new([0.3, -0.2]) * (x[0] + x[1]) as f64);
+
+        let control = ControlSignalState::default(config);
+        let steps = simulator.relax(&control, 5e-2, 1e-6, 10_000);
+
+        assert!(steps < 10_000, "Expected the damped lattice to relax");
+        let rest = SimulationState::new(config);
+        let displacement = simulator.simulation_states[simulator.offset].distance(&rest);
+        assert!(
+            displacement < 1e-2,
+            "Expected near-zero displacement after {steps} steps, got {displacement}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a zero control signal")]
+    fn test_relax_rejects_control() {
+        let config = config();
+        let mut simulator = RustSimulator::new(config).with_damping(0.5);
+        let control = ControlSignalState::new(vec![1.; 12]);
+        simulator.relax(&control, 5e-2, 1e-6, 10);
+    }
+
+    #[test]
+    fn test_stiffness_ramp() {
+        let config = config();
+        let mut simulator = RustSimulator::new(config).with_stiffness_ramp(4);
+        simulator.simulation_states[simulator.offset].position[5] = Vector::new([0.3, -0.1]);
+        let control = ControlSignalState::default(config);
+
+        for step in 0..=6 {
+            let scale = (step as f64 / 4.).min(1.);
+            assert_eq!(
+                simulator.effective_stiffness(),
+                (config.stiffness * scale, config.origin_stiffness * scale),
+                "Expected a linear stiffness ramp at step {step}"
+            );
+            block_on(simulator.update(&CoupledHarmonicOscillator::default(), 1e-2, &control));
+
+            let scale = ((step + 1) as f64 / 4.).min(1.);
+            let state = &simulator.simulation_states[simulator.offset];
+            let mut acceleration = state.acceleration.clone();
+            RustSimulator::par_compute_forces(state, None, scale, &mut acceleration);
+            assert_eq!(
+                state.acceleration, acceleration,
+                "Expected the forces to use the ramped stiffness after step {step}"
+            );
+        }
+    }
+
+    /// Fails to compile unless `T` is [`Send`].
+    fn assert_send<T: Send>(_: &T) {}
+
+    /// Fails to compile unless `T` is [`Sync`].
+    fn assert_sync<T: Sync>(_: &T) {}
+
+    #[test]
+    fn test_futures_are_send() {
+        let system = CoupledHarmonicOscillator::default();
+        let control = ControlSignalState::default(config());
+        let mut simulator = RustSimulator::new(config());
+        assert_send(&simulator);
+        assert_sync(&simulator);
+
+        assert_send(&simulator.get_observations());
+        assert_send(&simulator.update(&system, 0.1, &control));
+        assert_send(&simulator.step_n(&system, 0.1, &control, 2));
+        assert_send(&simulator.get_dynamics_loss());
+        assert_send(&simulator.is_stable());
+    }
+
+    #[test]
+    fn test_overlapped_observation_matches_sequential() {
+        let config = config();
+        let mut simulator = RustSimulator::new(config).with_observation_stride(2);
+        simulator.init_positions_from(|x| x.map(|x| x as f64 * 0.1));
+        let control = ControlSignalState::default(config);
+
+        for n in [1, 3, 2] {
+            block_on(simulator.step_n(&CoupledHarmonicOscillator::default(), 0.05, &control, n));
+        }
+
+        for (state, observed) in simulator
+            .simulation_states
+            .iter()
+            .zip(simulator.observable_substates.iter())
+        {
+            let mut expected = ObservableSimulationState::new(config);
+            state.observe(&mut expected);
+            assert_eq!(
+                (observed.time, &observed.position, &observed.velocity),
+                (expected.time, &expected.position, &expected.velocity),
+                "Expected the overlapped observation of the state at time {}",
+                state.time
+            );
+        }
+    }
+
+    #[test]
+    fn test_observation_stride() {
+        let config = config();
+        let control = ControlSignalState::default(config);
+        for (stride, expected) in [(1, [5., 4.5, 4.]), (2, [5., 4., 3.])] {
+            let mut simulator = RustSimulator::new(config).with_observation_stride(stride);
+            assert_eq!(
+                simulator.simulation_states.len(),
+                stride * DELAY_DEPTH + 1,
+                "Expected the history buffer to hold `stride * DELAY_DEPTH + 1` states"
+            );
+            block_on(simulator.step_n(&CoupledHarmonicOscillator::default(), 0.5, &control, 10));
+
+            let times = block_on(simulator.get_observations())
+                .iter()
+                .map(|observation| observation.time)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                times, expected,
+                "Expected observations {stride} steps apart"
+            );
+        }
+    }
+
+    #[test]
+    fn test_latest_observation() {
+        let config = config();
+        let control = ControlSignalState::default(config);
+        let mut simulator = RustSimulator::new(config);
+        simulator.simulation_states[simulator.offset].position[1] = Vector::new([0.2, -0.1]);
+
+        for _ in 0..2 * DELAY_DEPTH {
+            block_on(simulator.update(&CoupledHarmonicOscillator::default(), 0.1, &control));
+            let observations = block_on(simulator.get_observations());
+            assert_eq!(
+                block_on(simulator.latest_observation()),
+                observations[0],
+                "Expected the most recent observation, which comes first in the window"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a Python environment with JAX installed"]
+    fn test_state_to_jax() {
+        pyo3::prepare_freethreaded_python();
+        let mut simulator = RustSimulator::new(config());
+        let state = &mut simulator.simulation_states[simulator.offset];
+        state.position[index(Vector::new([1, 2]), 4)] = Vector::new([0.5, -0.25]);
+        state.velocity[index(Vector::new([3, 0]), 4)] = Vector::new([2., 4.]);
+        state.acceleration[index(Vector::new([0, 3]), 4)] = Vector::new([-1., 8.]);
+
+        let array = simulator.state_to_jax();
+        assert_eq!(
+            array.shape(),
+            [4, 4, 2, 3],
+            "Expected shape `[size, size, DIMS, 3]`"
+        );
+
+        // Indexes the flattened array at point `(x, y)`, component `dim` of field `field`.
+        let data = array.to_vec::<f64>();
+        let at = |x: usize, y: usize, dim: usize, field: usize| {
+            data[((x * 4 + y) * 2 + dim) * 3 + field]
+        };
+        assert_eq!(at(1, 2, 0, 0), 0.5, "Expected the x position of (1, 2)");
+        assert_eq!(at(1, 2, 1, 0), -0.25, "Expected the y position of (1, 2)");
+        assert_eq!(at(3, 0, 1, 1), 4., "Expected the y velocity of (3, 0)");
+        assert_eq!(at(0, 3, 0, 2), -1., "Expected the x acceleration of (0, 3)");
+        assert_eq!(at(0, 0, 0, 0), 0., "Expected untouched points at rest");
+    }
+}
@@ -1,6 +1,8 @@
 use std::marker::PhantomData;
 
-use crate::system::{CoupledHarmonicOscillator, Observation, StateTensor};
+use crate::system::{
+    CoupledHarmonicOscillator, Observation, ShapeSignatures, SimulationConfig, StateTensor,
+};
 use common::{interfaces::StatePredictionInterface, python::set_venv_site_packages, Float};
 use pyo3::{
     types::{IntoPyDict, PyAnyMethods, PyDict, PyModule},
@@ -9,18 +11,23 @@ use pyo3::{
 
 /// The driver that uses Python with JAX under the hood. This driver is designed for the
 /// [`CoupledHarmonicOscillator`].
-pub struct PythonStatePredictor<T, const DIMS: usize> {
+pub struct PythonStatePredictor<T: Float, const DIMS: usize> {
     /// The set of globally accessible variables.
     globals: Py<PyDict>,
     /// The set of locally accessible variables.
     locals: Py<PyDict>,
+    /// The shape signatures of the configurations the predictor has been used with.
+    signatures: ShapeSignatures,
+    /// The configuration of the simulation that the predictor is used with.
+    config: SimulationConfig<T, DIMS>,
     /// Phantom to make `T` and `DIMS` relevant.
     _phantom: PhantomData<[T; DIMS]>,
 }
 
-impl<T, const DIMS: usize> PythonStatePredictor<T, DIMS> {
-    /// Produces an instance of the [`PythonStatePredictor`].
-    pub fn new() -> Self {
+impl<T: Float, const DIMS: usize> PythonStatePredictor<T, DIMS> {
+    /// Produces an instance of the [`PythonStatePredictor`] for the simulation with the given
+    /// configuration.
+    pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
         pyo3::prepare_freethreaded_python();
 
         let [jax, np, state_estimator] = Python::with_gil(|py| {
@@ -54,6 +61,8 @@ impl<T, const DIMS: usize> PythonStatePredictor<T, DIMS> {
         Self {
             globals,
             locals,
+            signatures: ShapeSignatures::default(),
+            config,
             _phantom: PhantomData,
         }
     }
@@ -87,12 +96,9 @@ impl<T: Float, const DIMS: usize> StatePredictionInterface<T, CoupledHarmonicOsc
         &mut self,
         observation: &[Observation<T, DIMS>],
     ) -> StateTensor<T, DIMS> {
+        // Logs once per new shape signature, since it triggers a JAX recompilation.
+        self.signatures
+            .observe("PythonStatePredictor", &self.config);
         todo!()
     }
 }
-
-impl<T, const DIMS: usize> Default for PythonStatePredictor<T, DIMS> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
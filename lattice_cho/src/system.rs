@@ -1,6 +1,13 @@
-use std::marker::PhantomData;
+use std::{
+    collections::HashSet,
+    hash::Hasher,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
 
-use common::{rope::{Rope, RopeMut}, system::{DynamicVector, System}, vector::Vector, Float};
+use common::{rope::{Rope, RopeMut}, system::{DynamicVector, ObservationExt, System, SystemSizes}, vector::Vector, wire::{decode, encode, WireError, WireFormat}, Float};
+
+pub use common::lattice::{deindex, index};
 
 /// The number of dimensions of the latent embedding of the system state.
 pub const LATENT_SPACE_SHAPE: usize = 1024;
@@ -20,6 +27,8 @@ pub struct StateTensor<T, const DIMS: usize> {
 }
 
 impl<T: Float, const DIMS: usize> DynamicVector<T> for StateTensor<T, DIMS> {
+    const LEN: Option<usize> = Some(LATENT_SPACE_SHAPE);
+
     fn copy_from_slice(&mut self, v: &[T]) {
         self.state.as_array_mut().copy_from_slice(v);
     }
@@ -33,6 +42,17 @@ impl<T: Float, const DIMS: usize> DynamicVector<T> for StateTensor<T, DIMS> {
     }
 }
 
+/// The latent space has a fixed shape, so unlike the other messages the default [`StateTensor`]
+/// doesn't depend on the [`SimulationConfig`]: it is the zero embedding at time zero.
+impl<T: Float, const DIMS: usize> Default for StateTensor<T, DIMS> {
+    fn default() -> Self {
+        Self {
+            time: T::zero(),
+            state: Vector::zero(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[repr(C)]
 /// The control parameters output by our driver to be fed into our generator.
@@ -52,6 +72,16 @@ impl<T: Float, const DIMS: usize> DynamicVector<T> for ControlParameterState<T,
     }
 }
 
+impl<T: Float, const DIMS: usize> WireFormat for ControlParameterState<T, DIMS> {
+    fn to_bytes(&self) -> Vec<u8> {
+        encode(&self.0)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        decode(bytes).map(Self::new)
+    }
+}
+
 impl<T: Float, const DIMS: usize> ControlParameterState<T, DIMS> {
     /// Given data, wraps the data as a [`ControlParameterState`]
     pub fn new(data: Vec<T>) -> Self {
@@ -67,13 +97,14 @@ impl<T: Float, const DIMS: usize> ControlParameterState<T, DIMS> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[repr(C)]
-/// The control signals output by our driver to be fed into our generator.
-pub struct ControlSignalState<T, const DIMS: usize>(pub Vec<T>, PhantomData<[T; DIMS]>);
+/// The control signals output by our driver to be fed into our generator. The data is shared
+/// between clones, so passing the same signal around is cheap, and copied on the first write.
+pub struct ControlSignalState<T, const DIMS: usize>(pub Arc<[T]>, PhantomData<[T; DIMS]>);
 
 impl<T: Float, const DIMS: usize> ControlSignalState<T, DIMS> {
     /// Given data, wraps the data as a [`ControlSignalState`]
     pub fn new(data: Vec<T>) -> Self {
-        Self(data, PhantomData)
+        Self(data.into(), PhantomData)
     }
 
     /// Given a [`SimulationConfig<T, DIMS>`], produces a [`ControlSignalState<T, DIMS>`] that
@@ -81,11 +112,44 @@ impl<T: Float, const DIMS: usize> ControlSignalState<T, DIMS> {
     pub fn default(config: SimulationConfig<T, DIMS>) -> Self {
         Self::new(vec![T::zero(); config.size * 4 - 4])
     }
+
+    /// Linearly interpolates element-wise between this signal (at `t = 0`) and `target` (at
+    /// `t = 1`), so that a generator can ramp smoothly between signals over several steps.
+    ///
+    /// # Panics
+    /// If the signals don't have the same length.
+    pub fn interpolate_to(&self, target: &Self, t: T) -> Self {
+        assert_eq!(
+            self.0.len(),
+            target.0.len(),
+            "Cannot interpolate between control signals of lengths {} and {}",
+            self.0.len(),
+            target.0.len()
+        );
+
+        Self::new(
+            self.0
+                .iter()
+                .zip(target.0.iter())
+                .map(|(&a, &b)| a + (b - a) * t)
+                .collect(),
+        )
+    }
+}
+
+impl<T: Float, const DIMS: usize> WireFormat for ControlSignalState<T, DIMS> {
+    fn to_bytes(&self) -> Vec<u8> {
+        encode(&self.0)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        decode(bytes).map(Self::new)
+    }
 }
 
 impl<T: Float, const DIMS: usize> DynamicVector<T> for ControlSignalState<T, DIMS> {
     fn copy_from_slice(&mut self, v: &[T]) {
-        self.0.copy_from_slice(v);
+        Arc::make_mut(&mut self.0).copy_from_slice(v);
     }
 
     fn get_rope(&self) -> Rope<T> {
@@ -93,7 +157,7 @@ impl<T: Float, const DIMS: usize> DynamicVector<T> for ControlSignalState<T, DIM
     }
 
     fn get_rope_mut(&mut self) -> RopeMut<T> {
-        RopeMut::new([&mut self.0])
+        RopeMut::new([Arc::make_mut(&mut self.0)])
     }
 }
 
@@ -144,12 +208,17 @@ pub struct Observation<T, const DIMS: usize> {
     pub controls: ControlSignalState<T, DIMS>,
 }
 
+impl<T: Float, const DIMS: usize> ObservationExt<T> for Observation<T, DIMS> {
+    fn time(&self) -> T {
+        self.time
+    }
+}
+
 impl<T: Float, const DIMS: usize> DynamicVector<T> for Observation<T, DIMS> {
     fn copy_from_slice(&mut self, v: &[T]) {
-        let state_range = ..self.state.get_rope().len();
-        let controls_range = self.state.get_rope().len()..;
-        self.state.copy_from_slice(&v[state_range]);
-        self.controls.copy_from_slice(&v[controls_range]);
+        let (state, controls) = v.split_at(self.state.get_rope().len());
+        self.state.copy_from_slice(state);
+        self.controls.copy_from_slice(controls);
     }
 
     fn get_rope(&self) -> Rope<T> {
@@ -163,6 +232,74 @@ impl<T: Float, const DIMS: usize> DynamicVector<T> for Observation<T, DIMS> {
     }
 }
 
+impl<T: Float, const DIMS: usize> Observation<T, DIMS> {
+    /// Assembles an [`Observation`] by copying the given positions and velocities of the
+    /// observable lattice points. The control signal is shared rather than copied.
+    pub fn from_parts(
+        time: T,
+        positions: &[Vector<T, DIMS>],
+        velocities: &[Vector<T, DIMS>],
+        controls: &ControlSignalState<T, DIMS>,
+    ) -> Self {
+        Self {
+            time,
+            state: ObservableState {
+                position: positions.to_vec(),
+                velocity: velocities.to_vec(),
+            },
+            controls: controls.clone(),
+        }
+    }
+
+    /// Borrows the observation as an [`ObservationRef`].
+    pub fn as_ref(&self) -> ObservationRef<'_, T, DIMS> {
+        ObservationRef {
+            time: self.time,
+            position: &self.state.position,
+            velocity: &self.state.velocity,
+            controls: &self.controls,
+        }
+    }
+}
+
+/// A borrowed view of an [`Observation`], e.g. of the observable state held by the simulator,
+/// that can be read without copying the positions and velocities. Use
+/// [`ObservationRef::to_owned`] to get an owned [`Observation`] when needed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObservationRef<'a, T, const DIMS: usize> {
+    /// The time of the observations.
+    pub time: T,
+    /// The observable position displacements.
+    pub position: &'a [Vector<T, DIMS>],
+    /// The observable velocities.
+    pub velocity: &'a [Vector<T, DIMS>],
+    /// The control signal applied at the specified time.
+    pub controls: &'a ControlSignalState<T, DIMS>,
+}
+
+impl<T: Float, const DIMS: usize> ObservationRef<'_, T, DIMS> {
+    /// Copies the borrowed observation into an owned [`Observation`].
+    pub fn to_owned(&self) -> Observation<T, DIMS> {
+        Observation::from_parts(self.time, self.position, self.velocity, self.controls)
+    }
+
+    /// Gets the observation as a non-contiguous rope, laid out like
+    /// [`DynamicVector::get_rope`] of [`Observation`].
+    pub fn get_rope(&self) -> Rope<'_, T> {
+        Rope::new(&[
+            bytemuck::cast_slice(self.position),
+            bytemuck::cast_slice(self.velocity),
+        ])
+        .merge(self.controls.get_rope())
+    }
+}
+
+impl<T: Float, const DIMS: usize> ObservationExt<T> for ObservationRef<'_, T, DIMS> {
+    fn time(&self) -> T {
+        self.time
+    }
+}
+
 /// The full state of the CoupleHarmonicOscillator system.
 #[derive(Debug, Clone)]
 pub struct SimulationState<T: Float, const DIMS: usize> {
@@ -174,6 +311,14 @@ pub struct SimulationState<T: Float, const DIMS: usize> {
     pub stiffness: T,
     /// The strength of the coupling between the lattice point and its equilibrium position.
     pub origin_stiffness: T,
+    /// The minimum distance between neighboring lattice points, if any.
+    pub min_bond_length: Option<T>,
+    /// The stride over the boundary sites that are observed.
+    pub boundary_stride: usize,
+    /// The lower and upper corners of the box the positions are confined to, if any.
+    pub position_bounds: Option<(Vector<T, DIMS>, Vector<T, DIMS>)>,
+    /// Whether the walls of the box absorb rather than reflect the velocities.
+    pub absorbing_bounds: bool,
     /// The positions of the lattice points.
     pub position: Box<[Vector<T, DIMS>]>,
     /// The velocities of the lattice points.
@@ -189,6 +334,10 @@ impl<T: Float, const DIMS: usize> Default for SimulationState<T, DIMS> {
             size: 0,
             stiffness: T::zero(),
             origin_stiffness: T::zero(),
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
             position: Box::new([]),
             velocity: Box::new([]),
             acceleration: Box::new([]),
@@ -230,7 +379,9 @@ impl<T: Float, const DIMS: usize> DynamicVector<T> for SimulationState<T, DIMS>
     }
 }
 
-/// The observable subset of the simulation state. For this system, it is the boundary.
+/// The observable subset of the simulation state. For this system, it is the boundary, stored in
+/// the traversal order of [`boundary_index_to_grid`].
+#[derive(Debug, Clone)]
 pub struct ObservableSimulationState<T: Float, const DIMS: usize> {
     /// The current time in the system.
     pub time: T,
@@ -255,11 +406,12 @@ pub struct ExperimentConfig<T: Float> {
 pub struct CoupledHarmonicOscillator<T, const DIMS: usize>(PhantomData<[T; DIMS]>);
 
 impl<T: Float, const DIMS: usize> System<T> for CoupledHarmonicOscillator<T, DIMS> {
-    const CONTROL_SIGNAL_SIZE: usize = 1; // TODO: Set CONTROL_SIGNAL_SIZE
-    const CONTROL_PARAMS_SIZE: usize = 1; // TODO: Set CONTROL_PARAMS_SIZE
-    const LATENT_STATE_SIZE: usize = 1; // TODO: Set LATENT_STATE_SIZE
-    const SYSTEM_STATE_SIZE: usize = 1; // TODO: Set SYSTEM_STATE_SIZE
-    const OBSERVABLE_STATE_SIZE: usize = 1; // TODO: Set OBSERVABLE_STATE_SIZE
+    // Apart from the latent state, the sizes depend on the lattice size, see `sizes`.
+    const CONTROL_SIGNAL_SIZE: usize = 0;
+    const CONTROL_PARAMS_SIZE: usize = 0;
+    const LATENT_STATE_SIZE: usize = LATENT_SPACE_SHAPE;
+    const SYSTEM_STATE_SIZE: usize = 0;
+    const OBSERVABLE_STATE_SIZE: usize = 0;
 
     type SystemConfiguration = SimulationConfig<T, DIMS>;
     type DynamicsConfiguration = ExperimentConfig<T>;
@@ -273,6 +425,20 @@ impl<T: Float, const DIMS: usize> System<T> for CoupledHarmonicOscillator<T, DIM
     type ControlSignal = ControlSignalState<T, DIMS>;
 
     type SystemObservation = Observation<T, DIMS>;
+
+    fn sizes(config: &SimulationConfig<T, DIMS>) -> SystemSizes {
+        let boundary_size = compute_boundary_size(config.size);
+        let observed_size = compute_observed_boundary_size(config.size, config.boundary_stride);
+        SystemSizes {
+            control_signal: boundary_size,
+            control_params: boundary_size,
+            latent_state: LATENT_SPACE_SHAPE,
+            // The positions, velocities and accelerations of every lattice point.
+            system_state: 3 * config.size.pow(DIMS as u32) * DIMS,
+            // The positions and velocities of the observed boundary, and the boundary controls.
+            observable_state: 2 * observed_size * DIMS + boundary_size,
+        }
+    }
 }
 
 /// Compute the size of the boundary of the lattice in 2 dimensions.
@@ -280,19 +446,25 @@ fn compute_boundary_size(size: usize) -> usize {
     size * 4 - 4
 }
 
+/// Compute the number of observed sites of the boundary of the lattice in 2 dimensions, when
+/// observing every `stride`-th site.
+fn compute_observed_boundary_size(size: usize, stride: usize) -> usize {
+    compute_boundary_size(size).div_ceil(stride)
+}
+
 impl<T: Float, const DIMS: usize> ObservableSimulationState<T, DIMS> {
-    /// Construct a default [`ObservableSimulationState`] from the given configuration.
+    /// Construct a default [`ObservableSimulationState`] from the given configuration. Only the
+    /// boundaries of 2-dimensional lattices are observable, so using this with any other `DIMS`
+    /// fails to compile.
     pub fn new(config: SimulationConfig<T, DIMS>) -> Self {
-        if DIMS != 2 {
-            unimplemented!("Haven't implemented this yet!");
-        }
+        const { assert!(DIMS == 2, "Only 2-dimensional lattices are observable") };
 
-        let boundary_size = compute_boundary_size(config.size);
+        let observed_size = compute_observed_boundary_size(config.size, config.boundary_stride);
         Self {
             time: T::zero(),
             size: config.size,
-            position: vec![Vector::<T, DIMS>::zero(); boundary_size].into_boxed_slice(),
-            velocity: vec![Vector::<T, DIMS>::zero(); boundary_size].into_boxed_slice(),
+            position: vec![Vector::<T, DIMS>::zero(); observed_size].into_boxed_slice(),
+            velocity: vec![Vector::<T, DIMS>::zero(); observed_size].into_boxed_slice(),
         }
     }
 }
@@ -304,11 +476,19 @@ impl<T: Float, const DIMS: usize> SimulationState<T, DIMS> {
             size,
             stiffness,
             origin_stiffness,
+            min_bond_length,
+            boundary_stride,
+            position_bounds,
+            absorbing_bounds,
         } = config;
         Self {
             size,
             stiffness,
             origin_stiffness,
+            min_bond_length,
+            boundary_stride,
+            position_bounds,
+            absorbing_bounds,
             time: T::zero(),
             position: vec![Vector::<T, DIMS>::zero(); size.pow(DIMS as u32)].into_boxed_slice(),
             velocity: vec![Vector::<T, DIMS>::zero(); size.pow(DIMS as u32)].into_boxed_slice(),
@@ -316,25 +496,173 @@ impl<T: Float, const DIMS: usize> SimulationState<T, DIMS> {
         }
     }
 
+    /// Computes the root-mean-square distance between the positions of the lattice points of two
+    /// states. This is zero for identical states, and is useful to measure the per-step change
+    /// of the state when checking for convergence.
+    ///
+    /// # Panics
+    /// If the states don't have the same number of lattice points.
+    pub fn distance(&self, other: &Self) -> T {
+        rms_distance(&self.position, &other.position)
+    }
+
+    /// Computes the root-mean-square distance between two states in phase space, i.e. over both
+    /// the positions and the velocities of the lattice points.
+    ///
+    /// # Panics
+    /// If the states don't have the same number of lattice points.
+    pub fn phase_space_distance(&self, other: &Self) -> T {
+        let position = rms_distance(&self.position, &other.position);
+        let velocity = rms_distance(&self.velocity, &other.velocity);
+
+        (position * position + velocity * velocity).sqrt()
+    }
+
+    /// Checks whether the positions, velocities and accelerations of the lattice points of two
+    /// states agree component-wise to within `epsilon`, e.g. to compare integrated states in
+    /// tests. The times are ignored, so that tiny drifts in the accumulated time don't matter.
+    /// States of lattices of different sizes are never equal.
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool {
+        let close = |a: &[Vector<T, DIMS>], b: &[Vector<T, DIMS>]| {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| (*a - *b).into_iter().all(|x| x.abs() <= epsilon))
+        };
+
+        self.size == other.size
+            && close(&self.position, &other.position)
+            && close(&self.velocity, &other.velocity)
+            && close(&self.acceleration, &other.acceleration)
+    }
+
+    /// Loads the state from a flattened slice like [`DynamicVector::copy_from_slice`], but copies
+    /// the position, velocity and acceleration buffers concurrently using [`rayon`]. The buffers
+    /// are independent, so for large lattices the copies can overlap.
+    ///
+    /// # Panics
+    /// If the slice doesn't have the same length as the state.
+    pub fn par_copy_from_slice(&mut self, v: &[T]) {
+        let s = bytemuck::cast_slice::<_, Vector<T, DIMS>>(v);
+        let (pos, rest) = s.split_at(self.position.len());
+        let (vel, acc) = rest.split_at(self.velocity.len());
+
+        let position = &mut self.position;
+        let velocity = &mut self.velocity;
+        let acceleration = &mut self.acceleration;
+        rayon::join(
+            || position.copy_from_slice(pos),
+            || {
+                rayon::join(
+                    || velocity.copy_from_slice(vel),
+                    || acceleration.copy_from_slice(acc),
+                )
+            },
+        );
+    }
+
     /// For a given [`SimulationState`], fill the [`ObservableSimulationState`] with the observable
-    /// data of the state.
-    pub fn observe(&self, _observable: &mut ObservableSimulationState<T, DIMS>) {
-        todo!("observe")
+    /// data of the state, i.e. every [`SimulationState::boundary_stride`]-th boundary point in the
+    /// order of [`boundary_index_to_grid`]. Like [`ObservableSimulationState::new`], this only
+    /// compiles for 2-dimensional lattices, so the check costs nothing per step.
+    ///
+    /// # Panics
+    /// If the observable state doesn't have the observed boundary size of this state's lattice.
+    pub fn observe(&self, observable: &mut ObservableSimulationState<T, DIMS>) {
+        const { assert!(DIMS == 2, "Only 2-dimensional lattices are observable") };
+        let observed_size = compute_observed_boundary_size(self.size, self.boundary_stride);
+        assert_eq!(
+            observable.position.len(),
+            observed_size,
+            "Expected an observable state with {observed_size} boundary points but got {}",
+            observable.position.len()
+        );
+
+        observable.time = self.time;
+        observable.size = self.size;
+        let sites = (0..compute_boundary_size(self.size)).step_by(self.boundary_stride);
+        for (k, i) in sites.enumerate() {
+            let j = index(boundary_index_to_grid(i, self.size), self.size);
+            observable.position[k] = self.position[j];
+            observable.velocity[k] = self.velocity[j];
+        }
     }
 }
 
-/// Given a scalar index into a `DIMS`-dimensional flattened regular array of size `size^DIMS`,
-/// compute the vector index.
-pub fn deindex<const DIMS: usize>(index: usize, size: usize) -> Vector<usize, DIMS> {
-    let modulus = Vector::from_idx(|i| size.pow((DIMS - i - 1) as u32));
-    (Vector::broadcast(index) / modulus) % size
+/// Computes the root-mean-square of the Euclidean distances between corresponding vectors of two
+/// slices.
+///
+/// # Panics
+/// If the slices don't have the same length.
+fn rms_distance<T: Float, const DIMS: usize>(a: &[Vector<T, DIMS>], b: &[Vector<T, DIMS>]) -> T {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "Expected states of the same size but got {} and {} lattice points",
+        a.len(),
+        b.len()
+    );
+    if a.is_empty() {
+        return T::zero();
+    }
+
+    let sum_squares = a
+        .iter()
+        .zip(b.iter())
+        .map(|(&a, &b)| (a - b).map(|x| x * x).sum())
+        .fold(T::zero(), |acc, x| acc + x);
+
+    (sum_squares / T::from(a.len()).unwrap()).sqrt()
+}
+
+/// Given an index into the boundary of a 2-dimensional lattice of side-length `size`, compute the
+/// grid coordinate of the boundary point.
+///
+/// The boundary is traversed clockwise starting from the origin, with the first coordinate
+/// pointing down and the second coordinate pointing right: first along the top edge
+/// `(0, 0)..(0, size - 1)`, then down the right edge `(0, size - 1)..(size - 1, size - 1)`, then
+/// back along the bottom edge `(size - 1, size - 1)..(size - 1, 0)` and finally up the left edge
+/// `(size - 1, 0)..(0, 0)`, where each range excludes its end.
+///
+/// # Panics
+/// If `i` is not less than the boundary size `4 * size - 4`.
+pub fn boundary_index_to_grid(i: usize, size: usize) -> Vector<usize, 2> {
+    assert!(
+        i < compute_boundary_size(size),
+        "Expected a boundary index less than {} but got {i}",
+        compute_boundary_size(size)
+    );
+    let edge = size - 1;
+    let (side, k) = (i / edge, i % edge);
+
+    match side {
+        0 => Vector::new([0, k]),
+        1 => Vector::new([k, edge]),
+        2 => Vector::new([edge, edge - k]),
+        _ => Vector::new([edge - k, 0]),
+    }
 }
 
-/// Given a vector index into a `DIMS`-dimensional regular array of where each dimension has size `size`,
-/// compute the scalar index.
-pub fn index<const DIMS: usize>(index: Vector<usize, DIMS>, size: usize) -> usize {
-    let modulus = Vector::from_idx(|i| size.pow((DIMS - i - 1) as u32));
-    (index * modulus).sum()
+/// The inverse of [`boundary_index_to_grid`]: given the grid coordinate of a point of a
+/// 2-dimensional lattice of side-length `size`, compute its index into the boundary, or [`None`]
+/// if the point is not on the boundary.
+pub fn grid_to_boundary_index(x: Vector<usize, 2>, size: usize) -> Option<usize> {
+    let edge = size.checked_sub(1)?;
+    let [row, col] = [x[0], x[1]];
+
+    if row > edge || col > edge || edge == 0 {
+        None
+    } else if row == 0 && col < edge {
+        Some(col)
+    } else if col == edge && row < edge {
+        Some(edge + row)
+    } else if row == edge && col > 0 {
+        Some(2 * edge + (edge - col))
+    } else if col == 0 && row > 0 {
+        Some(3 * edge + (edge - row))
+    } else {
+        None
+    }
 }
 
 /// The configuration for the [`CoupledHarmonicOscillator`] system.
@@ -346,4 +674,398 @@ pub struct SimulationConfig<T: Float, const DIMS: usize> {
     pub stiffness: T,
     /// The strength of the coupling between the lattice point and its equilibrium position.
     pub origin_stiffness: T,
+    /// The minimum distance between neighboring lattice points. If set, neighboring lattice points
+    /// are projected apart after each position update so that the lattice can't invert under
+    /// large forces.
+    pub min_bond_length: Option<T>,
+    /// The stride over the boundary sites that are observed, i.e. only every `boundary_stride`-th
+    /// site in the order of [`boundary_index_to_grid`] is observed. With a stride of one, the
+    /// full boundary is observed, while larger strides reduce the observation dimension for large
+    /// lattices.
+    pub boundary_stride: usize,
+    /// The lower and upper corners of the box the positions of the lattice points are confined
+    /// to, if any. Lattice points leaving the box are clamped back onto its walls after each
+    /// position update.
+    pub position_bounds: Option<(Vector<T, DIMS>, Vector<T, DIMS>)>,
+    /// Whether the walls of the [`SimulationConfig::position_bounds`] absorb the velocity
+    /// component of a lattice point moving into them, rather than reflecting it.
+    pub absorbing_bounds: bool,
+}
+
+impl<T: Float, const DIMS: usize> SimulationConfig<T, DIMS> {
+    /// Computes a deterministic hash of everything about the configuration that determines the
+    /// shapes and types of the arrays handed to JAX, i.e. the `size`, `boundary_stride`, `DIMS`,
+    /// and float type. JAX recompiles its functions whenever this changes.
+    ///
+    /// The hash is the 64-bit FNV-1a hash of the little-endian encoding of these fields, so it is
+    /// the same across runs, toolchains and platforms, and can be compared between logs.
+    pub fn shape_signature(&self) -> u64 {
+        let mut hasher = fnv::FnvHasher::default();
+        for field in [self.size, self.boundary_stride, DIMS] {
+            hasher.write(&(field as u64).to_le_bytes());
+        }
+        hasher.write_u8(T::float_type() as u8);
+        hasher.finish()
+    }
+}
+
+/// The set of [`SimulationConfig::shape_signature`]s seen by a JAX-backed component, used to
+/// make the cost of recompilation visible.
+#[derive(Debug, Default)]
+pub(crate) struct ShapeSignatures(Mutex<HashSet<u64>>);
+
+impl ShapeSignatures {
+    /// Records the shape signature of the given configuration, logging once per new signature
+    /// that `component` will trigger a JAX recompilation. Returns whether the signature is new.
+    pub(crate) fn observe<T: Float, const DIMS: usize>(
+        &self,
+        component: &str,
+        config: &SimulationConfig<T, DIMS>,
+    ) -> bool {
+        let signature = config.shape_signature();
+        let is_new = self.0.lock().unwrap().insert(signature);
+        if is_new {
+            log::info!(
+                "{component} observed new shape signature {signature:#018x} (size {}, {DIMS} \
+                 dimensions, {}), which triggers a JAX recompilation",
+                config.size,
+                T::float_type()
+            );
+        }
+
+        is_new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        system::{assert_system_sizes_consistent, DynamicVector},
+        vector::Vector,
+        wire::{WireError, WireFormat},
+        FloatType,
+    };
+
+    use super::{
+        boundary_index_to_grid, compute_boundary_size, grid_to_boundary_index, index,
+        ControlParameterState, ControlSignalState, CoupledHarmonicOscillator,
+        ObservableSimulationState, ObservableState, Observation, ShapeSignatures, SimulationConfig,
+        SimulationState, StateTensor,
+    };
+
+    /// A small lattice configuration for testing.
+    fn config() -> SimulationConfig<f64, 2> {
+        SimulationConfig {
+            size: 4,
+            stiffness: 1.,
+            origin_stiffness: 1.,
+            min_bond_length: None,
+            boundary_stride: 1,
+            position_bounds: None,
+            absorbing_bounds: false,
+        }
+    }
+
+    #[test]
+    fn test_boundary_index_round_trip() {
+        for size in 2..6 {
+            let boundary_size = compute_boundary_size(size);
+            for i in 0..boundary_size {
+                let x = boundary_index_to_grid(i, size);
+                assert!(
+                    x.iter().any(|&x| x == 0 || x == size - 1),
+                    "Expected {x:?} to be on the boundary of a lattice of size {size}"
+                );
+                assert_eq!(
+                    grid_to_boundary_index(x, size),
+                    Some(i),
+                    "Expected {x:?} to map back to boundary index {i} for size {size}"
+                );
+            }
+        }
+
+        assert_eq!(boundary_index_to_grid(0, 4), Vector::new([0, 0]));
+        assert_eq!(boundary_index_to_grid(3, 4), Vector::new([0, 3]));
+        assert_eq!(boundary_index_to_grid(6, 4), Vector::new([3, 3]));
+        assert_eq!(boundary_index_to_grid(11, 4), Vector::new([1, 0]));
+        assert_eq!(grid_to_boundary_index(Vector::new([1, 2]), 4), None);
+    }
+
+    #[test]
+    fn test_observe_boundary() {
+        let config = config();
+        let mut state = SimulationState::new(config);
+        for (i, position) in state.position.iter_mut().enumerate() {
+            *position = Vector::broadcast(i as f64);
+        }
+        let mut observable = ObservableSimulationState::new(config);
+        state.observe(&mut observable);
+
+        for (i, &position) in observable.position.iter().enumerate() {
+            let j = index(boundary_index_to_grid(i, config.size), config.size);
+            assert_eq!(
+                position,
+                Vector::broadcast(j as f64),
+                "Expected boundary point {i} to observe lattice point {j}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_observe_boundary_stride() {
+        let config = SimulationConfig {
+            boundary_stride: 2,
+            ..config()
+        };
+        let mut state = SimulationState::new(config);
+        for (i, position) in state.position.iter_mut().enumerate() {
+            *position = Vector::broadcast(i as f64);
+        }
+        let mut observable = ObservableSimulationState::new(config);
+        state.observe(&mut observable);
+
+        assert_eq!(
+            observable.position.len(),
+            compute_boundary_size(config.size) / 2,
+            "Expected half of the boundary to be observed"
+        );
+        assert_eq!(
+            observable.position[..],
+            [0., 2., 7., 15., 13., 8.].map(Vector::broadcast),
+            "Expected every other boundary point"
+        );
+    }
+
+    #[test]
+    fn test_shape_signature() {
+        let config = config();
+        let stiffer = SimulationConfig {
+            stiffness: 2.,
+            min_bond_length: Some(0.1),
+            ..config
+        };
+        let larger = SimulationConfig { size: 5, ..config };
+
+        assert_eq!(
+            config.shape_signature(),
+            stiffer.shape_signature(),
+            "Expected configs with the same shapes to share a signature"
+        );
+        assert_ne!(
+            config.shape_signature(),
+            larger.shape_signature(),
+            "Expected differing sizes to have different signatures"
+        );
+        assert_ne!(
+            config.shape_signature(),
+            SimulationConfig::<f32, 2> {
+                size: 4,
+                stiffness: 1.,
+                origin_stiffness: 1.,
+                min_bond_length: None,
+                boundary_stride: 1,
+                position_bounds: None,
+                absorbing_bounds: false,
+            }
+            .shape_signature(),
+            "Expected differing float types to have different signatures"
+        );
+
+        assert_eq!(
+            config.shape_signature(),
+            0x4927_210c_b6a8_2d99,
+            "Expected the signature to be the same in every run"
+        );
+
+        let signatures = ShapeSignatures::default();
+        assert!(
+            signatures.observe("test", &config),
+            "Expected a new signature"
+        );
+        assert!(
+            !signatures.observe("test", &stiffer),
+            "Expected a known signature"
+        );
+        assert!(
+            signatures.observe("test", &larger),
+            "Expected a new signature"
+        );
+    }
+
+    #[test]
+    fn test_par_copy_from_slice_matches_sequential() {
+        let mut sequential = SimulationState::new(config());
+        let mut parallel = SimulationState::new(config());
+        let values = (0..sequential.get_rope().len())
+            .map(|i| i as f64 * 0.5 - 3.)
+            .collect::<Vec<_>>();
+        sequential.copy_from_slice(&values);
+        parallel.par_copy_from_slice(&values);
+
+        assert_eq!(
+            sequential.position, parallel.position,
+            "Expected identical positions"
+        );
+        assert_eq!(
+            sequential.velocity, parallel.velocity,
+            "Expected identical velocities"
+        );
+        assert_eq!(
+            sequential.acceleration, parallel.acceleration,
+            "Expected identical accelerations"
+        );
+    }
+
+    #[test]
+    fn test_distance_identical() {
+        let state = SimulationState::new(config());
+
+        assert_eq!(state.distance(&state.clone()), 0.);
+        assert_eq!(state.phase_space_distance(&state.clone()), 0.);
+    }
+
+    #[test]
+    fn test_distance_known_displacement() {
+        let state = SimulationState::new(config());
+        let mut displaced = state.clone();
+        displaced.position[5] = Vector::new([3., 4.]);
+        displaced.velocity[2] = Vector::new([0., 5.]);
+
+        assert_eq!(state.distance(&displaced), 5. / 4.);
+        assert_eq!(
+            state.phase_space_distance(&displaced),
+            (2f64 * 25. / 16.).sqrt()
+        );
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let state = SimulationState::new(config());
+        let mut perturbed = state.clone();
+        perturbed.time = 1e-12;
+        perturbed.acceleration[3] = Vector::new([0., 0.9e-6]);
+        perturbed.velocity[7] = Vector::new([-0.9e-6, 0.]);
+
+        assert!(
+            state.approx_eq(&perturbed, 1e-6),
+            "Expected differences below epsilon"
+        );
+
+        perturbed.position[1] = Vector::new([1.1e-6, 0.]);
+        assert!(
+            !state.approx_eq(&perturbed, 1e-6),
+            "Expected a difference above epsilon"
+        );
+        assert!(
+            !state.approx_eq(
+                &SimulationState::new(SimulationConfig {
+                    size: 5,
+                    ..config()
+                }),
+                1.
+            ),
+            "Expected different lattice sizes to differ"
+        );
+    }
+
+    #[test]
+    fn test_observation_from_parts() {
+        let positions = [Vector::new([1., 2.]), Vector::new([-0.5, 0.])];
+        let velocities = [Vector::new([0., 3.]), Vector::new([4., -1.])];
+        let controls = ControlSignalState::new(vec![0.25; 12]);
+
+        let observation = Observation::from_parts(1.5, &positions, &velocities, &controls);
+        assert_eq!(
+            observation,
+            Observation {
+                time: 1.5,
+                state: ObservableState {
+                    position: positions.to_vec(),
+                    velocity: velocities.to_vec(),
+                },
+                controls: controls.clone(),
+            }
+        );
+
+        let borrowed = observation.as_ref();
+        assert_eq!(borrowed.to_owned(), observation);
+        assert!(
+            borrowed.get_rope().iter().eq(observation.get_rope().iter()),
+            "Expected the borrowed observation to have the same layout as the owned one"
+        );
+    }
+
+    #[test]
+    fn test_interpolate_to_midpoint() {
+        let from = ControlSignalState::<f64, 2>::new(vec![0., 1., -2.]);
+        let to = ControlSignalState::new(vec![2., 1., 2.]);
+
+        assert_eq!(from.interpolate_to(&to, 0.), from);
+        assert_eq!(from.interpolate_to(&to, 0.5).0[..], [1., 1., 0.]);
+        assert_eq!(from.interpolate_to(&to, 1.), to);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot interpolate between control signals of lengths 3 and 2")]
+    fn test_interpolate_to_mismatched_lengths() {
+        let from = ControlSignalState::<f64, 2>::new(vec![0., 1., -2.]);
+        from.interpolate_to(&ControlSignalState::new(vec![2., 1.]), 0.5);
+    }
+
+    #[test]
+    fn test_control_wire_format() {
+        let params = ControlParameterState::<f64, 2>::new(vec![1., -0.5, 2.25]);
+        assert_eq!(
+            ControlParameterState::from_bytes(&params.to_bytes()),
+            Ok(params)
+        );
+
+        let signal = ControlSignalState::<f32, 2>::new((0..12).map(|i| i as f32).collect());
+        let bytes = signal.to_bytes();
+        assert_eq!(ControlSignalState::from_bytes(&bytes), Ok(signal));
+        assert_eq!(
+            ControlSignalState::<f64, 2>::from_bytes(&bytes),
+            Err(WireError::DType {
+                expected: FloatType::F64,
+                actual: 'f'
+            })
+        );
+    }
+
+    #[test]
+    fn test_system_sizes_consistent() {
+        for (size, boundary_stride) in [(2, 1), (4, 1), (5, 3)] {
+            let config = SimulationConfig {
+                size,
+                boundary_stride,
+                ..config()
+            };
+            let state = SimulationState::new(config);
+            let mut observable = ObservableSimulationState::new(config);
+            state.observe(&mut observable);
+            let signal = ControlSignalState::default(config);
+            let observation =
+                Observation::from_parts(0., &observable.position, &observable.velocity, &signal);
+
+            assert_system_sizes_consistent::<f64, CoupledHarmonicOscillator<f64, 2>>(
+                &config,
+                &state,
+                &StateTensor::default(),
+                &ControlParameterState::default(config),
+                &signal,
+                &observation,
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_tensor_default() {
+        let tensor = StateTensor::<f64, 2>::default();
+
+        assert_eq!(tensor.time, 0., "Expected the default tensor at time zero");
+        assert!(
+            tensor.get_rope().iter().all(|&x| x == 0.),
+            "Expected the default embedding to be zero, got {tensor:?}"
+        );
+    }
 }
@@ -1,7 +1,13 @@
-use common::{coordinator::experiment, python::JaxKey};
+use common::{
+    coordinator::{experiment_from_setup, ExperimentSetup},
+    python::JaxKey,
+};
 use sho::{
-    driver::SHOAgent, generator::SHOGenerator, simulator::SHOSimulator,
-    state_estimator::SHOStatePredictor, system::SimpleHarmonicOscillator,
+    driver::SHOAgent,
+    generator::SHOGenerator,
+    simulator::SHOSimulator,
+    state_estimator::SHOStatePredictor,
+    system::{GammaSchedule, SHODynamicsConfiguration, SimpleHarmonicOscillator},
 };
 use smol::block_on;
 
@@ -9,26 +15,38 @@ fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
     pyo3::prepare_freethreaded_python();
 
-    let key = JaxKey::key(112045);
-    let system = SimpleHarmonicOscillator {
-        stiffness: 1.0f32,
-        gamma: 1.1,
-    };
-    let simulator = SHOSimulator::new(&system);
+    let setup = ExperimentSetup::new(
+        SimpleHarmonicOscillator {
+            stiffness: 1.0f32,
+            gamma: GammaSchedule::Constant(1.1),
+        },
+        SHODynamicsConfiguration::default(),
+        1e-2,
+        112045,
+    );
+
+    let key = JaxKey::key(setup.seed);
+    let system = setup.system;
+    let simulator = SHOSimulator::new(&system).with_dynamics(&setup.dynamics, setup.seed);
     let generator = SHOGenerator::new(&system);
     let [key, driver_key] = key.split();
     let driver = SHOAgent::new(driver_key, &system);
     let [key, state_predictor_key] = key.split();
     let state_predictor = SHOStatePredictor::new(state_predictor_key, &system);
 
-    block_on(experiment(
+    let result = block_on(experiment_from_setup(
         &system,
         driver,
         generator,
         simulator,
         state_predictor,
-        1e-2,
+        &setup,
     ));
 
+    println!(
+        "Finished after {} steps at time {} with dynamics loss {}",
+        result.steps, result.time, result.dynamics_loss
+    );
+
     Ok(())
 }
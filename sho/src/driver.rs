@@ -1,37 +1,59 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use common::{
     interfaces::DriverInterface,
-    python::{JaxArray, JaxKey, PythonExt},
-    system::{DynamicVector, System},
+    python::{retry_async, JaxArray, JaxKey, PythonExt, PythonModelSource},
+    system::System,
     Float,
 };
 use pyo3::{
-    types::{IntoPyDict, PyAnyMethods, PyModule},
+    types::{IntoPyDict, PyAnyMethods},
     Bound, Py, PyAny, PyResult, Python, ToPyObject,
 };
 use smol::lock::Mutex;
 
-use crate::system::{SHOControlParams, SHOLatentState, SimpleHarmonicOscillator};
+use crate::system::{GammaSchedule, SHOControlParams, SHOLatentState, SimpleHarmonicOscillator};
+
+/// The number of times the agent step is attempted before giving up on transient Python errors.
+const STEP_ATTEMPTS: usize = 3;
 
 /// The implementation of [`DriverInterface`] for [`SimpleHarmonicOscillator`]
 pub struct SHOAgent<T: Float> {
     /// The object associated with the agent.
     agent: Mutex<Py<PyAny>>,
+    /// The schedule of the reward decay speed passed to the agent.
+    gamma: GammaSchedule<T>,
+    /// The number of times controls have been computed.
+    step: AtomicUsize,
     /// [`PhantomData`] to support the generic type.
     _phantom: PhantomData<T>,
 }
 
 impl<T: Float> SHOAgent<T> {
-    /// Creates an instance of [`SHOAgent`].
+    /// Creates an instance of [`SHOAgent`] from the embedded "sho_agent.py" script.
     pub fn new(key: JaxKey, system: &SimpleHarmonicOscillator<T>) -> Self {
+        Self::from_source(key, system, &PythonModelSource::Embedded, "SHOAgent").unwrap()
+    }
+
+    /// Creates an instance of [`SHOAgent`] from the model class called `class_name` in the given
+    /// source, e.g. an externally trained model. The class must expose the same `init_state` and
+    /// `step` methods as the `SHOAgent` class of the embedded script.
+    pub fn from_source(
+        key: JaxKey,
+        system: &SimpleHarmonicOscillator<T>,
+        source: &PythonModelSource,
+        class_name: &str,
+    ) -> PyResult<Self> {
         /// The code in the "sho_agent.py" script.
         const CODE: &str = include_str!("sho_agent.py");
 
         let agent = Python::with_gil_ext(|py| -> PyResult<Py<PyAny>> {
-            let module = PyModule::from_code_bound(py, CODE, "sho_agent.py", "sho_agent")?;
+            let class = source.load_class(py, (CODE, "sho_agent.py"), class_name)?;
 
-            let agent = module.getattr("SHOAgent")?.getattr("init_state")?.call(
+            let agent = class.getattr("init_state")?.call(
                 (),
                 Some(
                     &[
@@ -44,21 +66,22 @@ impl<T: Float> SHOAgent<T> {
                             "control_dimension",
                             SimpleHarmonicOscillator::<T>::CONTROL_PARAMS_SIZE.to_object(py),
                         ),
-                        ("gamma", system.gamma.to_object(py)),
+                        ("gamma", system.gamma.gamma(0).to_object(py)),
                     ]
                     .into_py_dict_bound(py),
                 ),
             )?;
 
             Ok(agent.unbind())
-        })
-        .unwrap()
+        })?
         .into();
 
-        Self {
+        Ok(Self {
             agent,
+            gamma: system.gamma,
+            step: AtomicUsize::new(0),
             _phantom: PhantomData,
-        }
+        })
     }
 }
 
@@ -68,41 +91,38 @@ impl<T: Float> DriverInterface<T, SimpleHarmonicOscillator<T>> for SHOAgent<T> {
         state_estimate: SHOLatentState<T>,
         dynamics_loss: T,
     ) -> SHOControlParams<T> {
-        let mut agent_lock = self.agent.lock().await;
-        let array = Python::with_gil_ext(|py| -> PyResult<_> {
-            py.check_signals()?;
+        let gamma = self.gamma.gamma(self.step.fetch_add(1, Ordering::Relaxed));
 
-            let data: JaxArray =
-                JaxArray::new_1d(state_estimate.get_rope().into_iter().copied().collect());
+        let mut agent_lock = self.agent.lock().await;
+        let data: JaxArray = JaxArray::from_vector(&state_estimate.latent_representation);
+        let array = retry_async(STEP_ATTEMPTS, || {
+            Python::with_gil_ext(|py| -> PyResult<_> {
+                py.check_signals()?;
 
-            let agent_bound = agent_lock.bind(py);
-            let result = agent_bound
-                .call_method(
-                    "step",
-                    (
-                        agent_bound,
-                        data.to_object(py),
-                        (-dynamics_loss).to_object(py),
-                    ),
-                    None,
-                )?
-                .extract::<(Bound<PyAny>, Bound<PyAny>)>()?;
+                let agent_bound = agent_lock
+                    .bind(py)
+                    .call_method1("with_gamma", (agent_lock.bind(py), gamma.to_object(py)))?;
+                let result = agent_bound
+                    .call_method(
+                        "step",
+                        (
+                            &agent_bound,
+                            data.to_object(py),
+                            (-dynamics_loss).to_object(py),
+                        ),
+                        None,
+                    )?
+                    .extract::<(Bound<PyAny>, Bound<PyAny>)>()?;
 
-            *agent_lock = result.0.unbind();
-            Ok(JaxArray::new(result.1.unbind()))
+                *agent_lock = result.0.unbind();
+                Ok(JaxArray::new(result.1.unbind()))
+            })
         })
+        .await
         .unwrap()
         .await
         .into_inner();
 
-        let control = Python::with_gil_ext(|py| {
-            array
-                .bind(py)
-                .call_method0("item")
-                .unwrap()
-                .extract::<T>()
-                .unwrap()
-        });
-        SHOControlParams { control }
+        Python::with_gil_ext(|py| SHOControlParams::try_from_py(&array, py)).unwrap()
     }
 }
@@ -8,16 +8,34 @@ pub struct SHOGenerator<T: Float> {
     time: T,
     /// The last controls supplied to the generator.
     controls: SHOControlParams<T>,
+    /// The magnitude of the force, i.e. the control authority.
+    magnitude: T,
+    /// Whether the force is scaled by `tanh(|control|)`, see [`SHOGenerator::with_saturation`].
+    saturation: bool,
 }
 
 impl<T: Float> SHOGenerator<T> {
-    /// Creates an instance of [`SHOGenerator`].
-    pub fn new(_system: &SimpleHarmonicOscillator<T>) -> Self {
+    /// Creates an instance of [`SHOGenerator`] producing unit forces.
+    pub fn new(system: &SimpleHarmonicOscillator<T>) -> Self {
+        Self::with_magnitude(system, T::one())
+    }
+
+    /// Creates an instance of [`SHOGenerator`] producing forces of the given magnitude.
+    pub fn with_magnitude(_system: &SimpleHarmonicOscillator<T>, magnitude: T) -> Self {
         Self {
             time: T::zero(),
-            controls: SHOControlParams { control: T::zero() },
+            controls: SHOControlParams::default(),
+            magnitude,
+            saturation: false,
         }
     }
+
+    /// Scales the force by `tanh(|control|)`, so that it ramps up from zero with the control
+    /// parameter and saturates at the configured magnitude for large parameters.
+    pub fn with_saturation(mut self) -> Self {
+        self.saturation = true;
+        self
+    }
 }
 
 impl<T: Float> GeneratorInterface<T, SimpleHarmonicOscillator<T>> for SHOGenerator<T> {
@@ -27,9 +45,60 @@ impl<T: Float> GeneratorInterface<T, SimpleHarmonicOscillator<T>> for SHOGenerat
     }
 
     fn control_signal(&mut self, _time: T) -> SHOControlSignal<T> {
-        let (sin, cos) = self.controls.control.sin_cos();
+        let control = self.controls.control;
+        let (sin, cos) = control.sin_cos();
+        let magnitude = if self.saturation {
+            self.magnitude * control.abs().tanh()
+        } else {
+            self.magnitude
+        };
         SHOControlSignal {
-            control: Vector::new([sin, cos]) * T::from(1.0).unwrap(),
+            control: Vector::new([sin, cos]) * magnitude,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::interfaces::GeneratorInterface;
+
+    use super::SHOGenerator;
+    use crate::system::{GammaSchedule, SHOControlParams, SimpleHarmonicOscillator};
+
+    /// The magnitude of the force produced for the given control parameter.
+    fn force_magnitude(generator: &mut SHOGenerator<f64>, control: f64) -> f64 {
+        smol::block_on(generator.set_parameters(SHOControlParams { control }, 0.));
+        let force = generator.control_signal(0.).control;
+        force.dot(force).sqrt()
+    }
+
+    #[test]
+    fn test_force_magnitude() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+
+        let mut generator = SHOGenerator::with_magnitude(&system, 2.5);
+        for control in [0., 0.3, -2., 100.] {
+            assert!(
+                (force_magnitude(&mut generator, control) - 2.5).abs() < 1e-12,
+                "Expected the configured force magnitude for control {control}"
+            );
+        }
+
+        let mut generator = SHOGenerator::with_magnitude(&system, 2.5).with_saturation();
+        assert_eq!(force_magnitude(&mut generator, 0.), 0.);
+        assert!(
+            force_magnitude(&mut generator, 0.5) < 2.5 * 0.5,
+            "Expected small controls to give weaker forces"
+        );
+        for control in [20., -1e6] {
+            let magnitude = force_magnitude(&mut generator, control);
+            assert!(
+                magnitude <= 2.5 && (magnitude - 2.5).abs() < 1e-9,
+                "Expected large controls to saturate at the configured magnitude, got {magnitude}"
+            );
         }
     }
 }
@@ -177,6 +177,10 @@ class SHOAgent(Jaximal):
             buffer, optimizer, buffer_state, opt_state, agent_params, gamma, 0, key
         )
 
+    @staticmethod
+    def with_gamma(state: 'SHOAgent', gamma: float) -> 'SHOAgent':
+        return replace(state, gamma=gamma)
+
     @staticmethod
     @jax.jit
     def update(state: 'SHOAgent', sample_key: PRNGKeyArray):
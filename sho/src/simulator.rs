@@ -1,25 +1,67 @@
-use common::{interfaces::SimulatorInterface, vector::Vector, Float};
-use smol::{fs::File, io::AsyncWriteExt};
+use common::{
+    integrator::{Integrator, VelocityVerlet},
+    interfaces::{axis_labels, SimulatorInterface},
+    noise::{GaussianNoise, Rng},
+    recorder::{CsvRecorder, TrajectoryRecorder},
+    ring::ring_indices,
+    system::DynamicVector,
+    vector::Vector,
+    Float,
+};
+use std::path::PathBuf;
 
 use crate::system::{
-    SHOControlSignal, SHOSystemObservation, SHOSystemState, SimpleHarmonicOscillator, DELAY_DEPTH,
+    SHOControlSignal, SHODynamicsConfiguration, SHOSystemObservation, SHOSystemState,
+    SimpleHarmonicOscillator, DELAY_DEPTH,
 };
 
-/// A simple Rust simulator for the [`SimpleHarmonicOscillator`] system.
-pub struct SHOSimulator<T: Float> {
+/// The default path of the file the trajectory is recorded to.
+const DEFAULT_RECORDS_PATH: &str = "./records.csv";
+
+/// The largest displacement of the oscillator from the origin for which the simulation is still
+/// considered stable.
+const MAX_STABLE_DISPLACEMENT: f64 = 1e6;
+
+/// A prescribed target trajectory of the observations of the [`SimpleHarmonicOscillator`], as a
+/// function of time.
+pub type Target<T> = dyn Fn(T) -> SHOSystemObservation<T> + Send + Sync;
+
+/// A [`TrajectoryRecorder`] of the [`SimpleHarmonicOscillator`] that can be held by the
+/// [`SHOSimulator`].
+pub type Recorder<T> = dyn TrajectoryRecorder<T, SimpleHarmonicOscillator<T>> + Send + Sync;
+
+/// A simple Rust simulator for the [`SimpleHarmonicOscillator`] system, stepped by the
+/// [`Integrator`] `I`.
+pub struct SHOSimulator<T: Float, I = VelocityVerlet> {
     /// The last `[DELAY_DEPTH] + 1` states.
     states: [SHOSystemState<T>; DELAY_DEPTH + 1],
     /// The last `[DELAY_DEPTH] + 1` controls.
     controls: [SHOControlSignal<T>; DELAY_DEPTH + 1],
     /// The offset of the current state.
     offset: usize,
-    /// The file to write records to.
-    file: File,
+    /// The path of the file to record the trajectory to, unless another recorder is given.
+    records_path: PathBuf,
+    /// The recorder of the states and controls, by default a [`CsvRecorder`] of the file at
+    /// `records_path` created on the first update.
+    recorder: Option<Box<Recorder<T>>>,
+    /// The integrator used to step the system.
+    integrator: I,
+    /// The noise added to the observed positions, if any.
+    noise: Option<GaussianNoise<T>>,
+    /// The target trajectory to track, if any.
+    target: Option<Box<Target<T>>>,
 }
 
 impl<T: Float> SHOSimulator<T> {
-    /// Creates an instance of [`SHOSimulator`].
-    pub fn new(_system: &SimpleHarmonicOscillator<T>) -> Self {
+    /// Creates an instance of [`SHOSimulator`] stepped by the [`VelocityVerlet`] integrator.
+    pub fn new(system: &SimpleHarmonicOscillator<T>) -> Self {
+        Self::with_integrator(system, VelocityVerlet)
+    }
+}
+
+impl<T: Float, I> SHOSimulator<T, I> {
+    /// Creates an instance of [`SHOSimulator`] stepped by the given [`Integrator`].
+    pub fn with_integrator(_system: &SimpleHarmonicOscillator<T>, integrator: I) -> Self {
         Self {
             states: [SHOSystemState {
                 time: T::zero(),
@@ -30,70 +72,140 @@ impl<T: Float> SHOSimulator<T> {
                 control: Vector::basis(0),
             }; DELAY_DEPTH + 1],
             offset: 0,
-            file: smol::block_on(File::create("./records.csv")).unwrap(),
+            records_path: PathBuf::from(DEFAULT_RECORDS_PATH),
+            recorder: None,
+            integrator,
+            noise: None,
+            target: None,
         }
     }
-}
 
-impl<T: Float> SHOSystemState<T> {
-    /// Computes the acceleration of the system.
-    fn compute_acceleration(&self, stiffness: T, control: SHOControlSignal<T>) -> Vector<T, 2> {
-        -self.position * stiffness + control.control
+    /// Adds Gaussian noise with the given standard deviation to the observed positions,
+    /// reproducibly drawn from a random number generator seeded by `seed`.
+    pub fn with_observation_noise(mut self, stddev: T, seed: u64) -> Self {
+        self.noise = Some(GaussianNoise::new(stddev, seed));
+        self
+    }
+
+    /// Adds Gaussian noise with the given standard deviation to the observed positions, drawn from the
+    /// given random number generator, e.g. a [`JaxKey`](common::python::JaxKey).
+    pub fn with_observation_noise_rng(mut self, stddev: T, rng: impl Rng<T> + 'static) -> Self {
+        self.noise = Some(GaussianNoise::with_rng(stddev, rng));
+        self
+    }
+
+    /// Records the trajectory to the file at the given path instead of `./records.csv`. The file
+    /// is created on the first update, and flushed when the simulator is dropped.
+    pub fn with_records_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.records_path = path.into();
+        self
+    }
+
+    /// Records the trajectory with the given recorder instead of to a file.
+    pub fn with_recorder(
+        mut self,
+        recorder: impl TrajectoryRecorder<T, SimpleHarmonicOscillator<T>> + Send + Sync + 'static,
+    ) -> Self {
+        self.recorder = Some(Box::new(recorder));
+        self
+    }
+
+    /// Sets the target trajectory `x*(t)` that [`SHOSimulator::tracking_loss`] compares the
+    /// observations to, e.g. a moving [`SHOSystemObservation::setpoint`].
+    pub fn with_target(
+        mut self,
+        target: impl Fn(T) -> SHOSystemObservation<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.target = Some(Box::new(target));
+        self
+    }
+
+    /// Computes the Euclidean distance between the current (noiseless) position and the position
+    /// of the target at the current time, or zero if there is no target. The controls of the
+    /// target are ignored.
+    pub fn tracking_loss(&self) -> T {
+        let Some(target) = &self.target else {
+            return T::zero();
+        };
+        let state = &self.states[self.offset];
+
+        let error = state.position - target(state.time).positions;
+        error.dot(error).sqrt()
+    }
+
+    /// Applies the given dynamics configuration, seeding any randomness with `seed`, e.g. the
+    /// [`ExperimentSetup::seed`](common::coordinator::ExperimentSetup::seed).
+    pub fn with_dynamics(self, dynamics: &SHODynamicsConfiguration<T>, seed: i64) -> Self {
+        match dynamics.observation_noise {
+            // Reinterprets the bits of the seed, so that distinct seeds stay distinct.
+            Some(stddev) => self.with_observation_noise(stddev, seed as u64),
+            None => self,
+        }
+    }
+
+    /// Gets the indices of the observed states in the history, oldest first.
+    fn observation_indices(&self) -> [usize; DELAY_DEPTH] {
+        ring_indices::<DELAY_DEPTH>(self.offset + 1, 1, DELAY_DEPTH + 1)
+    }
+
+    /// Observes the state at the given index of the history, applying the observation noise.
+    fn observation(&self, i: usize) -> SHOSystemObservation<T> {
+        let mut observation = SHOSystemObservation {
+            time: self.states[i].time,
+            positions: self.states[i].position,
+            controls: self.controls[i],
+        };
+        if let Some(noise) = &self.noise {
+            noise.apply(observation.positions.get_rope_mut());
+        }
+
+        observation
     }
 }
 
-impl<T: Float> SimulatorInterface<T, SimpleHarmonicOscillator<T>> for SHOSimulator<T> {
+impl<T: Float, I: Integrator<T, SimpleHarmonicOscillator<T>>>
+    SimulatorInterface<T, SimpleHarmonicOscillator<T>> for SHOSimulator<T, I>
+{
     async fn get_observations(&self) -> Vec<SHOSystemObservation<T>> {
-        let mut vec = Vec::with_capacity(DELAY_DEPTH);
-
-        for i in ((self.offset as isize + 1)..(self.offset as isize + 1 + DELAY_DEPTH as isize))
-            .map(|i| (i % (DELAY_DEPTH + 1) as isize) as usize)
-        {
-            vec.push(SHOSystemObservation {
-                time: self.states[i].time,
-                positions: self.states[i].position,
-                controls: self.controls[i],
-            })
-        }
+        self.observation_indices()
+            .into_iter()
+            .map(|i| self.observation(i))
+            .collect()
+    }
 
-        vec
+    async fn latest_observation(&self) -> SHOSystemObservation<T> {
+        let [.., latest] = self.observation_indices();
+        self.observation(latest)
     }
 
+    /// Steps the system with the integrator, switching from the control signal of the current
+    /// state at the start of the step to the new control signal at its end, see
+    /// [`Integrator::step_with_controls`].
     async fn update(
         &mut self,
         system: &SimpleHarmonicOscillator<T>,
         dt: T,
         control_signal: &SHOControlSignal<T>,
     ) {
-        let two = T::one() + T::one();
-
         let next_offset = (self.offset + 1) % (DELAY_DEPTH + 1);
         self.controls[next_offset].clone_from(control_signal);
 
-        let prev_acc = self.states[self.offset]
-            .compute_acceleration(system.stiffness, self.controls[self.offset]);
-
-        self.states[next_offset].time = self.states[self.offset].time + dt;
-        self.states[next_offset].position = self.states[self.offset].position
-            + self.states[self.offset].velocity * dt
-            + prev_acc * dt * dt / two;
-
-        let next_acc = self.states[next_offset]
-            .compute_acceleration(system.stiffness, self.controls[next_offset]);
-
-        self.states[next_offset].velocity =
-            self.states[self.offset].velocity + (prev_acc + next_acc) / two * dt;
-
-        let _ = self
-            .file
-            .write_all(
-                format!(
-                    "{:?}, {:?}\n",
-                    self.states[self.offset].position[0], self.states[self.offset].position[1]
-                )
-                .as_bytes(),
-            )
-            .await;
+        let state = self.states[self.offset];
+        self.integrator.step_with_controls(
+            system,
+            &state,
+            &mut self.states[next_offset],
+            &self.controls[self.offset],
+            control_signal,
+            dt,
+        );
+
+        let recorder = self
+            .recorder
+            .get_or_insert_with(|| Box::new(CsvRecorder::create(&self.records_path).unwrap()));
+        // Failing to record the trajectory shouldn't stop the experiment.
+        let _ = recorder.record_state(state.time, &state);
+        let _ = recorder.record_control(state.time, control_signal);
         self.offset = next_offset;
 
         println!("{}", self.states[self.offset].position.map(|i| i * i).sum())
@@ -107,10 +219,508 @@ impl<T: Float> SimulatorInterface<T, SimpleHarmonicOscillator<T>> for SHOSimulat
         (self.states[self.offset].position.map(|i| i * i).sum() - T::one())
             .powf(T::one() + T::one())
     }
+
+    async fn is_stable(&self) -> bool {
+        let threshold = T::from(MAX_STABLE_DISPLACEMENT).unwrap();
+        let SHOSystemState {
+            position, velocity, ..
+        } = self.states[self.offset];
+
+        position.iter().all(|x| x.abs() < threshold) && velocity.is_finite()
+    }
+
+    fn peek_full_state(&self) -> Option<SHOSystemState<T>> {
+        Some(self.states[self.offset])
+    }
+
+    fn observation_labels(&self) -> Vec<String> {
+        axis_labels("pos", 2)
+            .chain(axis_labels("ctrl", 2))
+            .collect()
+    }
 }
 
-impl<T: Float> Drop for SHOSimulator<T> {
+/// Flushes the buffered records, so that they aren't lost if the experiment stops early.
+impl<T: Float, I> Drop for SHOSimulator<T, I> {
     fn drop(&mut self) {
-        smol::block_on(self.file.flush());
+        if let Some(recorder) = &mut self.recorder {
+            let _ = recorder.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use common::{
+        coordinator::{replay, ExperimentSetup},
+        interfaces::SimulatorInterface,
+        recorder::TrajectoryRecorder,
+        system::DynamicVector,
+        test_util::energy_drift,
+        vector::Vector,
+    };
+
+    use super::SHOSimulator;
+    use crate::system::{
+        GammaSchedule, SHOControlSignal, SHODynamicsConfiguration, SHOSystemObservation,
+        SHOSystemState, SimpleHarmonicOscillator, DELAY_DEPTH,
+    };
+
+    /// A record of the [`LogRecorder`].
+    #[derive(Debug, PartialEq)]
+    enum Record {
+        /// The time of a recorded state.
+        State(f64),
+        /// A recorded control signal.
+        Control(Vector<f64, 2>),
+    }
+
+    /// The recorded states and control signals.
+    type Trace = (Vec<SHOSystemState<f64>>, Vec<SHOControlSignal<f64>>);
+
+    /// A recorder that keeps the full states and the control signals in memory.
+    #[derive(Default)]
+    struct TraceRecorder(Arc<Mutex<Trace>>);
+
+    impl TrajectoryRecorder<f64, SimpleHarmonicOscillator<f64>> for TraceRecorder {
+        fn record_state(&mut self, _time: f64, state: &SHOSystemState<f64>) -> io::Result<()> {
+            self.0.lock().unwrap().0.push(*state);
+            Ok(())
+        }
+
+        fn record_control(
+            &mut self,
+            _time: f64,
+            control: &SHOControlSignal<f64>,
+        ) -> io::Result<()> {
+            self.0.lock().unwrap().1.push(*control);
+            Ok(())
+        }
+    }
+
+    /// A recorder that logs the times of the states and the control signals in memory.
+    struct LogRecorder(Arc<Mutex<Vec<Record>>>);
+
+    impl TrajectoryRecorder<f64, SimpleHarmonicOscillator<f64>> for LogRecorder {
+        fn record_state(&mut self, time: f64, _state: &SHOSystemState<f64>) -> io::Result<()> {
+            self.0.lock().unwrap().push(Record::State(time));
+            Ok(())
+        }
+
+        fn record_control(
+            &mut self,
+            _time: f64,
+            control: &SHOControlSignal<f64>,
+        ) -> io::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(Record::Control(control.control));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tracking_loss_converges() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let path = std::env::temp_dir().join(format!("sho_tracking_{}.csv", std::process::id()));
+        let mut simulator = SHOSimulator::new(&system)
+            .with_records_path(&path)
+            .with_target(move |time| SHOSystemObservation::setpoint(time, Vector::zero()));
+        simulator.states[simulator.offset].position = Vector::new([2., 1.]);
+
+        // Damp the undamped oscillator, so that it settles on the target at rest.
+        let mut losses = Vec::new();
+        for step in 0..2000 {
+            if step % 500 == 0 {
+                losses.push(simulator.tracking_loss());
+            }
+            let state = simulator.states[simulator.offset];
+            let control = SHOControlSignal {
+                control: -state.velocity,
+            };
+            smol::block_on(simulator.update(&system, 1e-2, &control));
+        }
+        losses.push(simulator.tracking_loss());
+        drop(simulator);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            losses.windows(2).all(|w| w[1] < w[0]),
+            "Expected the tracking loss to decrease, got {losses:?}"
+        );
+        assert!(
+            losses.last().unwrap() < &1e-2,
+            "Expected the oscillator to settle on the target, got {losses:?}"
+        );
+    }
+
+    #[test]
+    fn test_tracking_loss_ignores_controls() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let mut simulator = SHOSimulator::new(&system)
+            .with_recorder(TraceRecorder::default())
+            .with_target(|time| SHOSystemObservation {
+                time,
+                positions: Vector::new([0., 4.]),
+                controls: SHOControlSignal {
+                    control: Vector::new([5., 5.]),
+                },
+            });
+        simulator.states[simulator.offset].position = Vector::new([3., 0.]);
+
+        assert_eq!(
+            simulator.tracking_loss(),
+            5.,
+            "Expected the distance between the positions alone"
+        );
+    }
+
+    #[test]
+    fn test_update_matches_baseline() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 2f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let mut simulator = SHOSimulator::new(&system).with_recorder(TraceRecorder::default());
+        simulator.states[simulator.offset].position = Vector::new([1., -0.5]);
+        simulator.states[simulator.offset].velocity = Vector::new([0., 0.25]);
+
+        // The states after every update of the simulator before it was generic over its
+        // integrator, whose acceleration at the start of a step used the previous control.
+        let expected = [
+            (0.1, [0.995, -0.47], [-0.14950000000000002, 0.397]),
+            (0.2, [0.9701, -0.4206], [-0.32101, 0.57106]),
+            (
+                0.30000000000000004,
+                [0.9307979999999999, -0.355788],
+                [-0.4360998, 0.7036988],
+            ),
+            (
+                0.4,
+                [0.8828800399999999, -0.27986024],
+                [-0.49246760399999995, 0.7922636239999999],
+            ),
+            (
+                0.5,
+                [0.8323044791999998, -0.1973352752],
+                [-0.48898605591999994, 0.8349831755199999],
+            ),
+        ];
+        for (i, (time, position, velocity)) in expected.into_iter().enumerate() {
+            let control = SHOControlSignal {
+                control: Vector::new([0.5 * i as f64, 1. - 0.3 * i as f64]),
+            };
+            smol::block_on(simulator.update(&system, 0.1, &control));
+
+            let state = simulator.states[simulator.offset];
+            assert_eq!(state.time, time, "Expected the time of update {i}");
+            assert_eq!(
+                state.position,
+                Vector::new(position),
+                "Expected the position of update {i}"
+            );
+            assert_eq!(
+                state.velocity,
+                Vector::new(velocity),
+                "Expected the velocity of update {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_verlet_energy_drift() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 2f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let mut simulator = SHOSimulator::new(&system).with_recorder(TraceRecorder::default());
+        simulator.states[simulator.offset].position = Vector::new([1., 0.]);
+        simulator.states[simulator.offset].velocity = Vector::new([0., 0.5]);
+
+        let (min, max) = smol::block_on(energy_drift(&system, &mut simulator, 1e-2, 5000));
+        assert!(
+            (max - min) / min < 1e-4,
+            "Expected velocity Verlet to keep the energy within [{min}, {max}] nearly constant"
+        );
+    }
+
+    #[test]
+    fn test_records_flushed_on_drop() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let control = SHOControlSignal {
+            control: Vector::new([0.5, 0.]),
+        };
+        let path = std::env::temp_dir().join(format!("sho_records_{}.csv", std::process::id()));
+
+        let mut simulator = SHOSimulator::new(&system).with_records_path(&path);
+        for _ in 0..5 {
+            smol::block_on(simulator.update(&system, 1e-2, &control));
+        }
+        // Stop the experiment mid-cycle, before the ring buffer wraps around.
+        drop(simulator);
+
+        let records = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for kind in ["state", "control"] {
+            assert_eq!(
+                records
+                    .lines()
+                    .filter(|line| line.starts_with(kind))
+                    .count(),
+                5,
+                "Expected a {kind} record for every update, got {records:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_records_controls() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut simulator = SHOSimulator::new(&system).with_recorder(LogRecorder(log.clone()));
+
+        let controls = (0..4)
+            .map(|i| SHOControlSignal {
+                control: Vector::new([i as f64, -0.5]),
+            })
+            .collect::<Vec<_>>();
+        for control in &controls {
+            smol::block_on(simulator.update(&system, 0.25, control));
+        }
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            controls
+                .iter()
+                .enumerate()
+                .flat_map(|(i, control)| [
+                    Record::State(i as f64 * 0.25),
+                    Record::Control(control.control)
+                ])
+                .collect::<Vec<_>>(),
+            "Expected the controls passed to every update, interleaved with the states"
+        );
+    }
+
+    #[test]
+    fn test_observation_labels() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let simulator = SHOSimulator::new(&system);
+        let observations = smol::block_on(simulator.get_observations());
+
+        assert_eq!(
+            simulator.observation_labels(),
+            ["pos_x", "pos_y", "ctrl_x", "ctrl_y"]
+        );
+        assert_eq!(
+            simulator.observation_labels().len(),
+            observations[0].get_rope().len(),
+            "Expected a label for every observation component"
+        );
+    }
+
+    #[test]
+    fn test_observations_chronological_after_wraparound() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut simulator = SHOSimulator::new(&system).with_recorder(LogRecorder(log));
+        let control = SHOControlSignal {
+            control: Vector::zero(),
+        };
+
+        for step in 1..=2 * (DELAY_DEPTH + 1) + 1 {
+            smol::block_on(simulator.update(&system, 0.5, &control));
+            let times = smol::block_on(simulator.get_observations())
+                .iter()
+                .map(|observation| observation.time)
+                .collect::<Vec<_>>();
+            assert_eq!(times.len(), DELAY_DEPTH);
+            assert!(
+                times.windows(2).all(|w| w[1] > w[0]) || step < DELAY_DEPTH,
+                "Expected chronological observations after {step} steps, got {times:?}"
+            );
+            assert_eq!(
+                times.last(),
+                Some(&((step - 1) as f64 * 0.5)),
+                "Expected the latest observation to precede the current state"
+            );
+        }
+    }
+
+    #[test]
+    fn test_latest_observation() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(1.),
+        };
+        let mut simulator = SHOSimulator::new(&system).with_recorder(TraceRecorder::default());
+        let control = SHOControlSignal {
+            control: Vector::new([0.5, -1.]),
+        };
+
+        for _ in 0..2 * DELAY_DEPTH {
+            smol::block_on(simulator.update(&system, 0.25, &control));
+            let observations = smol::block_on(simulator.get_observations());
+            let latest = smol::block_on(simulator.latest_observation());
+            assert_eq!(
+                latest.get_rope(),
+                observations.last().unwrap().get_rope(),
+                "Expected the last observation of the window"
+            );
+            assert_eq!(latest.time, observations.last().unwrap().time);
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_trajectory() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1f64,
+            gamma: GammaSchedule::Constant(0.5),
+        };
+        let setup = ExperimentSetup::new(system, SHODynamicsConfiguration::default(), 0.05, 0);
+
+        let original = TraceRecorder::default();
+        let trace = original.0.clone();
+        let mut simulator = SHOSimulator::new(&system).with_recorder(original);
+        for i in 0..40 {
+            let control = SHOControlSignal {
+                control: Vector::new([(i as f64).sin(), (i as f64 * 0.3).cos()]),
+            };
+            smol::block_on(simulator.update(&system, setup.dt, &control));
+        }
+        drop(simulator);
+        let (states, controls) = std::mem::take(&mut *trace.lock().unwrap());
+
+        let replayed = TraceRecorder::default();
+        let replayed_trace = replayed.0.clone();
+        let result = smol::block_on(replay(
+            &system,
+            SHOSimulator::new(&system).with_recorder(replayed),
+            &setup,
+            controls,
+        ));
+
+        assert_eq!(result.steps, 40, "Expected a step per recorded control");
+        let replayed_states = &replayed_trace.lock().unwrap().0;
+        assert_eq!(replayed_states.len(), states.len());
+        for (replayed, original) in replayed_states.iter().zip(&states) {
+            let distance = (replayed.position - original.position)
+                .map(f64::abs)
+                .into_iter()
+                .chain((replayed.velocity - original.velocity).map(f64::abs))
+                .fold(0., f64::max);
+            assert!(
+                distance < 1e-12,
+                "Expected the replayed state {replayed:?} to match {original:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_setup_toml_round_trip() {
+        let setup = ExperimentSetup::<f64, SimpleHarmonicOscillator<f64>>::new(
+            SimpleHarmonicOscillator {
+                stiffness: 2.,
+                gamma: GammaSchedule::Linear {
+                    start: 0.5,
+                    end: 1.,
+                    steps: 10,
+                },
+            },
+            SHODynamicsConfiguration {
+                observation_noise: Some(0.1),
+            },
+            1e-2,
+            112045,
+        )
+        .max_steps(5);
+        let path = std::env::temp_dir().join(format!("sho_setup_{}.toml", std::process::id()));
+        setup.save(&path).unwrap();
+        let loaded = ExperimentSetup::<f64, SimpleHarmonicOscillator<f64>>::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.max_steps,
+            Some(5),
+            "Expected the same stopping criterion"
+        );
+        assert_eq!(
+            loaded.max_control_rate, None,
+            "Expected no control rate limit"
+        );
+
+        // Builds the simulator from the setup alone, and replays a fixed control trace with it.
+        let run = |setup: &ExperimentSetup<f64, SimpleHarmonicOscillator<f64>>| {
+            let simulator =
+                || SHOSimulator::new(&setup.system).with_dynamics(&setup.dynamics, setup.seed);
+            let observations = smol::block_on(simulator().get_observations())
+                .into_iter()
+                .map(|observation| observation.positions)
+                .collect::<Vec<_>>();
+
+            let recorder = TraceRecorder::default();
+            let trace = recorder.0.clone();
+            let controls = (0..10).map(|i| SHOControlSignal {
+                control: Vector::new([(i as f64).sin(), (i as f64 * 0.3).cos()]),
+            });
+            let result = smol::block_on(replay(
+                &setup.system,
+                simulator().with_recorder(recorder),
+                setup,
+                controls,
+            ));
+            let states = (trace.lock().unwrap().0.iter())
+                .map(|state| (state.time, state.position, state.velocity))
+                .collect::<Vec<_>>();
+            (result, states, observations)
+        };
+
+        let (result, states, observations) = run(&loaded);
+        let (expected_result, expected_states, expected_observations) = run(&setup);
+        assert_eq!(
+            result.steps, 5,
+            "Expected the stopping criterion of the setup"
+        );
+        assert_eq!(
+            result.time, expected_result.time,
+            "Expected the timestep of the setup"
+        );
+        assert_eq!(
+            result.dynamics_loss, expected_result.dynamics_loss,
+            "Expected the reloaded setup to reproduce the run"
+        );
+        assert_eq!(
+            states, expected_states,
+            "Expected the reloaded setup to reproduce the trajectory"
+        );
+        assert_eq!(
+            observations, expected_observations,
+            "Expected the reloaded seed to reproduce the observation noise"
+        );
     }
 }
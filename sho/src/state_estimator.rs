@@ -1,10 +1,10 @@
 use std::marker::PhantomData;
 
 use common::{
-    interfaces::StatePredictionInterface, python::{JaxArray, JaxKey, PythonExt}, system::{DynamicVector, System}, vector::Vector, Float
+    interfaces::StatePredictionInterface, normalizer::RunningNormalizer, python::{BoundGetAttrExt, JaxArray, JaxKey, PythonExt, PythonModelSource, JAX, NUMPY}, system::{DynamicVector, ObservationExt, System}, vector::Vector, Float
 };
 use pyo3::{
-    types::{IntoPyDict, PyAnyMethods, PyModule},
+    types::{IntoPyDict, PyAnyMethods},
     Bound, Py, PyAny, PyResult, Python, ToPyObject,
 };
 use smol::lock::Mutex;
@@ -15,55 +15,96 @@ use crate::system::{SHOLatentState, SHOSystemObservation, SimpleHarmonicOscillat
 pub struct SHOStatePredictor<T: Float> {
     /// The object associated with the agent.
     agent: Mutex<Py<PyAny>>,
+    /// The model class the agent was initialized from, to reinitialize it on reset.
+    class: Py<PyAny>,
+    /// The key the agent was initialized with, to reinitialize it on reset.
+    key: JaxKey,
+    /// The running statistics used to standardize the observations before the model.
+    normalizer: RunningNormalizer<T>,
     /// [`PhantomData`] to support the generic type.
     _phantom: PhantomData<T>,
 }
 
 impl<T: Float> SHOStatePredictor<T> {
-    /// Creates an instance of [`SHOStatePredictor`].
+    /// Creates an instance of [`SHOStatePredictor`] from the embedded "sho_state_predictor.py"
+    /// script.
     pub fn new(key: JaxKey, system: &SimpleHarmonicOscillator<T>) -> Self {
+        Self::from_source(key, system, &PythonModelSource::Embedded, "SHOPredictor").unwrap()
+    }
+
+    /// Creates an instance of [`SHOStatePredictor`] from the model class called `class_name` in
+    /// the given source, e.g. an externally trained model. The class must expose the same
+    /// `init_state` and `step` methods as the `SHOPredictor` class of the embedded script.
+    pub fn from_source(
+        key: JaxKey,
+        _system: &SimpleHarmonicOscillator<T>,
+        source: &PythonModelSource,
+        class_name: &str,
+    ) -> PyResult<Self> {
         /// The code in the "sho_state_predictor.py" script.
         const CODE: &str = include_str!("sho_state_predictor.py");
 
-        let agent = Python::with_gil_ext(|py| -> PyResult<Py<PyAny>> {
-            let module = PyModule::from_code_bound(
-                py,
-                CODE,
-                "sho_state_predictor.py",
-                "sho_state_predictor",
-            )?;
-
-            let agent = module
-                .getattr("SHOPredictor")?
-                .getattr("init_state")?
-                .call(
-                    (),
-                    Some(
-                        &[
-                            ("key", key.to_object(py)),
-                            ("delay_depth", DELAY_DEPTH.to_object(py)),
-                            (
-                                "observation_dimension",
-                                SimpleHarmonicOscillator::<T>::OBSERVABLE_STATE_SIZE.to_object(py),
-                            ),
-                            (
-                                "latent_dimension",
-                                SimpleHarmonicOscillator::<T>::LATENT_STATE_SIZE.to_object(py),
-                            ),
-                        ]
-                        .into_py_dict_bound(py),
-                    ),
-                )?;
+        let (class, agent) = Python::with_gil_ext(|py| -> PyResult<_> {
+            let class = source.load_class(py, (CODE, "sho_state_predictor.py"), class_name)?;
+            let agent = Self::init_agent(&class, &key, py)?;
 
-            Ok(agent.unbind())
-        })
-        .unwrap()
-        .into();
+            Ok((class.unbind(), agent))
+        })?;
 
-        Self {
-            agent,
+        Ok(Self {
+            agent: agent.into(),
+            class,
+            key,
+            normalizer: RunningNormalizer::default(),
             _phantom: PhantomData,
-        }
+        })
+    }
+
+    /// Initializes the agent from the model class with the given key.
+    fn init_agent(class: &Bound<PyAny>, key: &JaxKey, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let agent = class.getattr("init_state")?.call(
+            (),
+            Some(
+                &[
+                    ("key", key.to_object(py)),
+                    ("delay_depth", DELAY_DEPTH.to_object(py)),
+                    (
+                        "observation_dimension",
+                        SimpleHarmonicOscillator::<T>::OBSERVABLE_STATE_SIZE.to_object(py),
+                    ),
+                    (
+                        "latent_dimension",
+                        SimpleHarmonicOscillator::<T>::LATENT_STATE_SIZE.to_object(py),
+                    ),
+                ]
+                .into_py_dict_bound(py),
+            ),
+        )?;
+
+        Ok(agent.unbind())
+    }
+
+    /// Flattens the leaves of the agent's state into a single vector, e.g. to inspect how the
+    /// hidden state evolves while debugging. Blocks until no prediction is in progress.
+    pub fn hidden_state_summary(&self) -> PyResult<Vec<f32>> {
+        let agent = self.agent.lock_blocking();
+        Python::with_gil_ext(|py| {
+            let leaves = JAX
+                .bind(py)
+                .getattr_split("tree_util.tree_leaves")?
+                .call1((agent.bind(py),))?;
+
+            let mut summary = Vec::new();
+            for leaf in leaves.iter()? {
+                let values = NUMPY
+                    .bind(py)
+                    .call_method1("ravel", (leaf?,))?
+                    .call_method0("tolist")?;
+                summary.extend(values.extract::<Vec<f32>>()?);
+            }
+
+            Ok(summary)
+        })
     }
 }
 
@@ -74,6 +115,14 @@ impl<T: Float + std::fmt::Debug> StatePredictionInterface<T, SimpleHarmonicOscil
         &mut self,
         observation: &[SHOSystemObservation<T>],
     ) -> SHOLatentState<T> {
+        if let Some(latest) = observation.last() {
+            self.normalizer.update(latest.get_rope());
+        }
+        let mut observation = observation.to_vec();
+        for i in observation.iter_mut() {
+            self.normalizer.normalize(&mut i.get_rope_mut());
+        }
+
         let mut agent_lock = self.agent.lock().await;
         let array = Python::with_gil_ext(|py| -> PyResult<JaxArray> {
             let data: JaxArray = JaxArray::new_1d(
@@ -100,16 +149,16 @@ impl<T: Float + std::fmt::Debug> StatePredictionInterface<T, SimpleHarmonicOscil
         .await
         .into_inner();
 
-        let latent_representation = Vector::new(Python::with_gil_ext(|py| {
-            array
-                .bind(py)
-                .extract::<[T; 12]>()
-                .unwrap()
-        }));
+        let time = observation.last().unwrap().time();
+        Python::with_gil_ext(|py| SHOLatentState::try_from_py(&array, py, time)).unwrap()
+    }
 
-        SHOLatentState {
-            time: observation.last().unwrap().time,
-            latent_representation,
+    /// Reinitializes the agent from its model class with the original key. If the model fails to
+    /// reinitialize, the error is logged and the current agent is kept.
+    fn reset(&mut self) {
+        match Python::with_gil_ext(|py| Self::init_agent(self.class.bind(py), &self.key, py)) {
+            Ok(agent) => *self.agent.get_mut() = agent,
+            Err(err) => log::error!("Failed to reinitialize the state predictor: {err}"),
         }
     }
 }
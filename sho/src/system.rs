@@ -1,9 +1,10 @@
 use common::{
     rope::{Rope, RopeMut},
-    system::{DynamicVector, System},
+    system::{DynamicVector, ObservationExt, PhysicsSystem, System},
     vector::Vector,
     Float,
 };
+use pyo3::{types::PyAnyMethods, Py, PyAny, PyResult, Python};
 
 /// The number of previous observations and controls to use to Markovianize the process with the
 /// state estimator.
@@ -16,12 +17,53 @@ pub const DELAY_DEPTH: usize = 3;
 ///
 /// where $\vec{x}$ is the current position of the oscillator, $k$ is the stiffness, and $\vec{F}$
 /// is the driving force.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleHarmonicOscillator<T: Float> {
     /// The stiffness of the Harmonic Oscillator.
     pub stiffness: T,
     /// The reward decay speed.
-    pub gamma: T,
+    pub gamma: GammaSchedule<T>,
+}
+
+/// The schedule of the reward decay speed over the steps of the driver agent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GammaSchedule<T: Float> {
+    /// A constant reward decay speed.
+    Constant(T),
+    /// A reward decay speed linearly interpolated from `start` to `end` over the first `steps`
+    /// steps, and held at `end` afterwards.
+    Linear {
+        /// The reward decay speed at the first step.
+        start: T,
+        /// The reward decay speed from step `steps` onwards.
+        end: T,
+        /// The number of steps to interpolate over.
+        steps: usize,
+    },
+}
+
+impl<T: Float> GammaSchedule<T> {
+    /// Gets the reward decay speed at the given step.
+    pub fn gamma(&self, step: usize) -> T {
+        match *self {
+            GammaSchedule::Constant(gamma) => gamma,
+            GammaSchedule::Linear { end, steps: 0, .. } => end,
+            GammaSchedule::Linear { start, end, steps } => {
+                let fraction = T::from(step.min(steps)).unwrap() / T::from(steps).unwrap();
+                start + (end - start) * fraction
+            }
+        }
+    }
+}
+
+/// The configuration of the dynamics of the [`SimpleHarmonicOscillator`] simulation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SHODynamicsConfiguration<T: Float> {
+    /// The standard deviation of the Gaussian noise added to the observed positions, if any.
+    pub observation_noise: Option<T>,
 }
 
 impl<T: Float> System<T> for SimpleHarmonicOscillator<T> {
@@ -31,9 +73,10 @@ impl<T: Float> System<T> for SimpleHarmonicOscillator<T> {
     const SYSTEM_STATE_SIZE: usize = 4;
     const OBSERVABLE_STATE_SIZE: usize = 4;
 
-    type SystemConfiguration = ();
+    // The oscillator is fully described by its parameters.
+    type SystemConfiguration = Self;
 
-    type DynamicsConfiguration = ();
+    type DynamicsConfiguration = SHODynamicsConfiguration<T>;
 
     type SystemState = SHOSystemState<T>;
     type LatentState = SHOLatentState<T>;
@@ -42,6 +85,44 @@ impl<T: Float> System<T> for SimpleHarmonicOscillator<T> {
     type SystemObservation = SHOSystemObservation<T>;
 }
 
+impl<T: Float> PhysicsSystem<T> for SimpleHarmonicOscillator<T> {
+    fn time(state: &SHOSystemState<T>) -> T {
+        state.time
+    }
+
+    fn set_time(state: &mut SHOSystemState<T>, time: T) {
+        state.time = time;
+    }
+
+    fn position(state: &SHOSystemState<T>) -> &[T] {
+        state.position.as_ref()
+    }
+
+    fn velocity(state: &SHOSystemState<T>) -> &[T] {
+        state.velocity.as_ref()
+    }
+
+    fn phase_space_mut(state: &mut SHOSystemState<T>) -> (&mut [T], &mut [T]) {
+        (state.position.as_mut(), state.velocity.as_mut())
+    }
+
+    fn acceleration(
+        &self,
+        state: &SHOSystemState<T>,
+        control_signal: &SHOControlSignal<T>,
+        acceleration: &mut [T],
+    ) {
+        let acc = -state.position * self.stiffness + control_signal.control;
+        acceleration.copy_from_slice(acc.as_ref());
+    }
+
+    fn energy(&self, state: &SHOSystemState<T>) -> T {
+        let kinetic = state.velocity.dot(state.velocity);
+        let potential = self.stiffness * state.position.dot(state.position);
+        (kinetic + potential) / (T::one() + T::one())
+    }
+}
+
 /// The system state for the [`SimpleHarmonicOscillator`].
 #[derive(Debug, Clone, Copy)]
 pub struct SHOSystemState<T: Float> {
@@ -87,7 +168,58 @@ pub struct SHOSystemObservation<T: Float> {
     pub(crate) controls: SHOControlSignal<T>,
 }
 
+impl<T: Float> SHOLatentState<T> {
+    /// Converts the output of the state estimator model, an array of the 12 components of the
+    /// latent representation, into the latent state at the given time.
+    pub fn try_from_py(obj: &Py<PyAny>, py: Python<'_>, time: T) -> PyResult<Self> {
+        Ok(Self {
+            time,
+            latent_representation: Vector::new(obj.bind(py).extract::<[T; 12]>()?),
+        })
+    }
+}
+
+impl<T: Float> SHOControlParams<T> {
+    /// Converts the output of the driver agent, a scalar array holding the parametrized control
+    /// signal, into the control parameters.
+    pub fn try_from_py(obj: &Py<PyAny>, py: Python<'_>) -> PyResult<Self> {
+        Ok(Self {
+            control: obj.bind(py).call_method0("item")?.extract::<T>()?,
+        })
+    }
+}
+
+/// The zero control parameter, i.e. a force at zero angle.
+impl<T: Float> Default for SHOControlParams<T> {
+    fn default() -> Self {
+        Self { control: T::zero() }
+    }
+}
+
+/// The zero control signal, i.e. no driving force.
+impl<T: Float> Default for SHOControlSignal<T> {
+    fn default() -> Self {
+        Self {
+            control: Vector::zero(),
+        }
+    }
+}
+
+impl<T: Float> SHOSystemObservation<T> {
+    /// An observation of the oscillator at rest at the given position and time, without any
+    /// control signal applied. Useful as a setpoint to track.
+    pub fn setpoint(time: T, position: Vector<T, 2>) -> Self {
+        Self {
+            time,
+            positions: position,
+            controls: SHOControlSignal::default(),
+        }
+    }
+}
+
 impl<T: Float> DynamicVector<T> for SHOSystemState<T> {
+    const LEN: Option<usize> = Some(SimpleHarmonicOscillator::<T>::SYSTEM_STATE_SIZE);
+
     fn get_rope(&self) -> Rope<T> {
         Rope::new(&[self.position.as_array(), self.velocity.as_array()])
     }
@@ -98,6 +230,8 @@ impl<T: Float> DynamicVector<T> for SHOSystemState<T> {
 }
 
 impl<T: Float> DynamicVector<T> for SHOLatentState<T> {
+    const LEN: Option<usize> = Some(SimpleHarmonicOscillator::<T>::LATENT_STATE_SIZE);
+
     fn get_rope(&self) -> Rope<T> {
         Rope::new(&[self.latent_representation.as_array()])
     }
@@ -108,6 +242,8 @@ impl<T: Float> DynamicVector<T> for SHOLatentState<T> {
 }
 
 impl<T: Float> DynamicVector<T> for SHOControlParams<T> {
+    const LEN: Option<usize> = Some(SimpleHarmonicOscillator::<T>::CONTROL_PARAMS_SIZE);
+
     fn get_rope(&self) -> Rope<T> {
         Rope::new(&[std::slice::from_ref(&self.control)])
     }
@@ -118,6 +254,8 @@ impl<T: Float> DynamicVector<T> for SHOControlParams<T> {
 }
 
 impl<T: Float> DynamicVector<T> for SHOControlSignal<T> {
+    const LEN: Option<usize> = Some(SimpleHarmonicOscillator::<T>::CONTROL_SIGNAL_SIZE);
+
     fn get_rope(&self) -> Rope<T> {
         Rope::new(&[self.control.as_array()])
     }
@@ -127,7 +265,15 @@ impl<T: Float> DynamicVector<T> for SHOControlSignal<T> {
     }
 }
 
+impl<T: Float> ObservationExt<T> for SHOSystemObservation<T> {
+    fn time(&self) -> T {
+        self.time
+    }
+}
+
 impl<T: Float> DynamicVector<T> for SHOSystemObservation<T> {
+    const LEN: Option<usize> = Some(SimpleHarmonicOscillator::<T>::OBSERVABLE_STATE_SIZE);
+
     fn get_rope(&self) -> Rope<T> {
         self.positions.get_rope().merge(self.controls.get_rope())
     }
@@ -138,3 +284,183 @@ impl<T: Float> DynamicVector<T> for SHOSystemObservation<T> {
             .merge(self.controls.get_rope_mut())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        system::{
+            assert_system_sizes_consistent, DynamicVector, ObservationExt, System,
+            SystemObservation,
+        },
+        vector::Vector,
+        Float,
+    };
+    use pyo3::{
+        types::{PyAnyMethods, PyModule},
+        Py, PyAny, Python,
+    };
+
+    use super::{
+        GammaSchedule, SHOControlParams, SHOControlSignal, SHOLatentState, SHOSystemObservation,
+        SHOSystemState, SimpleHarmonicOscillator,
+    };
+
+    /// A stand-in for a JAX array, supporting the sequence protocol and `item`.
+    const STUB_ARRAY: &str = r#"
+class StubArray(list):
+    def item(self):
+        (value,) = self
+        return value
+"#;
+
+    /// Builds a [`STUB_ARRAY`] holding the given values.
+    fn stub_array(py: Python<'_>, values: &[f64]) -> Py<PyAny> {
+        PyModule::from_code_bound(py, STUB_ARRAY, "stub_array.py", "stub_array")
+            .unwrap()
+            .getattr("StubArray")
+            .unwrap()
+            .call1((values.to_vec(),))
+            .unwrap()
+            .unbind()
+    }
+
+    #[test]
+    fn test_try_from_py() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let values = (0..12).map(f64::from).collect::<Vec<_>>();
+            let latent = SHOLatentState::try_from_py(&stub_array(py, &values), py, 1.5).unwrap();
+            assert_eq!(latent.time, 1.5);
+            assert_eq!(latent.latent_representation.iter().as_slice(), values);
+
+            let params = SHOControlParams::<f64>::try_from_py(&stub_array(py, &[0.25]), py);
+            assert_eq!(params.unwrap().control, 0.25);
+
+            assert!(
+                SHOLatentState::try_from_py(&stub_array(py, &values[..3]), py, 0.).is_err(),
+                "Expected a latent representation of the wrong size to be rejected"
+            );
+            assert!(
+                SHOControlParams::<f64>::try_from_py(&stub_array(py, &values), py).is_err(),
+                "Expected a non-scalar control to be rejected"
+            );
+        });
+    }
+
+    #[test]
+    fn test_constant_gamma_schedule() {
+        let schedule = GammaSchedule::Constant(0.9f64);
+
+        assert_eq!(schedule.gamma(0), 0.9);
+        assert_eq!(schedule.gamma(1000), 0.9);
+    }
+
+    #[test]
+    fn test_linear_gamma_schedule() {
+        let schedule = GammaSchedule::Linear {
+            start: 0.5f64,
+            end: 1.0,
+            steps: 10,
+        };
+
+        assert_eq!(schedule.gamma(0), 0.5);
+        assert_eq!(schedule.gamma(2), 0.6);
+        assert_eq!(schedule.gamma(5), 0.75);
+        assert_eq!(schedule.gamma(10), 1.0);
+        assert_eq!(schedule.gamma(20), 1.0);
+    }
+
+    #[test]
+    fn test_dynamic_vector_len() {
+        /// Checks that the const length of a vector matches the length of its rope.
+        fn check<V: DynamicVector<f64>>(v: V) {
+            assert_eq!(
+                V::LEN,
+                Some(v.get_rope().len()),
+                "Expected the const length of {} to match its rope",
+                std::any::type_name::<V>()
+            );
+        }
+
+        let controls = SHOControlSignal {
+            control: Vector::zero(),
+        };
+        check(SHOSystemState {
+            time: 0.,
+            position: Vector::zero(),
+            velocity: Vector::zero(),
+        });
+        check(SHOLatentState {
+            time: 0.,
+            latent_representation: Vector::zero(),
+        });
+        check(SHOControlParams { control: 0. });
+        check(controls);
+        check(SHOSystemObservation {
+            time: 0.,
+            positions: Vector::zero(),
+            controls,
+        });
+    }
+
+    #[test]
+    fn test_system_sizes_consistent() {
+        let system = SimpleHarmonicOscillator {
+            stiffness: 1.,
+            gamma: GammaSchedule::Constant(0.9),
+        };
+        let controls = SHOControlSignal {
+            control: Vector::zero(),
+        };
+        assert_system_sizes_consistent::<f64, SimpleHarmonicOscillator<f64>>(
+            &system,
+            &SHOSystemState {
+                time: 0.,
+                position: Vector::zero(),
+                velocity: Vector::zero(),
+            },
+            &SHOLatentState {
+                time: 0.,
+                latent_representation: Vector::zero(),
+            },
+            &SHOControlParams { control: 0. },
+            &controls,
+            &SHOSystemObservation::setpoint(0., Vector::zero()),
+        );
+    }
+
+    #[test]
+    fn test_control_defaults_zero() {
+        let params = SHOControlParams::<f64>::default();
+        let signal = SHOControlSignal::<f64>::default();
+
+        assert_eq!(params.control, 0., "Expected a zero control parameter");
+        assert_eq!(
+            signal.control,
+            Vector::zero(),
+            "Expected a zero control signal"
+        );
+    }
+
+    /// Gets the time of the latest observation of any system.
+    fn latest_time<T: Float, S: System<T>>(observations: &[SystemObservation<T, S>]) -> T {
+        observations.last().unwrap().time()
+    }
+
+    #[test]
+    fn test_observation_time() {
+        let observations = [0.5f64, 1.5].map(|time| SHOSystemObservation {
+            time,
+            positions: Vector::zero(),
+            controls: SHOControlSignal {
+                control: Vector::zero(),
+            },
+        });
+
+        assert_eq!(observations[0].time(), 0.5);
+        assert_eq!(
+            latest_time::<_, SimpleHarmonicOscillator<f64>>(&observations),
+            1.5
+        );
+    }
+}
@@ -8,8 +8,9 @@ from csv import reader
 
 import numpy as np
 
+# The records interleave `state, time, *position, *velocity` and `control, time, *control` rows.
 with open('records.csv', 'r') as f:
-    data = list(map(lambda x: list(map(float, x)), (reader(f))))
+    data = [list(map(float, row[2:4])) for row in reader(f) if row[0] == 'state']
 
 data = np.array(data)
 fig = plt.figure(figsize=(10, 10))